@@ -1,42 +1,40 @@
 use std::{collections::HashMap, path::Path};
 
 use crate::types::TestUnit;
+use ethereum_rust_consensus::validate_block;
 use ethereum_rust_core::{
     rlp::decode::RLPDecode,
     rlp::encode::RLPEncode,
-    types::{Account as CoreAccount, Block as CoreBlock},
+    types::{
+        Account as CoreAccount, Block as CoreBlock, BlockHeader as CoreBlockHeader, ChainConfig,
+    },
 };
-use ethereum_rust_evm::{evm_state, execute_block, EvmState, SpecId};
+use ethereum_rust_evm::{evm_state, execute_and_validate_block, spec_id, EvmState};
 use ethereum_rust_storage::{EngineType, Store};
 
-/// Tests the execute_block function
+/// Tests the execute_and_validate_block function
 pub fn execute_test(test_key: &str, test: &TestUnit) {
     // Build pre state
-    let mut evm_state = build_evm_state_for_test(test);
+    let store = build_evm_state_for_test(test).database().clone();
     let blocks = test.blocks.clone();
 
     // Check world_state
-    check_prestate_against_db(test_key, test, evm_state.database());
+    check_prestate_against_db(test_key, test, &store);
 
     // Execute all blocks in test
+    let chain_config = chain_config_for_network(&test.network);
+    let mut parent_header: CoreBlockHeader = test.genesis_block_header.clone().into();
     for block_fixture in blocks.iter() {
         let block: &CoreBlock = &block_fixture.block().clone().into();
+        let spec = spec_id(&chain_config, block.header.timestamp);
 
-        let spec = match &*test.network {
-            "Shanghai" => SpecId::SHANGHAI,
-            "Cancun" => SpecId::CANCUN,
-            "Paris" => SpecId::MERGE,
-            "ShanghaiToCancunAtTime15k" => {
-                if block.header.timestamp >= 15_000 {
-                    SpecId::CANCUN
-                } else {
-                    SpecId::SHANGHAI
-                }
-            }
-            _ => panic!("Unsupported network: {}", test.network),
+        // Blocks are validated against their parent before being executed
+        // Each block runs on a fresh state so the changes of rejected blocks are not kept
+        let execution_result = match validate_block(block, &parent_header, &chain_config) {
+            Ok(()) => execute_and_validate_block(block, &mut evm_state(store.clone()), spec)
+                .map_err(|error| error.to_string()),
+            Err(error) => Err(error.to_string()),
         };
-
-        let execution_result = execute_block(block, &mut evm_state, spec);
         if block_fixture.expect_exception.is_some() {
             assert!(
                 execution_result.is_err(),
@@ -49,10 +47,27 @@ pub fn execute_test(test_key: &str, test: &TestUnit) {
                 "Transaction execution failed on test: {} with error: {}",
                 test_key,
                 execution_result.unwrap_err()
-            )
+            );
+            parent_header = block.header.clone();
         }
     }
-    check_poststate_against_db(test_key, test, evm_state.database())
+    check_poststate_against_db(test_key, test, &store)
+}
+
+/// Returns the fork configuration of the network a test runs on
+fn chain_config_for_network(network: &str) -> ChainConfig {
+    let (shanghai_time, cancun_time) = match network {
+        "Paris" => (None, None),
+        "Shanghai" => (Some(0), None),
+        "Cancun" => (Some(0), Some(0)),
+        "ShanghaiToCancunAtTime15k" => (Some(0), Some(15_000)),
+        _ => panic!("Unsupported network: {}", network),
+    };
+    ChainConfig {
+        shanghai_time,
+        cancun_time,
+        ..Default::default()
+    }
 }
 
 pub fn parse_test_file(path: &Path) -> HashMap<String, TestUnit> {
@@ -38,7 +38,7 @@ datatest_stable::harness!(
     parse_and_execute,
     "vectors/cancun/",
     r"eip6780_selfdestruct/.*/.*\.json",
-    //parse_and_execute,
-    //"vectors/cancun/",
-    //r"eip4844_blobs/.*/.*\.json",
+    parse_and_execute,
+    "vectors/cancun/",
+    r"eip4844_blobs/.*/.*\.json",
 );
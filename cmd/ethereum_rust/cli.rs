@@ -19,6 +19,20 @@ pub fn cli() -> Command {
                 .value_name("PORT")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("ws.addr")
+                .long("ws.addr")
+                .default_value("localhost")
+                .value_name("ADDRESS")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("ws.port")
+                .long("ws.port")
+                .default_value("8546")
+                .value_name("PORT")
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("authrpc.addr")
                 .long("authrpc.addr")
@@ -33,6 +47,37 @@ pub fn cli() -> Command {
                 .value_name("PORT")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("authrpc.jwtsecret")
+                .long("authrpc.jwtsecret")
+                .default_value("jwt.hex")
+                .value_name("JWTSECRET_PATH")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("rpc.logs.maxblockrange")
+                .long("rpc.logs.maxblockrange")
+                .default_value("10000")
+                .value_name("BLOCKS")
+                .value_parser(clap::value_parser!(u64))
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("rpc.logs.maxresults")
+                .long("rpc.logs.maxresults")
+                .default_value("10000")
+                .value_name("LOGS")
+                .value_parser(clap::value_parser!(usize))
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("rpc.filters.timeout")
+                .long("rpc.filters.timeout")
+                .default_value("300")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64))
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("p2p.addr")
                 .long("p2p.addr")
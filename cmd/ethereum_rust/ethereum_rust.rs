@@ -1,12 +1,15 @@
+use bytes::Bytes;
 use ethereum_rust_core::types::Genesis;
-use ethereum_rust_net::bootnode::BootNode;
+use ethereum_rust_net::{bootnode::BootNode, node::NetworkHandle};
+use ethereum_rust_rpc::{decode_jwt_secret, LogsLimits};
 use ethereum_rust_storage::{EngineType, Store};
 use std::{
     io::{self, BufReader},
     net::{SocketAddr, ToSocketAddrs},
+    time::Duration,
 };
 use tokio::try_join;
-use tracing::{warn, Level};
+use tracing::{info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 mod cli;
 
@@ -26,12 +29,35 @@ async fn main() {
     let http_port = matches
         .get_one::<String>("http.port")
         .expect("http.port is required");
+    let ws_addr = matches
+        .get_one::<String>("ws.addr")
+        .expect("ws.addr is required");
+    let ws_port = matches
+        .get_one::<String>("ws.port")
+        .expect("ws.port is required");
     let authrpc_addr = matches
         .get_one::<String>("authrpc.addr")
         .expect("authrpc.addr is required");
     let authrpc_port = matches
         .get_one::<String>("authrpc.port")
         .expect("authrpc.port is required");
+    let authrpc_jwtsecret = matches
+        .get_one::<String>("authrpc.jwtsecret")
+        .expect("authrpc.jwtsecret is required");
+
+    let logs_limits = LogsLimits {
+        max_block_range: *matches
+            .get_one::<u64>("rpc.logs.maxblockrange")
+            .expect("rpc.logs.maxblockrange is required"),
+        max_results: *matches
+            .get_one::<usize>("rpc.logs.maxresults")
+            .expect("rpc.logs.maxresults is required"),
+    };
+    let filter_timeout = Duration::from_secs(
+        *matches
+            .get_one::<u64>("rpc.filters.timeout")
+            .expect("rpc.filters.timeout is required"),
+    );
 
     let tcp_addr = matches
         .get_one::<String>("p2p.addr")
@@ -62,6 +88,8 @@ async fn main() {
 
     let http_socket_addr =
         parse_socket_addr(http_addr, http_port).expect("Failed to parse http address and port");
+    let ws_socket_addr =
+        parse_socket_addr(ws_addr, ws_port).expect("Failed to parse ws address and port");
     let authrpc_socket_addr = parse_socket_addr(authrpc_addr, authrpc_port)
         .expect("Failed to parse authrpc address and port");
 
@@ -76,8 +104,19 @@ async fn main() {
         .add_initial_state(genesis)
         .expect("Failed to create genesis block");
 
-    let rpc_api = ethereum_rust_rpc::start_api(http_socket_addr, authrpc_socket_addr, store);
-    let networking = ethereum_rust_net::start_network(udp_socket_addr, tcp_socket_addr, bootnodes);
+    let jwt_secret = read_jwtsecret_file(authrpc_jwtsecret);
+    let network = NetworkHandle::new(tcp_socket_addr, udp_socket_addr);
+    let rpc_api = ethereum_rust_rpc::start_api(
+        http_socket_addr,
+        ws_socket_addr,
+        authrpc_socket_addr,
+        store,
+        jwt_secret,
+        logs_limits,
+        filter_timeout,
+        network.clone(),
+    );
+    let networking = ethereum_rust_net::start_network(network, bootnodes);
 
     try_join!(tokio::spawn(rpc_api), tokio::spawn(networking)).unwrap();
 }
@@ -88,6 +127,25 @@ fn read_genesis_file(genesis_file_path: &str) -> Genesis {
     serde_json::from_reader(genesis_reader).expect("Failed to read genesis file")
 }
 
+/// Reads the hex encoded secret used to authenticate Auth-RPC requests
+/// If the file doesn't exist a new random secret is generated and written to it
+/// Panics if the stored secret is not 32 bytes long
+fn read_jwtsecret_file(jwtsecret_file_path: &str) -> Bytes {
+    match std::fs::read_to_string(jwtsecret_file_path) {
+        Ok(contents) => {
+            decode_jwt_secret(&contents).expect("Jwt secret must be a hex encoded 32 byte value")
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            info!("Jwt secret file not found, generating a new secret at {jwtsecret_file_path}");
+            let secret: [u8; 32] = rand::random();
+            std::fs::write(jwtsecret_file_path, hex::encode(secret))
+                .expect("Failed to write jwt secret file");
+            Bytes::copy_from_slice(&secret)
+        }
+        Err(err) => panic!("Failed to read jwt secret file: {err}"),
+    }
+}
+
 fn parse_socket_addr(addr: &str, port: &str) -> io::Result<SocketAddr> {
     // NOTE: this blocks until hostname can be resolved
     format!("{addr}:{port}")
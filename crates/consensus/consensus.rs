@@ -1,14 +1,499 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+use ethereum_rust_core::types::{
+    calc_excess_blob_gas, calculate_base_fee_per_blob_gas, calculate_base_fee_per_gas,
+    compute_transactions_root, compute_withdrawals_root, Block, BlockBody, BlockHeader,
+    ChainConfig, Transaction, DEFAULT_OMMERS_HASH, GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK,
+};
+
+mod error;
+pub mod mempool;
+pub use error::{InvalidBlockError, MempoolError};
+
+/// Validates the block's header against its parent and its body against the header
+pub fn validate_block(
+    block: &Block,
+    parent_header: &BlockHeader,
+    chain_config: &ChainConfig,
+) -> Result<(), InvalidBlockError> {
+    validate_block_header(&block.header, parent_header, chain_config)?;
+    validate_block_body(&block.header, &block.body, chain_config)
+}
+
+/// Validates the header of a block against the header of its parent
+pub fn validate_block_header(
+    header: &BlockHeader,
+    parent_header: &BlockHeader,
+    chain_config: &ChainConfig,
+) -> Result<(), InvalidBlockError> {
+    if header.gas_used > header.gas_limit {
+        return Err(InvalidBlockError::GasUsedExceedsLimit {
+            gas_used: header.gas_used,
+            gas_limit: header.gas_limit,
+        });
+    }
+    let expected_base_fee_per_gas = calculate_base_fee_per_gas(
+        header.gas_limit,
+        parent_header.gas_limit,
+        parent_header.gas_used,
+        parent_header.base_fee_per_gas,
+    )
+    .ok_or(InvalidBlockError::GasLimitOutOfBounds {
+        gas_limit: header.gas_limit,
+        parent_gas_limit: parent_header.gas_limit,
+    })?;
+    if header.base_fee_per_gas != expected_base_fee_per_gas {
+        return Err(InvalidBlockError::BaseFeeMismatch {
+            header: header.base_fee_per_gas,
+            expected: expected_base_fee_per_gas,
+        });
+    }
+    if header.timestamp <= parent_header.timestamp {
+        return Err(InvalidBlockError::TimestampNotIncreasing {
+            timestamp: header.timestamp,
+            parent_timestamp: parent_header.timestamp,
+        });
+    }
+    if header.number != parent_header.number + 1 {
+        return Err(InvalidBlockError::NumberNotIncreasing {
+            number: header.number,
+            parent_number: parent_header.number,
+        });
+    }
+    if header.extra_data.len() > 32 {
+        return Err(InvalidBlockError::ExtraDataTooLong(header.extra_data.len()));
+    }
+    if !header.difficulty.is_zero() {
+        return Err(InvalidBlockError::NonZeroDifficulty(header.difficulty));
+    }
+    if header.nonce != 0 {
+        return Err(InvalidBlockError::NonZeroNonce(header.nonce));
+    }
+    if header.ommers_hash != *DEFAULT_OMMERS_HASH {
+        return Err(InvalidBlockError::BadOmmersHash(header.ommers_hash));
+    }
+    let parent_hash = parent_header.compute_block_hash();
+    if header.parent_hash != parent_hash {
+        return Err(InvalidBlockError::ParentHashMismatch {
+            header: header.parent_hash,
+            parent: parent_hash,
+        });
+    }
+    validate_cancun_header_fields(header, parent_header, chain_config)
+}
+
+// Checks the blob gas and beacon root fields introduced in Cancun
+// These fields must only be present once Cancun is active
+fn validate_cancun_header_fields(
+    header: &BlockHeader,
+    parent_header: &BlockHeader,
+    chain_config: &ChainConfig,
+) -> Result<(), InvalidBlockError> {
+    if !chain_config.is_cancun_activated(header.timestamp) {
+        if header.blob_gas_used.is_some() || header.excess_blob_gas.is_some() {
+            return Err(InvalidBlockError::UnexpectedBlobGasFields);
+        }
+        if header.parent_beacon_block_root.is_some() {
+            return Err(InvalidBlockError::UnexpectedParentBeaconBlockRoot);
+        }
+        return Ok(());
+    }
+    let (Some(blob_gas_used), Some(excess_blob_gas)) =
+        (header.blob_gas_used, header.excess_blob_gas)
+    else {
+        return Err(InvalidBlockError::MissingBlobGasFields);
+    };
+    if header.parent_beacon_block_root.is_none() {
+        return Err(InvalidBlockError::MissingParentBeaconBlockRoot);
+    }
+    // The parent's blob fields are not set if it is the last block before the fork
+    let expected_excess_blob_gas = calc_excess_blob_gas(
+        parent_header.excess_blob_gas.unwrap_or_default(),
+        parent_header.blob_gas_used.unwrap_or_default(),
+    );
+    if excess_blob_gas != expected_excess_blob_gas {
+        return Err(InvalidBlockError::ExcessBlobGasMismatch {
+            header: excess_blob_gas,
+            expected: expected_excess_blob_gas,
+        });
+    }
+    if blob_gas_used > MAX_BLOB_GAS_PER_BLOCK {
+        return Err(InvalidBlockError::BlobGasUsedExceedsMax(blob_gas_used));
+    }
+    if blob_gas_used % GAS_PER_BLOB != 0 {
+        return Err(InvalidBlockError::BlobGasUsedNotMultipleOfBlobGas(
+            blob_gas_used,
+        ));
+    }
+    Ok(())
+}
+
+/// Validates the body of a block against the roots and blob gas fields of its header
+pub fn validate_block_body(
+    header: &BlockHeader,
+    body: &BlockBody,
+    chain_config: &ChainConfig,
+) -> Result<(), InvalidBlockError> {
+    let transactions_root = compute_transactions_root(&body.transactions);
+    if header.transactions_root != transactions_root {
+        return Err(InvalidBlockError::TransactionsRootMismatch {
+            header: header.transactions_root,
+            body: transactions_root,
+        });
+    }
+    // Withdrawals must be present if and only if Shanghai is active
+    let withdrawals_root = body
+        .withdrawals
+        .as_ref()
+        .map(|withdrawals| compute_withdrawals_root(withdrawals));
+    if header.withdrawals_root != withdrawals_root
+        || withdrawals_root.is_some() != chain_config.is_shanghai_activated(header.timestamp)
+    {
+        return Err(InvalidBlockError::WithdrawalsRootMismatch {
+            header: header.withdrawals_root,
+            body: withdrawals_root,
+        });
+    }
+    validate_blob_transactions(header, body)
+}
+
+// Checks that the blob gas used matches the blobs in the block's transactions and that
+// every blob transaction has at least one blob and can pay the blob base fee
+fn validate_blob_transactions(
+    header: &BlockHeader,
+    body: &BlockBody,
+) -> Result<(), InvalidBlockError> {
+    let Some(excess_blob_gas) = header.excess_blob_gas else {
+        // Blob transactions are not allowed before Cancun
+        if body
+            .transactions
+            .iter()
+            .any(|tx| matches!(tx, Transaction::EIP4844Transaction(_)))
+        {
+            return Err(InvalidBlockError::UnexpectedBlobTransaction);
+        }
+        return Ok(());
+    };
+    let blob_base_fee = calculate_base_fee_per_blob_gas(excess_blob_gas);
+    let mut blob_gas_used: u64 = 0;
+    for tx in &body.transactions {
+        let Transaction::EIP4844Transaction(blob_tx) = tx else {
+            continue;
+        };
+        if blob_tx.blob_versioned_hashes.is_empty() {
+            return Err(InvalidBlockError::BlobTransactionWithoutBlobs(
+                tx.compute_hash(),
+            ));
+        }
+        if blob_tx.max_fee_per_blob_gas < blob_base_fee {
+            return Err(InvalidBlockError::InsufficientMaxFeePerBlobGas {
+                tx_hash: tx.compute_hash(),
+                max_fee: blob_tx.max_fee_per_blob_gas,
+                base_fee: blob_base_fee,
+            });
+        }
+        blob_gas_used =
+            blob_gas_used.saturating_add(GAS_PER_BLOB * blob_tx.blob_versioned_hashes.len() as u64);
+    }
+    if header.blob_gas_used != Some(blob_gas_used) {
+        return Err(InvalidBlockError::BlobGasUsedMismatch {
+            header: header.blob_gas_used.unwrap_or_default(),
+            transactions: blob_gas_used,
+        });
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
+    use ethereum_rust_core::{
+        types::{EIP4844Transaction, Withdrawal, TARGET_BLOB_GAS_PER_BLOCK},
+        Address, Bloom, Bytes, H256, U256,
+    };
+
     use super::*;
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn test_validate_block_header() {
+        let parent_block = BlockHeader {
+            parent_hash: H256::from_str(
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+            )
+            .unwrap(),
+            ommers_hash: H256::from_str(
+                "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+            )
+            .unwrap(),
+            coinbase: Address::zero(),
+            state_root: H256::from_str(
+                "0x590245a249decc317041b8dc7141cec0559c533efb82221e4e0a30a6456acf8b",
+            )
+            .unwrap(),
+            transactions_root: H256::from_str(
+                "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            )
+            .unwrap(),
+            receipt_root: H256::from_str(
+                "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            )
+            .unwrap(),
+            logs_bloom: Bloom::from([0; 256]),
+            difficulty: U256::zero(),
+            number: 0,
+            gas_limit: 0x016345785d8a0000,
+            gas_used: 0,
+            timestamp: 0,
+            extra_data: Bytes::new(),
+            prev_randao: H256::zero(),
+            nonce: 0x0000000000000000,
+            base_fee_per_gas: 0x07,
+            withdrawals_root: Some(
+                H256::from_str(
+                    "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                )
+                .unwrap(),
+            ),
+            blob_gas_used: Some(0x00),
+            excess_blob_gas: Some(0x00),
+            parent_beacon_block_root: Some(H256::zero()),
+        };
+        let block = BlockHeader {
+            parent_hash: H256::from_str(
+                "0x1ac1bf1eef97dc6b03daba5af3b89881b7ae4bc1600dc434f450a9ec34d44999",
+            )
+            .unwrap(),
+            ommers_hash: H256::from_str(
+                "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+            )
+            .unwrap(),
+            coinbase: Address::from_str("0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba").unwrap(),
+            state_root: H256::from_str(
+                "0x9de6f95cb4ff4ef22a73705d6ba38c4b927c7bca9887ef5d24a734bb863218d9",
+            )
+            .unwrap(),
+            transactions_root: H256::from_str(
+                "0x578602b2b7e3a3291c3eefca3a08bc13c0d194f9845a39b6f3bcf843d9fed79d",
+            )
+            .unwrap(),
+            receipt_root: H256::from_str(
+                "0x035d56bac3f47246c5eed0e6642ca40dc262f9144b582f058bc23ded72aa72fa",
+            )
+            .unwrap(),
+            logs_bloom: Bloom::from([0; 256]),
+            difficulty: U256::zero(),
+            number: 1,
+            gas_limit: 0x016345785d8a0000,
+            gas_used: 0xa8de,
+            timestamp: 0x03e8,
+            extra_data: Bytes::new(),
+            prev_randao: H256::zero(),
+            nonce: 0x0000000000000000,
+            base_fee_per_gas: 0x07,
+            withdrawals_root: Some(
+                H256::from_str(
+                    "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+                )
+                .unwrap(),
+            ),
+            blob_gas_used: Some(0x00),
+            excess_blob_gas: Some(0x00),
+            parent_beacon_block_root: Some(H256::zero()),
+        };
+        let chain_config = ChainConfig {
+            shanghai_time: Some(0),
+            cancun_time: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_block_header(&block, &parent_block, &chain_config),
+            Ok(())
+        );
+        let wrong_base_fee = BlockHeader {
+            base_fee_per_gas: 8,
+            ..block.clone()
+        };
+        assert_eq!(
+            validate_block_header(&wrong_base_fee, &parent_block, &chain_config),
+            Err(InvalidBlockError::BaseFeeMismatch {
+                header: 8,
+                expected: 7
+            })
+        );
+        let wrong_parent_hash = BlockHeader {
+            parent_hash: H256::zero(),
+            ..block.clone()
+        };
+        assert_eq!(
+            validate_block_header(&wrong_parent_hash, &parent_block, &chain_config),
+            Err(InvalidBlockError::ParentHashMismatch {
+                header: H256::zero(),
+                parent: block.parent_hash
+            })
+        );
+        // Cancun fields are required once the fork is active
+        let without_beacon_root = BlockHeader {
+            parent_beacon_block_root: None,
+            ..block.clone()
+        };
+        assert_eq!(
+            validate_block_header(&without_beacon_root, &parent_block, &chain_config),
+            Err(InvalidBlockError::MissingParentBeaconBlockRoot)
+        );
+        let wrong_excess_blob_gas = BlockHeader {
+            excess_blob_gas: Some(GAS_PER_BLOB),
+            ..block.clone()
+        };
+        assert_eq!(
+            validate_block_header(&wrong_excess_blob_gas, &parent_block, &chain_config),
+            Err(InvalidBlockError::ExcessBlobGasMismatch {
+                header: GAS_PER_BLOB,
+                expected: 0
+            })
+        );
+        let too_much_blob_gas = BlockHeader {
+            blob_gas_used: Some(MAX_BLOB_GAS_PER_BLOCK + GAS_PER_BLOB),
+            ..block.clone()
+        };
+        assert_eq!(
+            validate_block_header(&too_much_blob_gas, &parent_block, &chain_config),
+            Err(InvalidBlockError::BlobGasUsedExceedsMax(
+                MAX_BLOB_GAS_PER_BLOCK + GAS_PER_BLOB
+            ))
+        );
+        // And not allowed before it
+        let shanghai_config = ChainConfig {
+            cancun_time: None,
+            ..chain_config
+        };
+        assert_eq!(
+            validate_block_header(&block, &parent_block, &shanghai_config),
+            Err(InvalidBlockError::UnexpectedBlobGasFields)
+        );
+    }
+
+    #[test]
+    fn test_validate_block_body() {
+        let chain_config = ChainConfig {
+            shanghai_time: Some(0),
+            ..Default::default()
+        };
+        let body = BlockBody {
+            transactions: Vec::new(),
+            ommers: Vec::new(),
+            withdrawals: Some(vec![Withdrawal {
+                index: 0,
+                validator_index: 1,
+                address: Address::zero(),
+                amount: 100,
+            }]),
+        };
+        let header = BlockHeader {
+            transactions_root: compute_transactions_root(&[]),
+            withdrawals_root: Some(compute_withdrawals_root(body.withdrawals.as_ref().unwrap())),
+            ..Default::default()
+        };
+        assert_eq!(validate_block_body(&header, &body, &chain_config), Ok(()));
+        let wrong_withdrawals_root = BlockHeader {
+            withdrawals_root: Some(H256::zero()),
+            ..header.clone()
+        };
+        assert_eq!(
+            validate_block_body(&wrong_withdrawals_root, &body, &chain_config),
+            Err(InvalidBlockError::WithdrawalsRootMismatch {
+                header: Some(H256::zero()),
+                body: header.withdrawals_root
+            })
+        );
+        // Withdrawals are not allowed before Shanghai
+        assert!(matches!(
+            validate_block_body(&header, &body, &ChainConfig::default()),
+            Err(InvalidBlockError::WithdrawalsRootMismatch { .. })
+        ));
+        let wrong_transactions_root = BlockHeader {
+            transactions_root: H256::zero(),
+            ..header
+        };
+        assert!(matches!(
+            validate_block_body(&wrong_transactions_root, &body, &chain_config),
+            Err(InvalidBlockError::TransactionsRootMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_blob_transactions() {
+        let blob_tx = |blob_count: usize, max_fee_per_blob_gas: u64| {
+            Transaction::EIP4844Transaction(EIP4844Transaction {
+                chain_id: 1,
+                nonce: 0,
+                max_priority_fee_per_gas: 0,
+                max_fee_per_gas: 0,
+                gas: 21000,
+                to: Address::zero(),
+                value: U256::zero(),
+                data: Bytes::new(),
+                access_list: Vec::new(),
+                max_fee_per_blob_gas: U256::from(max_fee_per_blob_gas),
+                blob_versioned_hashes: vec![H256::zero(); blob_count],
+                signature_y_parity: false,
+                signature_r: U256::zero(),
+                signature_s: U256::zero(),
+            })
+        };
+        let header = |blob_gas_used: u64, excess_blob_gas: u64| BlockHeader {
+            blob_gas_used: Some(blob_gas_used),
+            excess_blob_gas: Some(excess_blob_gas),
+            ..Default::default()
+        };
+        let body = |transactions| BlockBody {
+            transactions,
+            ommers: Vec::new(),
+            withdrawals: Some(Vec::new()),
+        };
+        assert_eq!(
+            validate_blob_transactions(
+                &header(3 * GAS_PER_BLOB, 0),
+                &body(vec![blob_tx(1, 1), blob_tx(2, 1)])
+            ),
+            Ok(())
+        );
+        // Blob gas used doesn't match the amount of blobs
+        assert_eq!(
+            validate_blob_transactions(
+                &header(2 * GAS_PER_BLOB, 0),
+                &body(vec![blob_tx(1, 1), blob_tx(2, 1)])
+            ),
+            Err(InvalidBlockError::BlobGasUsedMismatch {
+                header: 2 * GAS_PER_BLOB,
+                transactions: 3 * GAS_PER_BLOB
+            })
+        );
+        // Blob transaction without blobs
+        let empty_blob_tx = blob_tx(0, 1);
+        assert_eq!(
+            validate_blob_transactions(&header(0, 0), &body(vec![empty_blob_tx.clone()])),
+            Err(InvalidBlockError::BlobTransactionWithoutBlobs(
+                empty_blob_tx.compute_hash()
+            ))
+        );
+        // Max fee per blob gas is below the blob base fee
+        let excess_blob_gas = 10 * TARGET_BLOB_GAS_PER_BLOCK;
+        assert_eq!(
+            calculate_base_fee_per_blob_gas(excess_blob_gas),
+            U256::from(3)
+        );
+        assert!(matches!(
+            validate_blob_transactions(
+                &header(GAS_PER_BLOB, excess_blob_gas),
+                &body(vec![blob_tx(1, 2)])
+            ),
+            Err(InvalidBlockError::InsufficientMaxFeePerBlobGas { .. })
+        ));
+        assert_eq!(
+            validate_blob_transactions(
+                &header(GAS_PER_BLOB, excess_blob_gas),
+                &body(vec![blob_tx(1, 3)])
+            ),
+            Ok(())
+        );
     }
 }
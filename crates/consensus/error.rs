@@ -0,0 +1,105 @@
+use ethereum_rust_core::{H256, U256};
+use ethereum_rust_storage::error::StoreError;
+use thiserror::Error;
+
+/// Reasons a block can be rejected before being executed
+#[derive(Debug, Error, PartialEq)]
+pub enum InvalidBlockError {
+    #[error("Gas used {gas_used} exceeds gas limit {gas_limit}")]
+    GasUsedExceedsLimit { gas_used: u64, gas_limit: u64 },
+    #[error(
+        "Gas limit {gas_limit} is out of the bounds set by the parent gas limit {parent_gas_limit}"
+    )]
+    GasLimitOutOfBounds {
+        gas_limit: u64,
+        parent_gas_limit: u64,
+    },
+    #[error("Base fee mismatch: header {header}, expected {expected}")]
+    BaseFeeMismatch { header: u64, expected: u64 },
+    #[error("Timestamp {timestamp} is not greater than parent timestamp {parent_timestamp}")]
+    TimestampNotIncreasing {
+        timestamp: u64,
+        parent_timestamp: u64,
+    },
+    #[error("Block number {number} doesn't follow parent block number {parent_number}")]
+    NumberNotIncreasing { number: u64, parent_number: u64 },
+    #[error("Extra data is {0} bytes long, maximum is 32")]
+    ExtraDataTooLong(usize),
+    #[error("Difficulty must be zero, got {0}")]
+    NonZeroDifficulty(U256),
+    #[error("Nonce must be zero, got {0}")]
+    NonZeroNonce(u64),
+    #[error("Ommers hash {0:#x} doesn't match the hash of an empty ommers list")]
+    BadOmmersHash(H256),
+    #[error("Parent hash mismatch: header {header:#x}, parent {parent:#x}")]
+    ParentHashMismatch { header: H256, parent: H256 },
+    #[error("Blob gas fields must not be present before Cancun")]
+    UnexpectedBlobGasFields,
+    #[error("Blob gas fields are missing")]
+    MissingBlobGasFields,
+    #[error("Parent beacon block root must not be present before Cancun")]
+    UnexpectedParentBeaconBlockRoot,
+    #[error("Parent beacon block root is missing")]
+    MissingParentBeaconBlockRoot,
+    #[error("Excess blob gas mismatch: header {header}, expected {expected}")]
+    ExcessBlobGasMismatch { header: u64, expected: u64 },
+    #[error("Blob gas used {0} exceeds the maximum blob gas per block")]
+    BlobGasUsedExceedsMax(u64),
+    #[error("Blob gas used {0} is not a multiple of the gas per blob")]
+    BlobGasUsedNotMultipleOfBlobGas(u64),
+    #[error("Blob gas used mismatch: header {header}, transactions {transactions}")]
+    BlobGasUsedMismatch { header: u64, transactions: u64 },
+    #[error("Blob transaction {0:#x} has no blobs")]
+    BlobTransactionWithoutBlobs(H256),
+    #[error("Blob transaction {tx_hash:#x} max fee per blob gas {max_fee} is lower than the blob base fee {base_fee}")]
+    InsufficientMaxFeePerBlobGas {
+        tx_hash: H256,
+        max_fee: U256,
+        base_fee: U256,
+    },
+    #[error("Blob transactions are not allowed before Cancun")]
+    UnexpectedBlobTransaction,
+    #[error("Transactions root mismatch: header {header:#x}, body {body:#x}")]
+    TransactionsRootMismatch { header: H256, body: H256 },
+    #[error("Withdrawals root mismatch: header {header:?}, body {body:?}")]
+    WithdrawalsRootMismatch {
+        header: Option<H256>,
+        body: Option<H256>,
+    },
+}
+
+/// Reasons a transaction can be rejected by the mempool
+#[derive(Debug, Error)]
+pub enum MempoolError {
+    #[error("Store error: {0}")]
+    Store(#[from] StoreError),
+    #[error("Chain config not found")]
+    MissingChainConfig,
+    #[error("Latest block not found")]
+    MissingLatestBlock,
+    #[error("Invalid chain id: transaction {transaction}, expected {expected}")]
+    ChainIdMismatch { transaction: u64, expected: U256 },
+    #[error("Blob transactions are not supported")]
+    BlobTransactionNotSupported,
+    #[error("Initcode size {0} exceeds the maximum initcode size")]
+    InitcodeTooLarge(usize),
+    #[error("Intrinsic gas too low: gas limit {gas_limit}, intrinsic gas {intrinsic_gas}")]
+    IntrinsicGasTooLow { gas_limit: u64, intrinsic_gas: u64 },
+    #[error("Gas limit {gas_limit} exceeds the block gas limit {block_gas_limit}")]
+    GasLimitExceedsBlockLimit {
+        gas_limit: u64,
+        block_gas_limit: u64,
+    },
+    #[error("Max priority fee {max_priority_fee} is higher than max fee {max_fee}")]
+    TipAboveFeeCap { max_priority_fee: u64, max_fee: u64 },
+    #[error("Max fee {max_fee} is lower than the base fee {base_fee}")]
+    FeeCapBelowBaseFee { max_fee: u64, base_fee: u64 },
+    #[error("Nonce too low: transaction {nonce}, account {account_nonce}")]
+    NonceTooLow { nonce: u64, account_nonce: u64 },
+    #[error("Insufficient funds: balance {balance}, cost {cost}")]
+    InsufficientFunds { balance: U256, cost: U256 },
+    #[error("Replacement transaction underpriced")]
+    ReplacementUnderpriced,
+    #[error("Transaction pool is full")]
+    PoolFull,
+}
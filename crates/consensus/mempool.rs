@@ -0,0 +1,231 @@
+use ethereum_rust_core::{
+    types::{Transaction, TxKind},
+    H256, U256,
+};
+use ethereum_rust_storage::Store;
+
+use crate::MempoolError;
+
+// Intrinsic gas costs
+const TX_GAS: u64 = 21_000;
+const TX_CREATE_GAS: u64 = 53_000;
+const TX_DATA_ZERO_GAS: u64 = 4;
+const TX_DATA_NON_ZERO_GAS: u64 = 16;
+const TX_ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+const TX_ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+const TX_INIT_CODE_WORD_GAS: u64 = 2;
+
+/// Maximum size of the initcode of a contract creation transaction (EIP-3860)
+const MAX_INITCODE_SIZE: usize = 49_152;
+
+/// Minimum fee increase (in percent) for a transaction to replace the one with the same sender and nonce
+const PRICE_BUMP: u64 = 10;
+
+/// Validates a transaction against the latest state and adds it to the mempool
+/// Returns the hash of the transaction
+pub fn add_transaction(transaction: Transaction, storage: &Store) -> Result<H256, MempoolError> {
+    let chain_config = storage
+        .get_chain_config()?
+        .ok_or(MempoolError::MissingChainConfig)?;
+    let header = storage
+        .get_latest_block_number()?
+        .map(|number| storage.get_block_header(number))
+        .transpose()?
+        .flatten()
+        .ok_or(MempoolError::MissingLatestBlock)?;
+
+    // Blob transactions need their sidecars, which we don't keep
+    if matches!(transaction, Transaction::EIP4844Transaction(_)) {
+        return Err(MempoolError::BlobTransactionNotSupported);
+    }
+    if let Some(chain_id) = transaction.chain_id() {
+        if U256::from(chain_id) != chain_config.chain_id {
+            return Err(MempoolError::ChainIdMismatch {
+                transaction: chain_id,
+                expected: chain_config.chain_id,
+            });
+        }
+    }
+
+    // Gas checks
+    let shanghai_activated = chain_config.is_shanghai_activated(header.timestamp);
+    if shanghai_activated
+        && transaction.to() == TxKind::Create
+        && transaction.data().len() > MAX_INITCODE_SIZE
+    {
+        return Err(MempoolError::InitcodeTooLarge(transaction.data().len()));
+    }
+    let intrinsic_gas = intrinsic_gas(&transaction, shanghai_activated);
+    if transaction.gas_limit() < intrinsic_gas {
+        return Err(MempoolError::IntrinsicGasTooLow {
+            gas_limit: transaction.gas_limit(),
+            intrinsic_gas,
+        });
+    }
+    if transaction.gas_limit() > header.gas_limit {
+        return Err(MempoolError::GasLimitExceedsBlockLimit {
+            gas_limit: transaction.gas_limit(),
+            block_gas_limit: header.gas_limit,
+        });
+    }
+
+    // Fee checks, gas_price is the max fee for EIP-1559 transactions
+    let max_fee = transaction.gas_price();
+    let max_priority_fee = transaction.max_priority_fee().unwrap_or(max_fee);
+    if max_priority_fee > max_fee {
+        return Err(MempoolError::TipAboveFeeCap {
+            max_priority_fee,
+            max_fee,
+        });
+    }
+    if max_fee < header.base_fee_per_gas {
+        return Err(MempoolError::FeeCapBelowBaseFee {
+            max_fee,
+            base_fee: header.base_fee_per_gas,
+        });
+    }
+
+    // Sender account checks
+    let sender = transaction.sender();
+    let (account_nonce, balance) = storage
+        .get_account_info(sender)?
+        .map(|info| (info.nonce, info.balance))
+        .unwrap_or_default();
+    if transaction.nonce() < account_nonce {
+        return Err(MempoolError::NonceTooLow {
+            nonce: transaction.nonce(),
+            account_nonce,
+        });
+    }
+    let cost = U256::from(transaction.gas_limit()) * U256::from(max_fee) + transaction.value();
+    if balance < cost {
+        return Err(MempoolError::InsufficientFunds { balance, cost });
+    }
+    if let Some(replaced) = storage.get_transaction_from_pool_by_nonce(sender, transaction.nonce())
+    {
+        validate_replacement(&transaction, &replaced)?;
+    }
+
+    let hash = transaction.compute_hash();
+    if !storage.add_transaction_to_pool(hash, sender, transaction, account_nonce) {
+        return Err(MempoolError::PoolFull);
+    }
+    Ok(hash)
+}
+
+/// Returns the gas charged to a transaction before its execution starts
+fn intrinsic_gas(transaction: &Transaction, shanghai_activated: bool) -> u64 {
+    let data = transaction.data();
+    let is_create = transaction.to() == TxKind::Create;
+    let mut gas = if is_create { TX_CREATE_GAS } else { TX_GAS };
+    let zero_bytes = data.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zero_bytes = data.len() as u64 - zero_bytes;
+    gas += zero_bytes * TX_DATA_ZERO_GAS + non_zero_bytes * TX_DATA_NON_ZERO_GAS;
+    for (_, storage_keys) in transaction.access_list() {
+        gas +=
+            TX_ACCESS_LIST_ADDRESS_GAS + storage_keys.len() as u64 * TX_ACCESS_LIST_STORAGE_KEY_GAS;
+    }
+    // EIP-3860: initcode is charged per word
+    if is_create && shanghai_activated {
+        gas += (data.len() as u64).div_ceil(32) * TX_INIT_CODE_WORD_GAS;
+    }
+    gas
+}
+
+/// A transaction can only replace another one if both its max fee and max priority fee are bumped
+fn validate_replacement(
+    transaction: &Transaction,
+    replaced: &Transaction,
+) -> Result<(), MempoolError> {
+    let bumped = |fee: u64| fee.saturating_mul(100 + PRICE_BUMP) / 100;
+    let max_priority_fee = |tx: &Transaction| tx.max_priority_fee().unwrap_or(tx.gas_price());
+    if transaction.gas_price() < bumped(replaced.gas_price())
+        || max_priority_fee(transaction) < bumped(max_priority_fee(replaced))
+    {
+        return Err(MempoolError::ReplacementUnderpriced);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_rust_core::{
+        types::{EIP1559Transaction, LegacyTransaction},
+        Address, Bytes,
+    };
+
+    use super::*;
+
+    fn eip1559_transaction(
+        to: TxKind,
+        data: Vec<u8>,
+        access_list: Vec<(Address, Vec<H256>)>,
+        max_fee_per_gas: u64,
+        max_priority_fee_per_gas: u64,
+    ) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            chain_id: 1,
+            nonce: 0,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: 100_000,
+            to,
+            value: U256::zero(),
+            data: Bytes::from(data),
+            access_list,
+            signature_y_parity: false,
+            signature_r: U256::zero(),
+            signature_s: U256::zero(),
+        })
+    }
+
+    #[test]
+    fn test_intrinsic_gas() {
+        let transfer = Transaction::LegacyTransaction(LegacyTransaction {
+            nonce: 0,
+            gas_price: 1,
+            gas: 21_000,
+            to: TxKind::Call(Address::repeat_byte(0xaa)),
+            value: U256::zero(),
+            data: Bytes::from(vec![0, 0, 1]),
+            v: U256::zero(),
+            r: U256::zero(),
+            s: U256::zero(),
+        });
+        assert_eq!(intrinsic_gas(&transfer, true), 21_000 + 2 * 4 + 16);
+
+        let create = eip1559_transaction(
+            TxKind::Create,
+            vec![1; 33],
+            vec![(Address::repeat_byte(0xbb), vec![H256::zero(); 2])],
+            1,
+            1,
+        );
+        let before_shanghai = 53_000 + 33 * 16 + 2_400 + 2 * 1_900;
+        assert_eq!(intrinsic_gas(&create, false), before_shanghai);
+        assert_eq!(intrinsic_gas(&create, true), before_shanghai + 2 * 2);
+    }
+
+    #[test]
+    fn test_validate_replacement() {
+        let transaction = |max_fee_per_gas, max_priority_fee_per_gas| {
+            eip1559_transaction(
+                TxKind::Create,
+                Vec::new(),
+                Vec::new(),
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            )
+        };
+        let replaced = transaction(100, 10);
+        assert!(validate_replacement(&transaction(110, 11), &replaced).is_ok());
+        assert!(matches!(
+            validate_replacement(&transaction(200, 10), &replaced),
+            Err(MempoolError::ReplacementUnderpriced)
+        ));
+        assert!(matches!(
+            validate_replacement(&transaction(109, 20), &replaced),
+            Err(MempoolError::ReplacementUnderpriced)
+        ));
+    }
+}
@@ -450,9 +450,13 @@ pub fn is_encoded_as_bytes(rlp: &[u8]) -> bool {
     (0xb8..=0xbf).contains(&prefix)
 }
 
-/// Receives an RLP bytes item (prefix between 0xb8 and 0xbf) and returns its payload
+/// Receives an RLP bytes item (prefix between 0x80 and 0xbf) and returns its payload
 pub fn get_rlp_bytes_item_payload(rlp: &[u8]) -> &[u8] {
-    let prefix = rlp.first().unwrap();
+    let prefix = *rlp.first().unwrap();
+    // Short items have their length encoded in the prefix itself
+    if prefix < 0xb8 {
+        return &rlp[1..];
+    }
     let offset: usize = (prefix - 0xb8 + 1).into();
     &rlp[offset + 1..]
 }
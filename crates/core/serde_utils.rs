@@ -3,6 +3,7 @@ use serde::{de::Error, Deserialize, Deserializer, Serializer};
 pub mod u256 {
     use super::*;
     use ethereum_types::U256;
+    use serde::ser::Error as _;
     use serde_json::Number;
 
     pub fn deser_number<'de, D>(d: D) -> Result<U256, D::Error>
@@ -30,6 +31,26 @@ pub mod u256 {
         let value = String::deserialize(d)?;
         U256::from_dec_str(&value).map_err(|e| D::Error::custom(e.to_string()))
     }
+
+    pub fn serialize_number<S>(value: &U256, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let value: u128 = (*value)
+            .try_into()
+            .map_err(|_| S::Error::custom("Number too large to be serialized"))?;
+        serializer.serialize_u128(value)
+    }
+
+    pub fn serialize_number_opt<S>(value: &Option<U256>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serialize_number(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
 }
 
 pub mod u64 {
@@ -78,6 +99,27 @@ pub mod u64 {
         }
     }
 
+    /// Serializes to and deserializes from a 0x prefixed hex string padded to 8 bytes
+    pub mod hex_str_padding_opt {
+        use serde::Serialize;
+
+        use super::*;
+
+        pub fn serialize<S>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            Option::<String>::serialize(&value.map(|v| format!("{:#018x}", v)), serializer)
+        }
+
+        pub fn deserialize<'de, D>(d: D) -> Result<Option<u64>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            hex_str_opt::deserialize(d)
+        }
+    }
+
     pub fn deser_dec_str<'de, D>(d: D) -> Result<u64, D::Error>
     where
         D: Deserializer<'de>,
@@ -129,6 +171,36 @@ pub mod bytes {
             }
             Ok(output)
         }
+
+        pub fn serialize<S>(value: &[Bytes], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_seq(value.iter().map(|bytes| format!("0x{:x}", bytes)))
+        }
+    }
+
+    pub mod opt {
+        use serde::Serialize;
+
+        use super::*;
+
+        pub fn deserialize<'de, D>(d: D) -> Result<Option<Bytes>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            super::deserialize(d).map(Some)
+        }
+
+        pub fn serialize<S>(value: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            Option::<String>::serialize(
+                &value.as_ref().map(|bytes| format!("0x{:x}", bytes)),
+                serializer,
+            )
+        }
     }
 }
 
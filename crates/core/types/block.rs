@@ -1,6 +1,7 @@
 use super::{
-    ReceiptBlockInfo, BASE_FEE_MAX_CHANGE_DENOMINATOR, ELASTICITY_MULTIPLIER,
-    GAS_LIMIT_ADJUSTMENT_FACTOR, GAS_LIMIT_MINIMUM,
+    ReceiptBlockInfo, BASE_FEE_MAX_CHANGE_DENOMINATOR, BLOB_BASE_FEE_UPDATE_FRACTION,
+    ELASTICITY_MULTIPLIER, GAS_LIMIT_ADJUSTMENT_FACTOR, GAS_LIMIT_MINIMUM,
+    MIN_BASE_FEE_PER_BLOB_GAS, TARGET_BLOB_GAS_PER_BLOCK,
 };
 use crate::{
     rlp::{
@@ -32,7 +33,7 @@ use lazy_static::lazy_static;
 lazy_static! {
     pub static ref DEFAULT_OMMERS_HASH: H256 = H256::from_slice(&hex::decode("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347").unwrap()); // = Keccak256(RLP([])) as of EIP-3675
 }
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Block {
     pub header: BlockHeader,
     pub body: BlockBody,
@@ -337,7 +338,7 @@ fn check_gas_limit(gas_limit: u64, parent_gas_limit: u64) -> bool {
 
 // Calculates the base fee for the current block based on its gas_limit and parent's gas and fee
 // Returns None if the block gas limit is not valid in relation to its parent's gas limit
-fn calculate_base_fee_per_gas(
+pub fn calculate_base_fee_per_gas(
     block_gas_limit: u64,
     parent_gas_limit: u64,
     parent_gas_used: u64,
@@ -377,29 +378,33 @@ fn calculate_base_fee_per_gas(
     })
 }
 
-pub fn validate_block_header(header: &BlockHeader, parent_header: &BlockHeader) -> bool {
-    if header.gas_used > header.gas_limit {
-        return false;
+/// Calculates the excess blob gas for a block given its parent's excess blob gas and blob gas used
+/// See [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub fn calc_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_used: u64) -> u64 {
+    (parent_excess_blob_gas + parent_blob_gas_used).saturating_sub(TARGET_BLOB_GAS_PER_BLOCK)
+}
+
+/// Calculates the base fee per blob gas for a block with the given excess blob gas
+/// See [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub fn calculate_base_fee_per_blob_gas(excess_blob_gas: u64) -> U256 {
+    fake_exponential(
+        U256::from(MIN_BASE_FEE_PER_BLOB_GAS),
+        U256::from(excess_blob_gas),
+        U256::from(BLOB_BASE_FEE_UPDATE_FRACTION),
+    )
+}
+
+// Approximates factor * e ** (numerator / denominator) using Taylor expansion
+fn fake_exponential(factor: U256, numerator: U256, denominator: U256) -> U256 {
+    let mut i = U256::one();
+    let mut output = U256::zero();
+    let mut numerator_accum = factor * denominator;
+    while !numerator_accum.is_zero() {
+        output = output.saturating_add(numerator_accum);
+        numerator_accum = numerator_accum.saturating_mul(numerator) / (denominator * i);
+        i += U256::one();
     }
-    let expected_base_fee_per_gas = if let Some(base_fee) = calculate_base_fee_per_gas(
-        header.gas_limit,
-        parent_header.gas_limit,
-        parent_header.gas_used,
-        parent_header.base_fee_per_gas,
-    ) {
-        base_fee
-    } else {
-        return false;
-    };
-
-    expected_base_fee_per_gas == header.base_fee_per_gas
-        && header.timestamp > parent_header.timestamp
-        && header.number == parent_header.number + 1
-        && header.extra_data.len() <= 32
-        && header.difficulty.is_zero()
-        && header.nonce == 0
-        && header.ommers_hash == *DEFAULT_OMMERS_HASH
-        && header.parent_hash == parent_header.compute_block_hash()
+    output / denominator
 }
 
 #[allow(unused)]
@@ -489,95 +494,6 @@ mod test {
         assert_eq!(root, expected_root);
     }
 
-    #[test]
-    fn test_validate_block_header() {
-        let parent_block = BlockHeader {
-            parent_hash: H256::from_str(
-                "0x0000000000000000000000000000000000000000000000000000000000000000",
-            )
-            .unwrap(),
-            ommers_hash: H256::from_str(
-                "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
-            )
-            .unwrap(),
-            coinbase: Address::zero(),
-            state_root: H256::from_str(
-                "0x590245a249decc317041b8dc7141cec0559c533efb82221e4e0a30a6456acf8b",
-            )
-            .unwrap(),
-            transactions_root: H256::from_str(
-                "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
-            )
-            .unwrap(),
-            receipt_root: H256::from_str(
-                "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
-            )
-            .unwrap(),
-            logs_bloom: Bloom::from([0; 256]),
-            difficulty: U256::zero(),
-            number: 0,
-            gas_limit: 0x016345785d8a0000,
-            gas_used: 0,
-            timestamp: 0,
-            extra_data: Bytes::new(),
-            prev_randao: H256::zero(),
-            nonce: 0x0000000000000000,
-            base_fee_per_gas: 0x07,
-            withdrawals_root: Some(
-                H256::from_str(
-                    "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
-                )
-                .unwrap(),
-            ),
-            blob_gas_used: Some(0x00),
-            excess_blob_gas: Some(0x00),
-            parent_beacon_block_root: Some(H256::zero()),
-        };
-        let block = BlockHeader {
-            parent_hash: H256::from_str(
-                "0x1ac1bf1eef97dc6b03daba5af3b89881b7ae4bc1600dc434f450a9ec34d44999",
-            )
-            .unwrap(),
-            ommers_hash: H256::from_str(
-                "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
-            )
-            .unwrap(),
-            coinbase: Address::from_str("0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba").unwrap(),
-            state_root: H256::from_str(
-                "0x9de6f95cb4ff4ef22a73705d6ba38c4b927c7bca9887ef5d24a734bb863218d9",
-            )
-            .unwrap(),
-            transactions_root: H256::from_str(
-                "0x578602b2b7e3a3291c3eefca3a08bc13c0d194f9845a39b6f3bcf843d9fed79d",
-            )
-            .unwrap(),
-            receipt_root: H256::from_str(
-                "0x035d56bac3f47246c5eed0e6642ca40dc262f9144b582f058bc23ded72aa72fa",
-            )
-            .unwrap(),
-            logs_bloom: Bloom::from([0; 256]),
-            difficulty: U256::zero(),
-            number: 1,
-            gas_limit: 0x016345785d8a0000,
-            gas_used: 0xa8de,
-            timestamp: 0x03e8,
-            extra_data: Bytes::new(),
-            prev_randao: H256::zero(),
-            nonce: 0x0000000000000000,
-            base_fee_per_gas: 0x07,
-            withdrawals_root: Some(
-                H256::from_str(
-                    "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
-                )
-                .unwrap(),
-            ),
-            blob_gas_used: Some(0x00),
-            excess_blob_gas: Some(0x00),
-            parent_beacon_block_root: Some(H256::zero()),
-        };
-        assert!(validate_block_header(&block, &parent_block))
-    }
-
     #[test]
     fn serialize_block() {
         let block_header = BlockHeader {
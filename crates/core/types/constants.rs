@@ -5,3 +5,10 @@ pub const GAS_LIMIT_ADJUSTMENT_FACTOR: u64 = 1024;
 pub const GAS_LIMIT_MINIMUM: u64 = 5000;
 pub const GWEI_TO_WEI: u64 = 1_000_000_000;
 pub const INITIAL_BASE_FEE: u64 = 1_000_000_000; //Initial base fee as defined in [EIP-1559](https://eips.ethereum.org/EIPS/eip-1559)
+
+// Blob related, as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = 393_216;
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+pub const MAX_BLOB_GAS_PER_BLOCK: u64 = 786_432;
+pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1;
+pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3_338_477;
@@ -0,0 +1,64 @@
+use ethereum_types::Address;
+use keccak_hash::H256;
+use serde::{Deserialize, Serialize};
+
+use super::payload::PayloadStatus;
+use crate::types::Withdrawal;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkChoiceState {
+    pub head_block_hash: H256,
+    pub safe_block_hash: H256,
+    pub finalized_block_hash: H256,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadAttributesV1 {
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    pub timestamp: u64,
+    pub prev_randao: H256,
+    pub suggested_fee_recipient: Address,
+}
+
+/// Payload attributes received by `engine_forkchoiceUpdatedV2`
+/// Withdrawals are only present for payloads after the Shanghai fork, otherwise it has the shape of `PayloadAttributesV1`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadAttributesV2 {
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    pub timestamp: u64,
+    pub prev_randao: H256,
+    pub suggested_fee_recipient: Address,
+    #[serde(default)]
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadAttributesV3 {
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    pub timestamp: u64,
+    pub prev_randao: H256,
+    pub suggested_fee_recipient: Address,
+    pub withdrawals: Vec<Withdrawal>,
+    pub parent_beacon_block_root: H256,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkChoiceResponse {
+    pub payload_status: PayloadStatus,
+    #[serde(with = "crate::serde_utils::u64::hex_str_padding_opt")]
+    pub payload_id: Option<u64>,
+}
+
+impl From<PayloadStatus> for ForkChoiceResponse {
+    fn from(payload_status: PayloadStatus) -> Self {
+        ForkChoiceResponse {
+            payload_status,
+            payload_id: None,
+        }
+    }
+}
@@ -1,2 +1,4 @@
+mod fork_choice;
 mod payload;
+pub use fork_choice::*;
 pub use payload::*;
@@ -1,9 +1,8 @@
 use bytes::Bytes;
-use ethereum_types::{Address, Bloom};
+use ethereum_types::{Address, Bloom, U256};
 use keccak_hash::H256;
 use serde::{Deserialize, Serialize};
 
-use crate::rlp::decode::RLPDecode;
 use crate::{rlp::error::RLPDecodeError, serde_utils};
 
 use crate::types::{
@@ -12,7 +11,63 @@ use crate::types::{
 };
 
 #[allow(unused)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPayloadV1 {
+    parent_hash: H256,
+    fee_recipient: Address,
+    state_root: H256,
+    receipts_root: H256,
+    logs_bloom: Bloom,
+    prev_randao: H256,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    block_number: u64,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    gas_limit: u64,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    gas_used: u64,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    pub timestamp: u64,
+    #[serde(with = "crate::serde_utils::bytes")]
+    extra_data: Bytes,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    base_fee_per_gas: u64,
+    pub block_hash: H256,
+    transactions: Vec<EncodedTransaction>,
+}
+
+/// Payload received by `engine_newPayloadV2` and returned by `engine_getPayloadV2`
+/// Withdrawals are only present for payloads after the Shanghai fork, otherwise it has the shape of an `ExecutionPayloadV1`
+#[allow(unused)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPayloadV2 {
+    parent_hash: H256,
+    fee_recipient: Address,
+    state_root: H256,
+    receipts_root: H256,
+    logs_bloom: Bloom,
+    prev_randao: H256,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    block_number: u64,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    gas_limit: u64,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    gas_used: u64,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    pub timestamp: u64,
+    #[serde(with = "crate::serde_utils::bytes")]
+    extra_data: Bytes,
+    #[serde(with = "crate::serde_utils::u64::hex_str")]
+    base_fee_per_gas: u64,
+    pub block_hash: H256,
+    transactions: Vec<EncodedTransaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+#[allow(unused)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExecutionPayloadV3 {
     parent_hash: H256,
@@ -28,7 +83,7 @@ pub struct ExecutionPayloadV3 {
     #[serde(with = "crate::serde_utils::u64::hex_str")]
     gas_used: u64,
     #[serde(with = "crate::serde_utils::u64::hex_str")]
-    timestamp: u64,
+    pub timestamp: u64,
     #[serde(with = "crate::serde_utils::bytes")]
     extra_data: Bytes,
     #[serde(with = "crate::serde_utils::u64::hex_str")]
@@ -57,6 +112,15 @@ impl<'de> Deserialize<'de> for EncodedTransaction {
     }
 }
 
+impl Serialize for EncodedTransaction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serde_utils::bytes::serialize(&self.0, serializer)
+    }
+}
+
 #[allow(unused)]
 impl EncodedTransaction {
     /// Based on [EIP-2718]
@@ -64,7 +128,144 @@ impl EncodedTransaction {
     /// A) `TransactionType || Transaction` (Where Transaction type is an 8-bit number between 0 and 0x7f, and Transaction is an rlp encoded transaction of type TransactionType)
     /// B) `LegacyTransaction` (An rlp encoded LegacyTransaction)
     fn decode(&self) -> Result<Transaction, RLPDecodeError> {
-        Transaction::decode(self.0.as_ref())
+        Transaction::decode_canonical(self.0.as_ref())
+    }
+}
+
+#[allow(unused)]
+impl ExecutionPayloadV1 {
+    /// Converts an `ExecutionPayloadV1` into a block (aka a BlockHeader and BlockBody)
+    pub fn into_block(self) -> Result<Block, RLPDecodeError> {
+        let body = BlockBody {
+            transactions: self
+                .transactions
+                .iter()
+                .map(|encoded_tx| encoded_tx.decode())
+                .collect::<Result<Vec<_>, RLPDecodeError>>()?,
+            ommers: vec![],
+            withdrawals: None,
+        };
+        Ok(Block {
+            header: BlockHeader {
+                parent_hash: self.parent_hash,
+                ommers_hash: *DEFAULT_OMMERS_HASH,
+                coinbase: self.fee_recipient,
+                state_root: self.state_root,
+                transactions_root: compute_transactions_root(&body.transactions),
+                receipt_root: self.receipts_root,
+                logs_bloom: self.logs_bloom,
+                difficulty: 0.into(),
+                number: self.block_number,
+                gas_limit: self.gas_limit,
+                gas_used: self.gas_used,
+                timestamp: self.timestamp,
+                extra_data: self.extra_data,
+                prev_randao: self.prev_randao,
+                nonce: 0,
+                base_fee_per_gas: self.base_fee_per_gas,
+                withdrawals_root: None,
+                blob_gas_used: None,
+                excess_blob_gas: None,
+                parent_beacon_block_root: None,
+            },
+            body,
+        })
+    }
+
+    /// Converts a block into an `ExecutionPayloadV1`, dropping the fields that are not part of the payload
+    pub fn from_block(block: Block) -> Self {
+        ExecutionPayloadV1 {
+            parent_hash: block.header.parent_hash,
+            fee_recipient: block.header.coinbase,
+            state_root: block.header.state_root,
+            receipts_root: block.header.receipt_root,
+            logs_bloom: block.header.logs_bloom,
+            prev_randao: block.header.prev_randao,
+            block_number: block.header.number,
+            gas_limit: block.header.gas_limit,
+            gas_used: block.header.gas_used,
+            timestamp: block.header.timestamp,
+            extra_data: block.header.extra_data.clone(),
+            base_fee_per_gas: block.header.base_fee_per_gas,
+            block_hash: block.header.compute_block_hash(),
+            transactions: block
+                .body
+                .transactions
+                .iter()
+                .map(|tx| EncodedTransaction(tx.encode_canonical_to_vec().into()))
+                .collect(),
+        }
+    }
+}
+
+#[allow(unused)]
+impl ExecutionPayloadV2 {
+    /// Converts an `ExecutionPayloadV2` into a block (aka a BlockHeader and BlockBody)
+    /// The withdrawals root is only set if the payload contains withdrawals
+    pub fn into_block(self) -> Result<Block, RLPDecodeError> {
+        let body = BlockBody {
+            transactions: self
+                .transactions
+                .iter()
+                .map(|encoded_tx| encoded_tx.decode())
+                .collect::<Result<Vec<_>, RLPDecodeError>>()?,
+            ommers: vec![],
+            withdrawals: self.withdrawals,
+        };
+        Ok(Block {
+            header: BlockHeader {
+                parent_hash: self.parent_hash,
+                ommers_hash: *DEFAULT_OMMERS_HASH,
+                coinbase: self.fee_recipient,
+                state_root: self.state_root,
+                transactions_root: compute_transactions_root(&body.transactions),
+                receipt_root: self.receipts_root,
+                logs_bloom: self.logs_bloom,
+                difficulty: 0.into(),
+                number: self.block_number,
+                gas_limit: self.gas_limit,
+                gas_used: self.gas_used,
+                timestamp: self.timestamp,
+                extra_data: self.extra_data,
+                prev_randao: self.prev_randao,
+                nonce: 0,
+                base_fee_per_gas: self.base_fee_per_gas,
+                withdrawals_root: body
+                    .withdrawals
+                    .as_ref()
+                    .map(|withdrawals| compute_withdrawals_root(withdrawals)),
+                blob_gas_used: None,
+                excess_blob_gas: None,
+                parent_beacon_block_root: None,
+            },
+            body,
+        })
+    }
+
+    /// Converts a block into an `ExecutionPayloadV2`, dropping the fields that are not part of the payload
+    pub fn from_block(block: Block) -> Self {
+        ExecutionPayloadV2 {
+            parent_hash: block.header.parent_hash,
+            fee_recipient: block.header.coinbase,
+            state_root: block.header.state_root,
+            receipts_root: block.header.receipt_root,
+            logs_bloom: block.header.logs_bloom,
+            prev_randao: block.header.prev_randao,
+            block_number: block.header.number,
+            gas_limit: block.header.gas_limit,
+            gas_used: block.header.gas_used,
+            timestamp: block.header.timestamp,
+            extra_data: block.header.extra_data.clone(),
+            base_fee_per_gas: block.header.base_fee_per_gas,
+            block_hash: block.header.compute_block_hash(),
+            transactions: block
+                .body
+                .transactions
+                .iter()
+                .map(|tx| EncodedTransaction(tx.encode_canonical_to_vec().into()))
+                .collect(),
+            withdrawals: block.body.withdrawals,
+        }
     }
 }
 
@@ -110,6 +311,86 @@ impl ExecutionPayloadV3 {
             body,
         })
     }
+
+    /// Converts a block into an `ExecutionPayloadV3`, dropping the fields that are not part of the payload
+    pub fn from_block(block: Block) -> Self {
+        ExecutionPayloadV3 {
+            parent_hash: block.header.parent_hash,
+            fee_recipient: block.header.coinbase,
+            state_root: block.header.state_root,
+            receipts_root: block.header.receipt_root,
+            logs_bloom: block.header.logs_bloom,
+            prev_randao: block.header.prev_randao,
+            block_number: block.header.number,
+            gas_limit: block.header.gas_limit,
+            gas_used: block.header.gas_used,
+            timestamp: block.header.timestamp,
+            extra_data: block.header.extra_data.clone(),
+            base_fee_per_gas: block.header.base_fee_per_gas,
+            block_hash: block.header.compute_block_hash(),
+            transactions: block
+                .body
+                .transactions
+                .iter()
+                .map(|tx| EncodedTransaction(tx.encode_canonical_to_vec().into()))
+                .collect(),
+            withdrawals: block.body.withdrawals.unwrap_or_default(),
+            blob_gas_used: block.header.blob_gas_used.unwrap_or_default(),
+            excess_blob_gas: block.header.excess_blob_gas.unwrap_or_default(),
+        }
+    }
+}
+
+/// Response to `engine_getPayloadV2`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPayloadResponseV2 {
+    pub execution_payload: ExecutionPayloadV2,
+    pub block_value: U256,
+}
+
+/// Response to `engine_getPayloadV3`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPayloadResponseV3 {
+    pub execution_payload: ExecutionPayloadV3,
+    pub block_value: U256,
+    pub blobs_bundle: BlobsBundleV1,
+    pub should_override_builder: bool,
+}
+
+/// Transactions and withdrawals of a block, returned by `engine_getPayloadBodiesByHashV1` and `engine_getPayloadBodiesByRangeV1`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPayloadBodyV1 {
+    pub transactions: Vec<EncodedTransaction>,
+    /// None for blocks before the Shanghai fork
+    pub withdrawals: Option<Vec<Withdrawal>>,
+}
+
+impl From<BlockBody> for ExecutionPayloadBodyV1 {
+    fn from(body: BlockBody) -> Self {
+        ExecutionPayloadBodyV1 {
+            transactions: body
+                .transactions
+                .iter()
+                .map(|tx| EncodedTransaction(tx.encode_canonical_to_vec().into()))
+                .collect(),
+            withdrawals: body.withdrawals,
+        }
+    }
+}
+
+/// Blobs, commitments and proofs of the blob transactions included in a payload
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobsBundleV1 {
+    #[serde(with = "crate::serde_utils::bytes::vec")]
+    pub commitments: Vec<Bytes>,
+    #[serde(with = "crate::serde_utils::bytes::vec")]
+    pub proofs: Vec<Bytes>,
+    #[serde(with = "crate::serde_utils::bytes::vec")]
+    pub blobs: Vec<Bytes>,
 }
 
 #[allow(unused)]
@@ -151,6 +432,15 @@ impl PayloadStatus {
         }
     }
 
+    /// Creates a PayloadStatus with invalid status, latest valid hash and error message
+    pub fn invalid_with(hash: BlockHash, error: &str) -> Self {
+        PayloadStatus {
+            status: PayloadValidationStatus::Invalid,
+            latest_valid_hash: Some(hash),
+            validation_error: Some(error.to_string()),
+        }
+    }
+
     /// Creates a PayloadStatus with syncing status and no other info
     pub fn syncing() -> Self {
         PayloadStatus {
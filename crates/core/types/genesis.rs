@@ -1,7 +1,7 @@
 use bytes::Bytes;
 use ethereum_types::{Address, Bloom, H256, U256};
 use patricia_merkle_tree::PatriciaMerkleTree;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 
@@ -36,11 +36,14 @@ pub struct Genesis {
 
 /// Blockchain settings defined per block
 #[allow(unused)]
-#[derive(Debug, Deserialize, Default, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ChainConfig {
     /// Current chain identifier
-    #[serde(deserialize_with = "crate::serde_utils::u256::deser_number")]
+    #[serde(
+        deserialize_with = "crate::serde_utils::u256::deser_number",
+        serialize_with = "crate::serde_utils::u256::serialize_number"
+    )]
     pub chain_id: U256,
 
     /// Block numbers for the block where each fork was activated
@@ -77,7 +80,9 @@ pub struct ChainConfig {
     /// Amount of total difficulty reached by the network that triggers the consensus upgrade.
     #[serde(
         default,
-        deserialize_with = "crate::serde_utils::u256::deser_number_opt"
+        deserialize_with = "crate::serde_utils::u256::deser_number_opt",
+        serialize_with = "crate::serde_utils::u256::serialize_number_opt",
+        skip_serializing_if = "Option::is_none"
     )]
     pub terminal_total_difficulty: Option<U256>,
     /// Network has already passed the terminal total difficult
@@ -85,6 +90,36 @@ pub struct ChainConfig {
     pub terminal_total_difficulty_passed: bool,
 }
 
+/// Post-merge forks, in activation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fork {
+    Paris,
+    Shanghai,
+    Cancun,
+}
+
+impl ChainConfig {
+    pub fn is_shanghai_activated(&self, block_timestamp: u64) -> bool {
+        self.shanghai_time
+            .is_some_and(|time| time <= block_timestamp)
+    }
+
+    pub fn is_cancun_activated(&self, block_timestamp: u64) -> bool {
+        self.cancun_time.is_some_and(|time| time <= block_timestamp)
+    }
+
+    /// Returns the latest fork active at the given timestamp
+    pub fn get_fork(&self, block_timestamp: u64) -> Fork {
+        if self.is_cancun_activated(block_timestamp) {
+            Fork::Cancun
+        } else if self.is_shanghai_activated(block_timestamp) {
+            Fork::Shanghai
+        } else {
+            Fork::Paris
+        }
+    }
+}
+
 #[allow(unused)]
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct GenesisAccount {
@@ -106,6 +141,7 @@ impl Genesis {
     }
 
     fn get_block_header(&self) -> BlockHeader {
+        let cancun_activated = self.config.is_cancun_activated(self.timestamp);
         BlockHeader {
             parent_hash: H256::zero(),
             ommers_hash: *DEFAULT_OMMERS_HASH,
@@ -123,10 +159,13 @@ impl Genesis {
             prev_randao: self.mixhash,
             nonce: self.nonce,
             base_fee_per_gas: INITIAL_BASE_FEE,
-            withdrawals_root: Some(compute_withdrawals_root(&[])),
-            blob_gas_used: Some(0),
-            excess_blob_gas: Some(0),
-            parent_beacon_block_root: Some(H256::zero()),
+            withdrawals_root: self
+                .config
+                .is_shanghai_activated(self.timestamp)
+                .then(|| compute_withdrawals_root(&[])),
+            blob_gas_used: cancun_activated.then_some(0),
+            excess_blob_gas: cancun_activated.then_some(0),
+            parent_beacon_block_root: cancun_activated.then_some(H256::zero()),
         }
     }
 
@@ -134,7 +173,10 @@ impl Genesis {
         BlockBody {
             transactions: vec![],
             ommers: vec![],
-            withdrawals: Some(vec![]),
+            withdrawals: self
+                .config
+                .is_shanghai_activated(self.timestamp)
+                .then(Vec::new),
         }
     }
 
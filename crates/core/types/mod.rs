@@ -3,6 +3,7 @@ mod block;
 mod constants;
 mod engine;
 mod genesis;
+mod overrides;
 mod receipt;
 mod transaction;
 
@@ -11,5 +12,6 @@ pub use block::*;
 pub use constants::*;
 pub use engine::*;
 pub use genesis::*;
+pub use overrides::*;
 pub use receipt::*;
 pub use transaction::*;
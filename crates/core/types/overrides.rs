@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+use serde::Deserialize;
+
+use super::{BlockHeader, BlockNumber};
+
+/// Overrides applied to the state of each account before simulating transactions on top of it
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+/// Changes applied to an account before simulating transactions, which are never persisted
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(default)]
+    pub balance: Option<U256>,
+    #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
+    pub nonce: Option<u64>,
+    #[serde(default, with = "crate::serde_utils::bytes::opt")]
+    pub code: Option<Bytes>,
+    /// Replaces the whole storage of the account
+    #[serde(default)]
+    pub state: Option<HashMap<H256, H256>>,
+    /// Replaces the given storage slots, keeping the rest of the storage
+    #[serde(default)]
+    pub state_diff: Option<HashMap<H256, H256>>,
+}
+
+/// Changes applied to the header of a block before simulating transactions in it
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverrides {
+    #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
+    pub number: Option<BlockNumber>,
+    #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
+    pub time: Option<u64>,
+    #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
+    pub gas_limit: Option<u64>,
+    #[serde(default, alias = "baseFee", with = "crate::serde_utils::u64::hex_str_opt")]
+    pub base_fee_per_gas: Option<u64>,
+    #[serde(default)]
+    pub fee_recipient: Option<Address>,
+    #[serde(default)]
+    pub prev_randao: Option<H256>,
+}
+
+impl BlockOverrides {
+    pub fn apply(&self, header: &mut BlockHeader) {
+        if let Some(number) = self.number {
+            header.number = number;
+        }
+        if let Some(time) = self.time {
+            header.timestamp = time;
+        }
+        if let Some(gas_limit) = self.gas_limit {
+            header.gas_limit = gas_limit;
+        }
+        if let Some(base_fee_per_gas) = self.base_fee_per_gas {
+            header.base_fee_per_gas = base_fee_per_gas;
+        }
+        if let Some(fee_recipient) = self.fee_recipient {
+            header.coinbase = fee_recipient;
+        }
+        if let Some(prev_randao) = self.prev_randao {
+            header.prev_randao = prev_randao;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deserialize_overrides() {
+        let overrides: StateOverride = serde_json::from_str(
+            r#"{
+                "0x000000000000000000000000000000000000000a": {
+                    "balance": "0x10",
+                    "nonce": "0x2",
+                    "code": "0x6000",
+                    "stateDiff": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001": "0x0000000000000000000000000000000000000000000000000000000000000005"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let account = &overrides[&Address::from_low_u64_be(10)];
+        assert_eq!(account.balance, Some(U256::from(16)));
+        assert_eq!(account.nonce, Some(2));
+        assert_eq!(account.code, Some(Bytes::from_static(&[0x60, 0x00])));
+        assert!(account.state.is_none());
+        assert_eq!(
+            account.state_diff,
+            Some(HashMap::from([(
+                H256::from_low_u64_be(1),
+                H256::from_low_u64_be(5)
+            )]))
+        );
+
+        let overrides: BlockOverrides =
+            serde_json::from_str(r#"{"number": "0x20", "baseFee": "0x7"}"#).unwrap();
+        let mut header = BlockHeader::default();
+        overrides.apply(&mut header);
+        assert_eq!(header.number, 32);
+        assert_eq!(header.base_fee_per_gas, 7);
+        assert_eq!(header.timestamp, 0);
+    }
+}
@@ -604,8 +604,56 @@ impl Transaction {
         }
     }
 
+    /// Returns the tip per unit of gas paid to the block's coinbase given the block's base fee
+    /// Returns None if the transaction's fee cap is lower than the base fee
+    pub fn effective_gas_tip(&self, base_fee: Option<u64>) -> Option<u64> {
+        let base_fee = base_fee.unwrap_or_default();
+        let max_tip = self.gas_price().checked_sub(base_fee)?;
+        Some(match self.max_priority_fee() {
+            Some(max_priority_fee) => max_priority_fee.min(max_tip),
+            None => max_tip,
+        })
+    }
+
+    /// Encodes the transaction as defined in [EIP-2718]:
+    /// `TransactionType || Transaction` for typed transactions and `LegacyTransaction` for legacy ones
+    /// This is the format used by the engine api and by `eth_sendRawTransaction`
+    pub fn encode_canonical_to_vec(&self) -> Vec<u8> {
+        let encoded = self.encode_to_vec();
+        match self {
+            Transaction::LegacyTransaction(_) => encoded,
+            // Typed transactions are encoded as an rlp bytes item when included in a block
+            _ => get_rlp_bytes_item_payload(&encoded).to_vec(),
+        }
+    }
+
+    /// Decodes a transaction encoded as defined in [EIP-2718] (see `encode_canonical_to_vec`)
+    pub fn decode_canonical(bytes: &[u8]) -> Result<Self, RLPDecodeError> {
+        match bytes.first() {
+            Some(tx_type) if *tx_type <= 0x7f => {
+                let tx_encoding = &bytes[1..];
+                match *tx_type {
+                    0x1 => {
+                        EIP2930Transaction::decode(tx_encoding).map(Transaction::EIP2930Transaction)
+                    }
+                    0x2 => {
+                        EIP1559Transaction::decode(tx_encoding).map(Transaction::EIP1559Transaction)
+                    }
+                    0x3 => {
+                        EIP4844Transaction::decode(tx_encoding).map(Transaction::EIP4844Transaction)
+                    }
+                    ty => Err(RLPDecodeError::Custom(format!(
+                        "Invalid transaction type: {ty}"
+                    ))),
+                }
+            }
+            _ => LegacyTransaction::decode(bytes).map(Transaction::LegacyTransaction),
+        }
+    }
+
+    /// Returns the transaction hash, computed over its [EIP-2718] encoding
     pub fn compute_hash(&self) -> H256 {
-        keccak_hash::keccak(self.encode_to_vec())
+        keccak_hash::keccak(self.encode_canonical_to_vec())
     }
 
     pub fn receipt_info(&self, index: u64) -> ReceiptTxInfo {
@@ -731,7 +779,7 @@ mod serde_impl {
         }
     }
 
-    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
     #[serde(rename_all = "camelCase")]
     pub struct AccessListEntry {
         pub address: Address,
@@ -877,15 +925,16 @@ mod serde_impl {
     }
 
     /// Unsigned Transaction struct generic to all types which may not contain all required transaction fields
-    /// Used to perform gas estimations and access list creation
-    #[derive(Deserialize, Debug, PartialEq)]
+    /// Used to perform calls, gas estimations and access list creation
+    #[derive(Deserialize, Debug, PartialEq, Clone)]
     #[serde(rename_all = "camelCase")]
     pub struct GenericTransaction {
         #[serde(default)]
         pub r#type: TxType,
-        #[serde(with = "crate::serde_utils::u64::hex_str")]
-        pub nonce: u64,
+        #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
+        pub nonce: Option<u64>,
         pub to: TxKind,
+        #[serde(default)]
         pub from: Address,
         #[serde(default, with = "crate::serde_utils::u64::hex_str_opt")]
         pub gas: Option<u64>,
@@ -1024,6 +1073,15 @@ mod tests {
         assert_eq!(tx, expected_tx);
     }
 
+    #[test]
+    fn canonical_decode_rejects_reserved_tx_type() {
+        // 0x7f is the highest type prefix, it must not be decoded as a legacy transaction
+        let encoded_tx = "f86c8330182480114e82f618946177843db3138ae69679a54b95cf345ed759450d870aa87bee53800080c080a0151ccc02146b9b11adf516e6787b59acae3e76544fdcd75e77e67c6b598ce65da064c5dd5aae2fbb535830ebbdad0234975cd7ece3562013b63ea18cc0df6c97d4";
+        let mut encoded_tx_bytes = vec![0x7f];
+        encoded_tx_bytes.extend(hex::decode(encoded_tx).unwrap());
+        assert!(Transaction::decode_canonical(&encoded_tx_bytes).is_err());
+    }
+
     #[test]
     fn deserialize_tx_kind() {
         let tx_kind_create = r#""""#;
@@ -1081,7 +1139,7 @@ mod tests {
         }"#;
         let deserialized_generic_transaction = GenericTransaction {
             r#type: TxType::EIP2930,
-            nonce: 2,
+            nonce: Some(2),
             to: TxKind::Create,
             from: Address::from_slice(
                 &hex::decode("6177843db3138ae69679A54b95cf345ED759450d").unwrap(),
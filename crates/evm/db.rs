@@ -1,25 +1,32 @@
-use ethereum_rust_core::{Address as CoreAddress, H256 as CoreH256};
+use ethereum_rust_core::{types::BlockNumber, Address as CoreAddress, H256 as CoreH256};
 use ethereum_rust_storage::{error::StoreError, Store};
 use revm::primitives::{
     AccountInfo as RevmAccountInfo, Address as RevmAddress, Bytecode as RevmBytecode,
     Bytes as RevmBytes, B256 as RevmB256, U256 as RevmU256,
 };
 
-pub struct StoreWrapper(pub Store);
+pub struct StoreWrapper {
+    pub store: Store,
+    /// Block whose resulting state is read, the current state is read if not set
+    pub block_number: Option<BlockNumber>,
+}
 
 impl revm::Database for StoreWrapper {
     type Error = StoreError;
 
     fn basic(&mut self, address: RevmAddress) -> Result<Option<RevmAccountInfo>, Self::Error> {
-        let acc_info = match self
-            .0
-            .get_account_info(CoreAddress::from(address.0.as_ref()))?
-        {
-            None => return Ok(None),
-            Some(acc_info) => acc_info,
+        let address = CoreAddress::from(address.0.as_ref());
+        let acc_info = match self.block_number {
+            Some(block_number) => self
+                .store
+                .get_account_info_by_block(block_number, address)?,
+            None => self.store.get_account_info(address)?,
+        };
+        let Some(acc_info) = acc_info else {
+            return Ok(None);
         };
         let code = self
-            .0
+            .store
             .get_account_code(acc_info.code_hash)?
             .map(|b| RevmBytecode::new_raw(RevmBytes(b)));
 
@@ -32,25 +39,28 @@ impl revm::Database for StoreWrapper {
     }
 
     fn code_by_hash(&mut self, code_hash: RevmB256) -> Result<RevmBytecode, Self::Error> {
-        self.0
+        self.store
             .get_account_code(CoreH256::from(code_hash.as_ref()))?
             .map(|b| RevmBytecode::new_raw(RevmBytes(b)))
             .ok_or_else(|| StoreError::Custom(format!("No code for hash {code_hash}")))
     }
 
     fn storage(&mut self, address: RevmAddress, index: RevmU256) -> Result<RevmU256, Self::Error> {
-        Ok(self
-            .0
-            .get_storage_at(
-                CoreAddress::from(address.0.as_ref()),
-                CoreH256::from(index.to_be_bytes()),
-            )?
+        let address = CoreAddress::from(address.0.as_ref());
+        let key = CoreH256::from(index.to_be_bytes());
+        let value = match self.block_number {
+            Some(block_number) => self
+                .store
+                .get_storage_at_by_block(block_number, address, key)?,
+            None => self.store.get_storage_at(address, key)?,
+        };
+        Ok(value
             .map(|value| RevmU256::from_limbs(value.0))
             .unwrap_or_else(|| RevmU256::ZERO))
     }
 
     fn block_hash(&mut self, number: RevmU256) -> Result<RevmB256, Self::Error> {
-        self.0
+        self.store
             .get_block_header(number.to())?
             .map(|header| RevmB256::from_slice(&header.compute_block_hash().0))
             .ok_or_else(|| StoreError::Custom(format!("Block {number} not found")))
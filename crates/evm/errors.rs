@@ -8,6 +8,8 @@ pub enum EvmError {
     Transaction(String),
     #[error("Invalid Header: {0}")]
     Header(String),
+    #[error("Invalid Block: {0}")]
+    InvalidBlock(String),
     #[error("DB error: {0}")]
     DB(#[from] StoreError),
     #[error("{0}")]
@@ -1,24 +1,29 @@
 mod db;
 mod errors;
 mod execution_result;
+pub mod payload;
+pub mod trace;
 
 use db::StoreWrapper;
 
 use ethereum_rust_core::{
     types::{
-        AccountInfo, Block, BlockHeader, GenericTransaction, Transaction, TxKind, Withdrawal,
+        compute_receipts_root, AccountInfo, Block, BlockHeader, BlockNumber, ChainConfig, Fork,
+        GenericTransaction, Log, Receipt, StateOverride, Transaction, TxKind, Withdrawal,
         GWEI_TO_WEI,
     },
-    Address, BigEndianHash, H256, U256,
+    Address, BigEndianHash, Bloom, H256, U256,
 };
-use ethereum_rust_storage::{error::StoreError, Store};
+use ethereum_rust_storage::{error::StoreError, AccountUpdate, Store};
 use lazy_static::lazy_static;
 use revm::{
     db::states::bundle_state::BundleRetention,
     inspector_handle_register,
-    inspectors::TracerEip3155,
     precompile::{PrecompileSpecId, Precompiles},
-    primitives::{BlockEnv, TxEnv, B256, U256 as RevmU256},
+    primitives::{
+        Account as RevmAccount, AccountStatus, BlobExcessGasAndPrice, BlockEnv, Bytecode,
+        EvmStorageSlot, HashMap, TxEnv, B256, U256 as RevmU256,
+    },
     Database, DatabaseCommit, Evm,
 };
 use revm_inspectors::access_list::AccessListInspector;
@@ -39,27 +44,120 @@ pub struct EvmState(revm::db::State<StoreWrapper>);
 impl EvmState {
     /// Get a reference to inner `Store` database
     pub fn database(&self) -> &Store {
-        &self.0.database.0
+        &self.0.database.store
+    }
+}
+
+/// Returns the spec id of the fork active at the given block timestamp
+pub fn spec_id(chain_config: &ChainConfig, block_timestamp: u64) -> SpecId {
+    match chain_config.get_fork(block_timestamp) {
+        Fork::Paris => SpecId::MERGE,
+        Fork::Shanghai => SpecId::SHANGHAI,
+        Fork::Cancun => SpecId::CANCUN,
     }
 }
 
 /// Executes all transactions in a block and performs the state transition on the database
-pub fn execute_block(block: &Block, state: &mut EvmState, spec_id: SpecId) -> Result<(), EvmError> {
+pub fn execute_block(
+    block: &Block,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<Vec<Receipt>, EvmError> {
+    let receipts = run_block(block, state, spec_id)?;
+    apply_state_transitions(state, block.header.number)?;
+    Ok(receipts)
+}
+
+/// Executes all transactions in a block and checks that the resulting gas used, receipts root, logs bloom
+/// and state root match the ones in the block header
+/// The given state must be the one resulting from the block's parent
+/// The state transition is only performed on the database if the block is valid, replacing the state of
+/// any stored block that has the same parent
+pub fn execute_and_validate_block(
+    block: &Block,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<Vec<Receipt>, EvmError> {
+    let header = &block.header;
+    let receipts = run_block(block, state, spec_id)?;
+    let account_updates = get_state_transitions(state);
+
+    let gas_used = receipts
+        .last()
+        .map(|receipt| receipt.cumulative_gas_used)
+        .unwrap_or_default();
+    if gas_used != header.gas_used {
+        return Err(EvmError::InvalidBlock(format!(
+            "Gas used mismatch: header {}, execution {gas_used}",
+            header.gas_used
+        )));
+    }
+    let receipts_root = compute_receipts_root(&receipts);
+    if receipts_root != header.receipt_root {
+        return Err(EvmError::InvalidBlock(format!(
+            "Receipts root mismatch: header {:#x}, execution {receipts_root:#x}",
+            header.receipt_root
+        )));
+    }
+    let mut logs_bloom = Bloom::zero();
+    for receipt in receipts.iter() {
+        logs_bloom.accrue_bloom(&receipt.bloom);
+    }
+    if logs_bloom != header.logs_bloom {
+        return Err(EvmError::InvalidBlock(format!(
+            "Logs bloom mismatch: header {:#x}, execution {logs_bloom:#x}",
+            header.logs_bloom
+        )));
+    }
+    // The block is executed on top of its parent's state, which may not be the current one
+    let state_root = state.database().world_state_root_at_block_with_updates(
+        header.number.saturating_sub(1),
+        &account_updates,
+    )?;
+    if state_root != header.state_root {
+        return Err(EvmError::InvalidBlock(format!(
+            "State root mismatch: header {:#x}, execution {state_root:#x}",
+            header.state_root
+        )));
+    }
+
+    state
+        .database()
+        .apply_account_updates(header.number, &account_updates)?;
+    Ok(receipts)
+}
+
+/// Executes all transactions and withdrawals in a block and returns the transaction receipts
+/// The resulting state transitions are kept in the state and are not applied to the database
+pub fn run_block(
+    block: &Block,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<Vec<Receipt>, EvmError> {
     let block_header = &block.header;
     //eip 4788: execute beacon_root_contract_call before block transactions
     if block_header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
         beacon_root_contract_call(state, block_header, spec_id)?;
     }
+    let mut receipts = Vec::new();
+    let mut cumulative_gas_used = 0;
     for transaction in block.body.transactions.iter() {
-        execute_tx(transaction, block_header, state, spec_id)?;
+        let result = execute_tx(transaction, block_header, state, spec_id)?;
+        cumulative_gas_used += result.gas_used();
+        let logs = result.logs();
+        receipts.push(Receipt::new(
+            transaction.tx_type(),
+            result.is_success(),
+            cumulative_gas_used,
+            Log::bloom(&logs),
+            logs,
+        ));
     }
 
-    apply_state_transitions(state)?;
     if let Some(withdrawals) = &block.body.withdrawals {
         process_withdrawals(state, withdrawals)?;
     }
-    apply_state_transitions(state)?;
-    Ok(())
+    Ok(receipts)
 }
 
 // Executes a single tx, doesn't perform state transitions
@@ -94,15 +192,119 @@ fn run_evm(
             })
             .with_spec_id(spec_id)
             .reset_handler()
-            .with_external_context(
-                TracerEip3155::new(Box::new(std::io::stderr())).without_summary(),
-            )
             .build();
         evm.transact_commit().map_err(EvmError::from)?
     };
     Ok(tx_result.into())
 }
 
+/// Runs the transaction on top of the state of the given block without committing its changes
+/// Base fee and block gas limit checks are disabled so that calls can be made without a gas price
+pub fn simulate_tx_from_generic(
+    tx: &GenericTransaction,
+    header: &BlockHeader,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<ExecutionResult, EvmError> {
+    let tx_env = tx_env_from_generic(tx);
+    let block_env = block_env(header);
+    simulate_tx(tx_env, block_env, state, spec_id)
+}
+
+/// Runs the transaction on top of the state of the given block and commits its changes to the state,
+/// so that the transactions simulated after it see them. The changes are never applied to the DB
+/// Base fee and block gas limit checks are disabled as with other simulations
+pub fn simulate_tx_from_generic_and_commit(
+    tx: &GenericTransaction,
+    header: &BlockHeader,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<ExecutionResult, EvmError> {
+    let tx_result = {
+        let mut evm = Evm::builder()
+            .with_db(&mut state.0)
+            .with_block_env(block_env(header))
+            .with_tx_env(tx_env_from_generic(tx))
+            .with_spec_id(spec_id)
+            .modify_cfg_env(|env| {
+                env.disable_base_fee = true;
+                env.disable_block_gas_limit = true
+            })
+            .build();
+        evm.transact_commit().map_err(EvmError::from)?
+    };
+    Ok(tx_result.into())
+}
+
+/// Applies the overrides to the accounts in the state, so that later simulations run on top of them
+/// The overrides are never applied to the DB
+pub fn apply_state_overrides(
+    state: &mut EvmState,
+    overrides: &StateOverride,
+) -> Result<(), EvmError> {
+    let mut accounts = HashMap::new();
+    for (address, account_override) in overrides {
+        let revm_address = RevmAddress(address.0.into());
+        // Loads the account into the cache, which is needed to commit changes to it
+        let mut info = state.0.basic(revm_address)?.unwrap_or_default();
+        if let Some(balance) = account_override.balance {
+            info.balance = RevmU256::from_limbs(balance.0);
+        }
+        if let Some(nonce) = account_override.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = &account_override.code {
+            let code = Bytecode::new_raw(code.clone().into());
+            info.code_hash = code.hash_slow();
+            info.code = Some(code);
+        }
+        let mut status = AccountStatus::Touched;
+        let mut storage = HashMap::new();
+        match (&account_override.state, &account_override.state_diff) {
+            (Some(_), Some(_)) => {
+                return Err(EvmError::Custom(format!(
+                    "Both state and stateDiff overrides given for account {address:#x}"
+                )))
+            }
+            // Marking the account as created clears its previous storage
+            (Some(slots), None) => {
+                status |= AccountStatus::Created;
+                for (key, value) in slots {
+                    let value = RevmU256::from_be_bytes(value.0);
+                    storage.insert(
+                        RevmU256::from_be_bytes(key.0),
+                        EvmStorageSlot::new_changed(RevmU256::ZERO, value),
+                    );
+                }
+            }
+            (None, Some(slots)) => {
+                for (key, value) in slots {
+                    let key = RevmU256::from_be_bytes(key.0);
+                    let previous_value = state.0.storage(revm_address, key)?;
+                    storage.insert(
+                        key,
+                        EvmStorageSlot::new_changed(
+                            previous_value,
+                            RevmU256::from_be_bytes(value.0),
+                        ),
+                    );
+                }
+            }
+            (None, None) => {}
+        }
+        accounts.insert(
+            revm_address,
+            RevmAccount {
+                info,
+                storage,
+                status,
+            },
+        );
+    }
+    state.0.commit(accounts);
+    Ok(())
+}
+
 /// Runs the transaction and returns the access list and estimated gas use (when running the tx with said access list)
 pub fn create_access_list(
     tx: &GenericTransaction,
@@ -126,7 +328,7 @@ pub fn create_access_list(
                     .collect(),
             )
         }));
-        estimate_gas(tx_env, block_env, state, spec_id)?
+        simulate_tx(tx_env, block_env, state, spec_id)?
     } else {
         execution_result
     };
@@ -173,8 +375,8 @@ fn create_access_list_inner(
     Ok((tx_result.result.into(), access_list))
 }
 
-/// Runs the transaction and returns the estimated gas
-fn estimate_gas(
+/// Runs the transaction without committing its changes and returns its execution result
+fn simulate_tx(
     tx_env: TxEnv,
     block_env: BlockEnv,
     state: &mut EvmState,
@@ -196,55 +398,59 @@ fn estimate_gas(
     Ok(tx_result.result.into())
 }
 
-// Merges transitions stored when executing transactions and applies the resulting changes to the DB
-pub fn apply_state_transitions(state: &mut EvmState) -> Result<(), StoreError> {
+// Merges transitions stored when executing the transactions of a block and applies the resulting changes to the DB
+pub fn apply_state_transitions(
+    state: &mut EvmState,
+    block_number: BlockNumber,
+) -> Result<(), StoreError> {
+    let account_updates = get_state_transitions(state);
+    state
+        .database()
+        .apply_account_updates(block_number, &account_updates)
+}
+
+/// Merges transitions stored when executing transactions and returns the resulting account updates
+/// Doesn't update the DB
+pub fn get_state_transitions(state: &mut EvmState) -> Vec<AccountUpdate> {
     state.0.merge_transitions(BundleRetention::PlainState);
     let bundle = state.0.take_bundle();
-    // Update accounts
+    let mut account_updates = Vec::new();
     for (address, account) in bundle.state() {
         if account.status.is_not_modified() {
             continue;
         }
-        let address = Address::from_slice(address.0.as_slice());
-        // Remove account from DB if destroyed
-        if account.status.was_destroyed() {
-            state.database().remove_account(address)?;
-        }
-        // Apply account changes to DB
+        let mut update = AccountUpdate {
+            address: Address::from_slice(address.0.as_slice()),
+            // Remove account from DB if destroyed
+            removed: account.status.was_destroyed(),
+            ..Default::default()
+        };
         // If the account was changed then both original and current info will be present in the bundle account
         if account.is_info_changed() {
-            // Update account info in DB
             if let Some(new_acc_info) = account.account_info() {
-                let code_hash = H256::from_slice(new_acc_info.code_hash.as_slice());
-                let account_info = AccountInfo {
-                    code_hash,
+                update.info = Some(AccountInfo {
+                    code_hash: H256::from_slice(new_acc_info.code_hash.as_slice()),
                     balance: U256::from_little_endian(new_acc_info.balance.as_le_slice()),
                     nonce: new_acc_info.nonce,
-                };
-                state.database().add_account_info(address, account_info)?;
-
+                });
                 if account.is_contract_changed() {
-                    // Update code in db
-                    if let Some(code) = new_acc_info.code {
-                        state
-                            .database()
-                            .add_account_code(code_hash, code.original_bytes().clone().0)?;
-                    }
+                    update.code = new_acc_info
+                        .code
+                        .map(|code| code.original_bytes().clone().0);
                 }
             }
         }
-        // Update account storage in DB
         for (key, slot) in account.storage.iter() {
             if slot.is_changed() {
-                state.database().add_storage_at(
-                    address,
+                update.added_storage.insert(
                     H256::from_uint(&U256::from_little_endian(key.as_le_slice())),
                     U256::from_little_endian(slot.present_value().as_le_slice()),
-                )?;
+                );
             }
         }
+        account_updates.push(update);
     }
-    Ok(())
+    account_updates
 }
 
 /// Processes a block's withdrawals, updating the account balances in the state
@@ -270,9 +476,25 @@ pub fn process_withdrawals(
 
 /// Builds EvmState from a Store
 pub fn evm_state(store: Store) -> EvmState {
+    build_evm_state(StoreWrapper {
+        store,
+        block_number: None,
+    })
+}
+
+/// Builds EvmState from the state a Store had right after the given block was executed
+/// Changes made on top of it must not be applied to the Store
+pub fn evm_state_at_block(store: Store, block_number: BlockNumber) -> EvmState {
+    build_evm_state(StoreWrapper {
+        store,
+        block_number: Some(block_number),
+    })
+}
+
+fn build_evm_state(database: StoreWrapper) -> EvmState {
     EvmState(
         revm::db::State::builder()
-            .with_database(StoreWrapper(store))
+            .with_database(database)
             .with_bundle_update()
             .without_state_clear()
             .build(),
@@ -320,7 +542,6 @@ pub fn beacon_root_contract_call(
         .with_tx_env(tx_env)
         .with_spec_id(spec_id)
         .reset_handler()
-        .with_external_context(TracerEip3155::new(Box::new(std::io::stderr())).without_summary())
         .build();
 
     let transaction_result = evm.transact()?;
@@ -343,7 +564,10 @@ fn block_env(header: &BlockHeader) -> BlockEnv {
         basefee: RevmU256::from(header.base_fee_per_gas),
         difficulty: RevmU256::from_limbs(header.difficulty.0),
         prevrandao: Some(header.prev_randao.as_fixed_bytes().into()),
-        ..Default::default()
+        // Blocks prior to Cancun have no excess blob gas, the value is ignored for their spec
+        blob_excess_gas_and_price: Some(BlobExcessGasAndPrice::new(
+            header.excess_blob_gas.unwrap_or_default(),
+        )),
     }
 }
 
@@ -390,7 +614,8 @@ fn tx_env(tx: &Transaction) -> TxEnv {
     }
 }
 
-// Used to estimate gas and create access lists
+// Used to simulate transactions, estimate gas and create access lists
+// The nonce is only checked if the transaction specifies it
 fn tx_env_from_generic(tx: &GenericTransaction) -> TxEnv {
     TxEnv {
         caller: RevmAddress(tx.from.0.into()),
@@ -402,7 +627,7 @@ fn tx_env_from_generic(tx: &GenericTransaction) -> TxEnv {
         },
         value: RevmU256::from_limbs(tx.value.0),
         data: tx.input.clone().into(),
-        nonce: Some(tx.nonce),
+        nonce: tx.nonce,
         chain_id: tx.chain_id,
         access_list: tx
             .access_list
@@ -1,5 +1,5 @@
 use bytes::Bytes;
-use ethereum_rust_core::Address;
+use ethereum_rust_core::{types::Log, Address, H256};
 use revm::primitives::result::Output as RevmOutput;
 use revm::primitives::result::SuccessReason as RevmSuccessReason;
 use revm::primitives::ExecutionResult as RevmExecutionResult;
@@ -10,6 +10,7 @@ pub enum ExecutionResult {
         reason: SuccessReason,
         gas_used: u64,
         gas_refunded: u64,
+        logs: Vec<Log>,
         output: Output,
     },
     /// Reverted by `REVERT` opcode
@@ -43,7 +44,7 @@ impl From<RevmExecutionResult> for ExecutionResult {
                 reason,
                 gas_used,
                 gas_refunded,
-                logs: _,
+                logs,
                 output,
             } => ExecutionResult::Success {
                 reason: match reason {
@@ -54,6 +55,18 @@ impl From<RevmExecutionResult> for ExecutionResult {
                 },
                 gas_used,
                 gas_refunded,
+                logs: logs
+                    .into_iter()
+                    .map(|log| Log {
+                        address: Address::from_slice(log.address.0.as_ref()),
+                        topics: log
+                            .topics()
+                            .iter()
+                            .map(|topic| H256::from_slice(topic.as_ref()))
+                            .collect(),
+                        data: log.data.data.0,
+                    })
+                    .collect(),
                 output: match output {
                     RevmOutput::Call(bytes) => Output::Call(bytes.0),
                     RevmOutput::Create(bytes, addr) => Output::Create(
@@ -76,14 +89,22 @@ impl From<RevmExecutionResult> for ExecutionResult {
 
 impl ExecutionResult {
     pub fn is_success(&self) -> bool {
-        matches!(
-            self,
-            ExecutionResult::Success {
-                reason: _,
-                gas_used: _,
-                gas_refunded: _,
-                output: _
-            }
-        )
+        matches!(self, ExecutionResult::Success { .. })
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        match self {
+            ExecutionResult::Success { gas_used, .. } => *gas_used,
+            ExecutionResult::Revert { gas_used, .. } => *gas_used,
+            ExecutionResult::Halt { gas_used, .. } => *gas_used,
+        }
+    }
+
+    /// Logs emitted during execution, reverted and halted executions produce no logs
+    pub fn logs(&self) -> Vec<Log> {
+        match self {
+            ExecutionResult::Success { logs, .. } => logs.clone(),
+            _ => vec![],
+        }
     }
 }
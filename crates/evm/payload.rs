@@ -0,0 +1,182 @@
+use ethereum_rust_core::{
+    rlp::encode::RLPEncode,
+    types::{
+        calc_excess_blob_gas, calculate_base_fee_per_gas, compute_receipts_root,
+        compute_transactions_root, compute_withdrawals_root, Block, BlockBody, BlockHash,
+        BlockHeader, Log, Receipt, Transaction, Withdrawal, DEFAULT_OMMERS_HASH,
+    },
+    Address, Bloom, Bytes, H256, U256,
+};
+use ethereum_rust_storage::Store;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    beacon_root_contract_call, evm_state_at_block, execute_tx, get_state_transitions,
+    process_withdrawals, spec_id, EvmError,
+};
+
+/// Arguments received from the consensus layer to start building a payload
+pub struct BuildPayloadArgs {
+    pub parent: BlockHash,
+    pub timestamp: u64,
+    pub fee_recipient: Address,
+    pub random: H256,
+    pub withdrawals: Option<Vec<Withdrawal>>,
+    pub beacon_root: Option<H256>,
+}
+
+impl BuildPayloadArgs {
+    /// Computes an 8-byte identifier by hashing the components of the payload arguments
+    pub fn id(&self) -> u64 {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.parent);
+        hasher.update(self.timestamp.to_be_bytes());
+        hasher.update(self.random);
+        hasher.update(self.fee_recipient);
+        if let Some(withdrawals) = &self.withdrawals {
+            hasher.update(withdrawals.encode_to_vec());
+        }
+        if let Some(beacon_root) = self.beacon_root {
+            hasher.update(beacon_root);
+        }
+        let hash = hasher.finalize();
+        u64::from_be_bytes(hash[..8].try_into().unwrap())
+    }
+}
+
+/// Creates a new payload on top of the parent block referenced by the payload arguments
+/// The payload has no transactions and its execution related fields are not set until it is built with `build_payload`
+/// Fork specific fields are set according to the fork active at the payload's timestamp
+pub fn create_payload(args: &BuildPayloadArgs, storage: &Store) -> Result<Block, EvmError> {
+    let chain_config = storage
+        .get_chain_config()?
+        .ok_or_else(|| EvmError::Custom("Chain config not found".to_string()))?;
+    let cancun_activated = chain_config.is_cancun_activated(args.timestamp);
+    let parent_number = storage
+        .get_block_number(args.parent)?
+        .ok_or_else(|| EvmError::Custom(format!("Parent block {} not found", args.parent)))?;
+    let parent = storage
+        .get_block_header(parent_number)?
+        .ok_or_else(|| EvmError::Custom(format!("Parent block {} not found", args.parent)))?;
+    // We keep the parent's gas limit
+    let gas_limit = parent.gas_limit;
+    let base_fee_per_gas = calculate_base_fee_per_gas(
+        gas_limit,
+        parent.gas_limit,
+        parent.gas_used,
+        parent.base_fee_per_gas,
+    )
+    .ok_or_else(|| EvmError::Header("Invalid parent gas limit".to_string()))?;
+
+    let body = BlockBody {
+        transactions: Vec::new(),
+        ommers: Vec::new(),
+        withdrawals: args.withdrawals.clone(),
+    };
+    let header = BlockHeader {
+        parent_hash: args.parent,
+        ommers_hash: *DEFAULT_OMMERS_HASH,
+        coinbase: args.fee_recipient,
+        state_root: parent.state_root,
+        transactions_root: compute_transactions_root(&body.transactions),
+        receipt_root: compute_receipts_root(&[]),
+        logs_bloom: Bloom::zero(),
+        difficulty: U256::zero(),
+        number: parent.number + 1,
+        gas_limit,
+        gas_used: 0,
+        timestamp: args.timestamp,
+        extra_data: Bytes::new(),
+        prev_randao: args.random,
+        nonce: 0,
+        base_fee_per_gas,
+        withdrawals_root: args
+            .withdrawals
+            .as_ref()
+            .map(|withdrawals| compute_withdrawals_root(withdrawals)),
+        blob_gas_used: cancun_activated.then_some(0),
+        excess_blob_gas: cancun_activated.then(|| {
+            calc_excess_blob_gas(
+                parent.excess_blob_gas.unwrap_or_default(),
+                parent.blob_gas_used.unwrap_or_default(),
+            )
+        }),
+        parent_beacon_block_root: args.beacon_root,
+    };
+    Ok(Block { header, body })
+}
+
+/// Fills a payload created with `create_payload` with the given transactions and updates its
+/// execution related fields (state root, receipts root, logs bloom, gas used)
+/// Transactions that can't be included in the payload are skipped
+/// Returns the value of the payload: the total amount of tips paid to the fee recipient
+/// The payload is executed on top of the state resulting from its parent block
+/// The resulting state changes are not persisted
+pub fn build_payload(
+    payload: &mut Block,
+    storage: &Store,
+    transactions: Vec<Transaction>,
+) -> Result<U256, EvmError> {
+    let chain_config = storage
+        .get_chain_config()?
+        .ok_or_else(|| EvmError::Custom("Chain config not found".to_string()))?;
+    let spec_id = spec_id(&chain_config, payload.header.timestamp);
+    let parent_number = payload.header.number.saturating_sub(1);
+    let mut state = evm_state_at_block(storage.clone(), parent_number);
+    //eip 4788: execute beacon_root_contract_call before block transactions
+    if payload.header.parent_beacon_block_root.is_some() {
+        beacon_root_contract_call(&mut state, &payload.header, spec_id)?;
+    }
+
+    let base_fee = payload.header.base_fee_per_gas;
+    let mut included = Vec::new();
+    let mut receipts = Vec::new();
+    let mut gas_used = 0;
+    let mut block_value = U256::zero();
+    for tx in transactions {
+        // Blob transactions can't be included as we don't keep their sidecars
+        if matches!(tx, Transaction::EIP4844Transaction(_)) {
+            continue;
+        }
+        // Skip transactions that don't fit in the remaining gas or can't pay the base fee
+        if gas_used + tx.gas_limit() > payload.header.gas_limit {
+            continue;
+        }
+        let Some(tip) = tx.effective_gas_tip(Some(base_fee)) else {
+            continue;
+        };
+        // Transactions that fail validation (nonce, balance, etc) are not executed
+        let Ok(result) = execute_tx(&tx, &payload.header, &mut state, spec_id) else {
+            continue;
+        };
+        gas_used += result.gas_used();
+        block_value += U256::from(tip) * U256::from(result.gas_used());
+        let logs = result.logs();
+        receipts.push(Receipt::new(
+            tx.tx_type(),
+            result.is_success(),
+            gas_used,
+            Log::bloom(&logs),
+            logs,
+        ));
+        included.push(tx);
+    }
+
+    if let Some(withdrawals) = &payload.body.withdrawals {
+        process_withdrawals(&mut state, withdrawals)?;
+    }
+    let account_updates = get_state_transitions(&mut state);
+
+    let mut logs_bloom = Bloom::zero();
+    for receipt in receipts.iter() {
+        logs_bloom.accrue_bloom(&receipt.bloom);
+    }
+    payload.header.state_root =
+        storage.world_state_root_at_block_with_updates(parent_number, &account_updates)?;
+    payload.header.transactions_root = compute_transactions_root(&included);
+    payload.header.receipt_root = compute_receipts_root(&receipts);
+    payload.header.logs_bloom = logs_bloom;
+    payload.header.gas_used = gas_used;
+    payload.body.transactions = included;
+    Ok(block_value)
+}
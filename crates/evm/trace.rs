@@ -0,0 +1,417 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use alloy_rpc_types_trace::{
+    geth::{FourByteFrame, GethDebugBuiltInTracerType, GethDebugTracerType, NoopFrame},
+    parity::{Action, TraceOutput, TransactionTrace},
+};
+use ethereum_rust_core::{
+    types::{Block, BlockHeader, GenericTransaction, Transaction},
+    Address,
+};
+use ethereum_rust_storage::error::StoreError;
+use revm::{
+    db::State,
+    inspector_handle_register,
+    primitives::{
+        AccountInfo as RevmAccountInfo, Address as RevmAddress, BlockEnv, Bytecode, ResultAndState,
+        TxEnv, B256, U256 as RevmU256,
+    },
+    Database, DatabaseCommit, DatabaseRef, Evm, Inspector,
+};
+use revm_inspectors::tracing::{FourByteInspector, TracingInspector, TracingInspectorConfig};
+
+use crate::{
+    beacon_root_contract_call, block_env, db::StoreWrapper, execute_tx, tx_env,
+    tx_env_from_generic, EvmError, EvmState, SpecId,
+};
+
+// Tracer options and results follow geth's format
+pub use alloy_rpc_types_trace::geth::{
+    GethDebugTracingOptions as TracingOptions, GethTrace as Trace,
+};
+// Results of the parity trace namespace
+pub use alloy_rpc_types_trace::parity::{
+    LocalizedTransactionTrace, TraceResults, TraceResultsWithTransactionHash, TraceType,
+};
+
+/// Returns true if the tracer selected in the options is available
+pub fn is_supported(options: &TracingOptions) -> bool {
+    matches!(
+        options.tracer,
+        None | Some(GethDebugTracerType::BuiltInTracer(
+            GethDebugBuiltInTracerType::CallTracer
+                | GethDebugBuiltInTracerType::PreStateTracer
+                | GethDebugBuiltInTracerType::FourByteTracer
+                | GethDebugBuiltInTracerType::NoopTracer
+        ))
+    )
+}
+
+/// Replays the transactions that precede the one at the given index in the block, then traces it
+/// The state must be the one resulting from the block's parent
+pub fn trace_transaction(
+    block: &Block,
+    index: usize,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    options: &TracingOptions,
+) -> Result<Trace, EvmError> {
+    let transaction = replay_until(block, index, state, spec_id)?;
+    trace_tx(
+        tx_env(transaction),
+        block_env(&block.header),
+        state,
+        spec_id,
+        options,
+        false,
+    )
+}
+
+/// Traces every transaction in the block, in order
+/// The state must be the one resulting from the block's parent
+pub fn trace_block(
+    block: &Block,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    options: &TracingOptions,
+) -> Result<Vec<Trace>, EvmError> {
+    start_block(&block.header, state, spec_id)?;
+    block
+        .body
+        .transactions
+        .iter()
+        .map(|transaction| {
+            trace_tx(
+                tx_env(transaction),
+                block_env(&block.header),
+                state,
+                spec_id,
+                options,
+                false,
+            )
+        })
+        .collect()
+}
+
+/// Traces the transaction on top of the state without committing its changes
+/// As with calls, base fee and block gas limit checks are disabled
+pub fn trace_call(
+    tx: &GenericTransaction,
+    header: &BlockHeader,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    options: &TracingOptions,
+) -> Result<Trace, EvmError> {
+    trace_tx(
+        tx_env_from_generic(tx),
+        block_env(header),
+        state,
+        spec_id,
+        options,
+        true,
+    )
+}
+
+/// Returns the parity style traces of the transaction at the given index in the block
+/// The state must be the one resulting from the block's parent
+pub fn localized_transaction_traces(
+    block: &Block,
+    index: usize,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<Vec<LocalizedTransactionTrace>, EvmError> {
+    let transaction = replay_until(block, index, state, spec_id)?;
+    let trace_types = HashSet::from([TraceType::Trace]);
+    let results = trace_tx_parity(transaction, &block.header, state, spec_id, &trace_types)?;
+    Ok(localize(results.trace, block, index))
+}
+
+/// Returns the parity style traces of every transaction in the block, in order
+/// The state must be the one resulting from the block's parent
+pub fn localized_block_traces(
+    block: &Block,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<Vec<LocalizedTransactionTrace>, EvmError> {
+    start_block(&block.header, state, spec_id)?;
+    let trace_types = HashSet::from([TraceType::Trace]);
+    let mut traces = Vec::new();
+    for (index, transaction) in block.body.transactions.iter().enumerate() {
+        let results = trace_tx_parity(transaction, &block.header, state, spec_id, &trace_types)?;
+        traces.extend(localize(results.trace, block, index));
+    }
+    Ok(traces)
+}
+
+/// Replays the transaction at the given index in the block, returning the requested trace types
+/// The state must be the one resulting from the block's parent
+pub fn replay_transaction(
+    block: &Block,
+    index: usize,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    trace_types: &HashSet<TraceType>,
+) -> Result<TraceResults, EvmError> {
+    let transaction = replay_until(block, index, state, spec_id)?;
+    trace_tx_parity(transaction, &block.header, state, spec_id, trace_types)
+}
+
+/// Replays every transaction in the block, returning the requested trace types for each of them
+/// The state must be the one resulting from the block's parent
+pub fn replay_block_transactions(
+    block: &Block,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    trace_types: &HashSet<TraceType>,
+) -> Result<Vec<TraceResultsWithTransactionHash>, EvmError> {
+    start_block(&block.header, state, spec_id)?;
+    block
+        .body
+        .transactions
+        .iter()
+        .map(|transaction| {
+            let full_trace =
+                trace_tx_parity(transaction, &block.header, state, spec_id, trace_types)?;
+            Ok(TraceResultsWithTransactionHash {
+                full_trace,
+                transaction_hash: B256::from(transaction.compute_hash().0),
+            })
+        })
+        .collect()
+}
+
+/// Returns the address that originated the traced action, and the one it was directed to if any
+/// For contract creations, the receiver is the created contract
+pub fn trace_addresses(trace: &TransactionTrace) -> (Address, Option<Address>) {
+    let (from, to) = match &trace.action {
+        Action::Call(call) => (call.from, Some(call.to)),
+        Action::Create(create) => (
+            create.from,
+            match &trace.result {
+                Some(TraceOutput::Create(output)) => Some(output.address),
+                _ => None,
+            },
+        ),
+        Action::Selfdestruct(selfdestruct) => {
+            (selfdestruct.address, Some(selfdestruct.refund_address))
+        }
+        Action::Reward(reward) => (reward.author, None),
+    };
+    (
+        Address::from_slice(from.as_slice()),
+        to.map(|to| Address::from_slice(to.as_slice())),
+    )
+}
+
+// Runs the system calls and the transactions that precede the one at the given index in the block
+// Returns the transaction at the index
+fn replay_until<'a>(
+    block: &'a Block,
+    index: usize,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<&'a Transaction, EvmError> {
+    let transaction = block
+        .body
+        .transactions
+        .get(index)
+        .ok_or_else(|| EvmError::Custom(format!("Block has no transaction {index}")))?;
+    start_block(&block.header, state, spec_id)?;
+    for previous in block.body.transactions.iter().take(index) {
+        execute_tx(previous, &block.header, state, spec_id)?;
+    }
+    Ok(transaction)
+}
+
+// Adds the position of the transaction in the block to its traces
+fn localize(
+    traces: Vec<TransactionTrace>,
+    block: &Block,
+    index: usize,
+) -> Vec<LocalizedTransactionTrace> {
+    let block_hash = B256::from(block.header.compute_block_hash().0);
+    let transaction_hash = B256::from(block.body.transactions[index].compute_hash().0);
+    traces
+        .into_iter()
+        .map(|trace| LocalizedTransactionTrace {
+            trace,
+            block_hash: Some(block_hash),
+            block_number: Some(block.header.number),
+            transaction_hash: Some(transaction_hash),
+            transaction_position: Some(index as u64),
+        })
+        .collect()
+}
+
+// Runs the system calls done at the start of every block
+fn start_block(
+    header: &BlockHeader,
+    state: &mut EvmState,
+    spec_id: SpecId,
+) -> Result<(), EvmError> {
+    if header.parent_beacon_block_root.is_some() && spec_id == SpecId::CANCUN {
+        beacon_root_contract_call(state, header, spec_id)?;
+    }
+    Ok(())
+}
+
+/// Runs the transaction with the tracer selected in the options and returns its trace
+/// Changes are committed to the state unless the transaction is a call
+fn trace_tx(
+    tx_env: TxEnv,
+    block_env: BlockEnv,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    options: &TracingOptions,
+    is_call: bool,
+) -> Result<Trace, EvmError> {
+    let config = options.config.clone();
+    let tracer_config = options.tracer_config.clone();
+    let (trace, result) = match &options.tracer {
+        // The struct logger is used if no tracer is given
+        None => {
+            let mut inspector =
+                TracingInspector::new(TracingInspectorConfig::from_geth_config(&config));
+            let result = inspect(tx_env, block_env, state, spec_id, &mut inspector, is_call)?;
+            let gas_used = result.result.gas_used();
+            let output = result.result.output().cloned().unwrap_or_default();
+            let frame = inspector
+                .into_geth_builder()
+                .geth_traces(gas_used, output, config);
+            (frame.into(), result)
+        }
+        Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::CallTracer)) => {
+            let call_config = tracer_config
+                .into_call_config()
+                .map_err(|error| EvmError::Custom(format!("Invalid tracer config: {error}")))?;
+            let mut inspector =
+                TracingInspector::new(TracingInspectorConfig::from_geth_call_config(&call_config));
+            let result = inspect(tx_env, block_env, state, spec_id, &mut inspector, is_call)?;
+            let frame = inspector
+                .into_geth_builder()
+                .geth_call_traces(call_config, result.result.gas_used());
+            (frame.into(), result)
+        }
+        Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::PreStateTracer)) => {
+            let prestate_config = tracer_config
+                .into_pre_state_config()
+                .map_err(|error| EvmError::Custom(format!("Invalid tracer config: {error}")))?;
+            let mut inspector = TracingInspector::new(
+                TracingInspectorConfig::from_geth_prestate_config(&prestate_config),
+            );
+            let result = inspect(tx_env, block_env, state, spec_id, &mut inspector, is_call)?;
+            // The changes are not committed yet, so the state still holds the values prior to the transaction
+            let frame = inspector.into_geth_builder().geth_prestate_traces(
+                &result,
+                prestate_config,
+                StateRef(RefCell::new(&mut state.0)),
+            )?;
+            (frame.into(), result)
+        }
+        Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::FourByteTracer)) => {
+            let mut inspector = FourByteInspector::default();
+            let result = inspect(tx_env, block_env, state, spec_id, &mut inspector, is_call)?;
+            (FourByteFrame::from(inspector).into(), result)
+        }
+        Some(GethDebugTracerType::BuiltInTracer(GethDebugBuiltInTracerType::NoopTracer)) => {
+            let result = inspect(
+                tx_env,
+                block_env,
+                state,
+                spec_id,
+                &mut revm::inspectors::NoOpInspector,
+                is_call,
+            )?;
+            (NoopFrame::default().into(), result)
+        }
+        Some(tracer) => return Err(EvmError::Custom(format!("Unsupported tracer: {tracer:?}"))),
+    };
+    if !is_call {
+        state.0.commit(result.state);
+    }
+    Ok(trace)
+}
+
+/// Runs the transaction with an inspector configured for the requested trace types, committing its changes
+fn trace_tx_parity(
+    transaction: &Transaction,
+    header: &BlockHeader,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    trace_types: &HashSet<TraceType>,
+) -> Result<TraceResults, EvmError> {
+    let mut inspector =
+        TracingInspector::new(TracingInspectorConfig::from_parity_config(trace_types));
+    let result = inspect(
+        tx_env(transaction),
+        block_env(header),
+        state,
+        spec_id,
+        &mut inspector,
+        false,
+    )?;
+    // The state diff is built by comparing the changes against the state prior to the transaction
+    let results = inspector
+        .into_parity_builder()
+        .into_trace_results_with_state(
+            &result,
+            trace_types,
+            StateRef(RefCell::new(&mut state.0)),
+        )?;
+    state.0.commit(result.state);
+    Ok(results)
+}
+
+/// Runs the transaction with the inspector attached, without committing its changes
+fn inspect<I>(
+    tx_env: TxEnv,
+    block_env: BlockEnv,
+    state: &mut EvmState,
+    spec_id: SpecId,
+    inspector: &mut I,
+    is_call: bool,
+) -> Result<ResultAndState, EvmError>
+where
+    I: for<'db> Inspector<&'db mut State<StoreWrapper>>,
+{
+    let chain_id = state.database().get_chain_id()?.map(|ci| ci.low_u64());
+    let mut evm = Evm::builder()
+        .with_db(&mut state.0)
+        .with_block_env(block_env)
+        .with_tx_env(tx_env)
+        .with_spec_id(spec_id)
+        .modify_cfg_env(|cfg| {
+            if let Some(chain_id) = chain_id {
+                cfg.chain_id = chain_id
+            }
+            cfg.disable_base_fee = is_call;
+            cfg.disable_block_gas_limit = is_call;
+        })
+        .with_external_context(inspector)
+        .append_handler_register(inspector_handle_register)
+        .build();
+    evm.transact().map_err(EvmError::from)
+}
+
+/// Gives read access to the state through a shared reference, as needed to build prestate traces
+struct StateRef<'a>(RefCell<&'a mut State<StoreWrapper>>);
+
+impl DatabaseRef for StateRef<'_> {
+    type Error = StoreError;
+
+    fn basic_ref(&self, address: RevmAddress) -> Result<Option<RevmAccountInfo>, Self::Error> {
+        self.0.borrow_mut().basic(address)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.0.borrow_mut().code_by_hash(code_hash)
+    }
+
+    fn storage_ref(&self, address: RevmAddress, index: RevmU256) -> Result<RevmU256, Self::Error> {
+        self.0.borrow_mut().storage(address, index)
+    }
+
+    fn block_hash_ref(&self, number: RevmU256) -> Result<B256, Self::Error> {
+        self.0.borrow_mut().block_hash(number)
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    io,
     net::SocketAddr,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -8,15 +9,20 @@ use discv4::{Endpoint, FindNodeMessage, Message, Packet, PingMessage, PongMessag
 use ethereum_rust_core::{H256, H512};
 use k256::{
     ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey},
-    elliptic_curve::{rand_core::OsRng, sec1::ToEncodedPoint, PublicKey},
+    elliptic_curve::{sec1::ToEncodedPoint, PublicKey},
     SecretKey,
 };
 use kademlia::{KademliaTable, PeerData};
-use rlpx::handshake::RLPxLocalClient;
+use node::{NetworkHandle, PeerInfo};
+use rlpx::{
+    connection::RLPxConnection,
+    handshake::RLPxLocalClient,
+    utils::{id2pubkey, pubkey2id},
+};
 use sha3::{Digest, Keccak256};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    net::{TcpSocket, UdpSocket},
+    net::{TcpSocket, TcpStream, UdpSocket},
     try_join,
 };
 use tracing::{info, warn};
@@ -24,17 +30,26 @@ use tracing::{info, warn};
 pub mod bootnode;
 pub(crate) mod discv4;
 pub(crate) mod kademlia;
+pub mod node;
 pub mod rlpx;
 
+pub use rlpx::connection::SUPPORTED_CAPABILITIES;
+
 const MAX_DISC_PACKET_SIZE: usize = 1280;
 
-pub async fn start_network(udp_addr: SocketAddr, tcp_addr: SocketAddr, bootnodes: Vec<BootNode>) {
+pub async fn start_network(network: NetworkHandle, bootnodes: Vec<BootNode>) {
+    let udp_addr = network.local_node().udp_address;
+    let tcp_addr = network.local_node().tcp_address;
     info!("Starting discovery service at {udp_addr}");
     info!("Listening for requests at {tcp_addr}");
-    let signer = SigningKey::random(&mut OsRng);
-
-    let discovery_handle = tokio::spawn(discover_peers(udp_addr, signer.clone(), bootnodes));
-    let server_handle = tokio::spawn(serve_requests(tcp_addr, signer));
+    info!("Local node: {}", network.local_node().enode_url());
+
+    let discovery_handle = tokio::spawn(discover_peers(
+        udp_addr,
+        network.signer().clone(),
+        bootnodes,
+    ));
+    let server_handle = tokio::spawn(serve_requests(tcp_addr, network));
     try_join!(discovery_handle, server_handle).unwrap();
 }
 
@@ -152,8 +167,8 @@ async fn pong(socket: &UdpSocket, to_addr: SocketAddr, ping_hash: H256, signer:
     socket.send_to(&buf, to_addr).await.unwrap();
 }
 
-async fn serve_requests(tcp_addr: SocketAddr, signer: SigningKey) {
-    let secret_key: SecretKey = signer.clone().into();
+async fn serve_requests(tcp_addr: SocketAddr, network: NetworkHandle) {
+    let signer = network.signer().clone();
     let tcp_socket = TcpSocket::new_v4().unwrap();
     tcp_socket.bind(tcp_addr).unwrap();
 
@@ -197,30 +212,80 @@ async fn serve_requests(tcp_addr: SocketAddr, signer: SigningKey) {
 
     let peer_pk = VerifyingKey::recover_from_prehash(&digest, signature, rid).unwrap();
 
-    let mut client = RLPxLocalClient::random();
-    let mut auth_message = vec![];
-    client.encode_auth_message(&secret_key, &peer_pk.into(), &mut auth_message);
-
     // NOTE: for some reason kurtosis peers don't publish their active TCP port
     let tcp_addr = endpoint
         .tcp_address()
         .unwrap_or(str_tcp_addr.parse().unwrap());
 
-    let mut stream = TcpSocket::new_v4()
-        .unwrap()
-        .connect(tcp_addr)
-        .await
-        .unwrap();
+    connect_to_peer(network, pubkey2id(&peer_pk.into()), tcp_addr).await;
+}
+
+/// Connects to the peer and keeps the connection open until the peer is removed or closes it
+pub(crate) async fn connect_to_peer(network: NetworkHandle, node_id: H512, tcp_addr: SocketAddr) {
+    let Some(peer_pk) = id2pubkey(node_id) else {
+        warn!("Invalid node id {node_id:#x}");
+        return;
+    };
+    let (connection, mut stream) = match handshake(network.signer(), &peer_pk, tcp_addr).await {
+        Ok(connection) => connection,
+        Err(error) => {
+            warn!("Failed to connect to peer {node_id:#x} at {tcp_addr}: {error}");
+            return;
+        }
+    };
+    let disconnect = network.peer_connected(PeerInfo {
+        node_id,
+        address: tcp_addr,
+        client_id: connection.client_id,
+        capabilities: connection.capabilities,
+    });
+    info!("Connected to peer {node_id:#x} at {tcp_addr}");
+
+    // TODO: messages after the Hello must be snappy compressed, until then they are discarded
+    let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
+    loop {
+        tokio::select! {
+            _ = disconnect.notified() => break,
+            read = stream.read(&mut buf) => {
+                if matches!(read, Ok(0) | Err(_)) {
+                    network.peer_disconnected(node_id, &disconnect);
+                    break;
+                }
+            }
+        }
+    }
+    info!("Disconnected from peer {node_id:#x}");
+}
 
-    stream.write_all(&auth_message).await.unwrap();
+/// Performs the RLPx handshake and exchanges Hello messages with the peer
+async fn handshake(
+    signer: &SigningKey,
+    peer_pk: &PublicKey<k256::Secp256k1>,
+    tcp_addr: SocketAddr,
+) -> io::Result<(RLPxConnection, TcpStream)> {
+    let secret_key: SecretKey = signer.clone().into();
+    let mut client = RLPxLocalClient::random();
+    let mut auth_message = vec![];
+    client.encode_auth_message(&secret_key, peer_pk, &mut auth_message);
+
+    let mut stream = TcpSocket::new_v4()?.connect(tcp_addr).await?;
+
+    stream.write_all(&auth_message).await?;
     info!("Sent auth message correctly!");
     // Read the ack message's size
-    stream.read_exact(&mut buf[..2]).await.unwrap();
-    let auth_data = buf[..2].try_into().unwrap();
+    let mut buf = vec![0; MAX_DISC_PACKET_SIZE];
+    stream.read_exact(&mut buf[..2]).await?;
+    let auth_data = [buf[0], buf[1]];
     let msg_size = u16::from_be_bytes(auth_data) as usize;
+    if msg_size + 2 > buf.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Ack message too large: {msg_size} bytes"),
+        ));
+    }
 
     // Read the rest of the ack message
-    stream.read_exact(&mut buf[2..msg_size + 2]).await.unwrap();
+    stream.read_exact(&mut buf[2..msg_size + 2]).await?;
 
     let msg = &mut buf[2..msg_size + 2];
     let mut pending_conn = client.decode_ack_message(&secret_key, msg, auth_data);
@@ -228,11 +293,10 @@ async fn serve_requests(tcp_addr: SocketAddr, signer: SigningKey) {
 
     pending_conn
         .send_hello(&PublicKey::from(signer.verifying_key()), &mut stream)
-        .await;
+        .await?;
 
-    let _conn = pending_conn.receive_hello(&mut stream).await;
+    let connection = pending_conn.receive_hello(&mut stream).await?;
 
     info!("Completed Hello roundtrip!");
-
-    // TODO: messages after the Hello must be snappy compressed
+    Ok((connection, stream))
 }
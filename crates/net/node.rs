@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use ethereum_rust_core::H512;
+use k256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng, PublicKey};
+use tokio::sync::Notify;
+
+use crate::{bootnode::BootNode, connect_to_peer, rlpx::utils::pubkey2id};
+
+/// Identity of the local node along with the addresses it listens at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalNode {
+    pub node_id: H512,
+    pub tcp_address: SocketAddr,
+    pub udp_address: SocketAddr,
+}
+
+impl LocalNode {
+    /// Returns the node's url with the format "enode://nodeID@IPaddress:port"
+    /// The discovery port is added as a query parameter if it differs from the listening one
+    pub fn enode_url(&self) -> String {
+        let mut url = format!("enode://{:x}@{}", self.node_id, self.tcp_address);
+        if self.udp_address.port() != self.tcp_address.port() {
+            url.push_str(&format!("?discport={}", self.udp_address.port()));
+        }
+        url
+    }
+}
+
+/// Peer the node is connected to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerInfo {
+    pub node_id: H512,
+    pub address: SocketAddr,
+    /// Client software the peer announced
+    pub client_id: String,
+    /// Capabilities the peer announced, as (protocol name, version)
+    pub capabilities: Vec<(String, u8)>,
+}
+
+struct ConnectedPeer {
+    info: PeerInfo,
+    // Notified to close the connection to the peer
+    disconnect: Arc<Notify>,
+}
+
+/// Handle to the networking service, shared with other services to inspect and manage its peers
+#[derive(Clone)]
+pub struct NetworkHandle {
+    local_node: LocalNode,
+    signer: SigningKey,
+    peers: Arc<Mutex<HashMap<H512, ConnectedPeer>>>,
+}
+
+impl NetworkHandle {
+    /// Creates the handle for a node listening at the given addresses, with a new random node key
+    pub fn new(tcp_address: SocketAddr, udp_address: SocketAddr) -> Self {
+        let signer = SigningKey::random(&mut OsRng);
+        let node_id = pubkey2id(&PublicKey::from(signer.verifying_key()));
+        Self {
+            local_node: LocalNode {
+                node_id,
+                tcp_address,
+                udp_address,
+            },
+            signer,
+            peers: Default::default(),
+        }
+    }
+
+    pub fn local_node(&self) -> &LocalNode {
+        &self.local_node
+    }
+
+    /// Returns the peers the node is currently connected to
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        self.peers
+            .lock()
+            .unwrap()
+            .values()
+            .map(|peer| peer.info.clone())
+            .collect()
+    }
+
+    /// Connects to the node in the background, the peer is listed once the RLPx handshake completes
+    /// Must be called from within a tokio runtime
+    pub fn add_peer(&self, node: BootNode) {
+        tokio::spawn(connect_to_peer(
+            self.clone(),
+            node.node_id,
+            node.socket_address,
+        ));
+    }
+
+    /// Closes the connection to the peer, returns false if the node was not connected to it
+    pub fn remove_peer(&self, node_id: H512) -> bool {
+        match self.peers.lock().unwrap().remove(&node_id) {
+            Some(peer) => {
+                peer.disconnect.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn signer(&self) -> &SigningKey {
+        &self.signer
+    }
+
+    /// Lists the peer as connected, replacing any previous connection to it
+    /// Returns the notifier used to close the connection when the peer is removed
+    pub(crate) fn peer_connected(&self, info: PeerInfo) -> Arc<Notify> {
+        let disconnect = Arc::new(Notify::new());
+        let previous = self.peers.lock().unwrap().insert(
+            info.node_id,
+            ConnectedPeer {
+                info,
+                disconnect: disconnect.clone(),
+            },
+        );
+        if let Some(previous) = previous {
+            previous.disconnect.notify_one();
+        }
+        disconnect
+    }
+
+    /// Removes the peer from the list if its connection was closed by the peer
+    pub(crate) fn peer_disconnected(&self, node_id: H512, disconnect: &Arc<Notify>) {
+        let mut peers = self.peers.lock().unwrap();
+        // The peer may have reconnected since
+        if peers
+            .get(&node_id)
+            .is_some_and(|peer| Arc::ptr_eq(&peer.disconnect, disconnect))
+        {
+            peers.remove(&node_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn enode_url() {
+        let node = LocalNode {
+            node_id: H512::from_low_u64_be(1),
+            tcp_address: SocketAddr::from_str("127.0.0.1:30303").unwrap(),
+            udp_address: SocketAddr::from_str("127.0.0.1:30303").unwrap(),
+        };
+        let url = node.enode_url();
+        assert_eq!(url, format!("enode://{}1@127.0.0.1:30303", "0".repeat(127)));
+        assert_eq!(
+            BootNode::from_str(&url).unwrap(),
+            BootNode {
+                node_id: node.node_id,
+                socket_address: node.tcp_address,
+            }
+        );
+        let node = LocalNode {
+            udp_address: SocketAddr::from_str("127.0.0.1:30304").unwrap(),
+            ..node
+        };
+        assert!(node
+            .enode_url()
+            .ends_with("@127.0.0.1:30303?discport=30304"));
+    }
+
+    #[test]
+    fn track_connected_peers() {
+        let address = SocketAddr::from_str("127.0.0.1:30303").unwrap();
+        let network = NetworkHandle::new(address, address);
+        let info = PeerInfo {
+            node_id: H512::from_low_u64_be(1),
+            address,
+            client_id: "peer".to_string(),
+            capabilities: vec![("p2p".to_string(), 5)],
+        };
+        let first = network.peer_connected(info.clone());
+        // Reconnecting closes the previous connection
+        let second = network.peer_connected(info.clone());
+        network.peer_disconnected(info.node_id, &first);
+        assert_eq!(network.peers(), vec![info.clone()]);
+        network.peer_disconnected(info.node_id, &second);
+        assert!(network.peers().is_empty());
+        network.peer_connected(info.clone());
+        assert!(network.remove_peer(info.node_id));
+        assert!(!network.remove_peer(info.node_id));
+    }
+}
@@ -14,10 +14,11 @@ use ethereum_rust_core::{
 };
 use k256::PublicKey;
 use sha3::{Digest, Keccak256};
-use std::pin::pin;
+use std::{io, pin::pin};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
-const SUPPORTED_CAPABILITIES: [(&str, u8); 1] = [("p2p", 5)];
+/// Capabilities announced to peers, as (protocol name, version)
+pub const SUPPORTED_CAPABILITIES: [(&str, u8); 1] = [("p2p", 5)];
 
 pub(crate) type Aes256Ctr64BE = ctr::Ctr64BE<aes::Aes256>;
 
@@ -25,7 +26,10 @@ pub(crate) type Aes256Ctr64BE = ctr::Ctr64BE<aes::Aes256>;
 pub(crate) struct RLPxConnection {
     #[allow(unused)]
     state: RLPxState,
-    // ...capabilities information
+    /// Client software the peer announced in its Hello message
+    pub client_id: String,
+    /// Capabilities the peer announced in its Hello message, as (protocol name, version)
+    pub capabilities: Vec<(String, u8)>,
 }
 
 /// RLPx connection which is pending the receive of a Hello message.
@@ -38,7 +42,11 @@ impl RLPxConnectionPending {
         Self { state }
     }
 
-    pub async fn send_hello<S: AsyncWrite>(&mut self, node_pk: &PublicKey, stream: S) {
+    pub async fn send_hello<S: AsyncWrite>(
+        &mut self,
+        node_pk: &PublicKey,
+        stream: S,
+    ) -> io::Result<()> {
         let mut stream = pin!(stream);
 
         let egress_aes = &mut self.state.egress_aes;
@@ -86,7 +94,7 @@ impl RLPxConnectionPending {
         header.extend_from_slice(&header_mac[..16]);
 
         // Write header
-        stream.write_all(&header).await.unwrap();
+        stream.write_all(&header).await?;
 
         // Pad to next multiple of 16
         frame_data.resize(frame_data.len().next_multiple_of(16), 0);
@@ -94,7 +102,7 @@ impl RLPxConnectionPending {
         let frame_ciphertext = frame_data;
 
         // Send frame
-        stream.write_all(&frame_ciphertext).await.unwrap();
+        stream.write_all(&frame_ciphertext).await?;
 
         // Compute frame-mac
         egress_mac.update(&frame_ciphertext);
@@ -110,10 +118,10 @@ impl RLPxConnectionPending {
         let frame_mac = egress_mac.clone().finalize();
 
         // Send frame-mac
-        stream.write_all(&frame_mac[..16]).await.unwrap();
+        stream.write_all(&frame_mac[..16]).await
     }
 
-    pub async fn receive_hello<S: AsyncRead>(self, stream: S) -> RLPxConnection {
+    pub async fn receive_hello<S: AsyncRead>(self, stream: S) -> io::Result<RLPxConnection> {
         let mut stream = pin!(stream);
 
         let Self { mut state } = self;
@@ -125,7 +133,7 @@ impl RLPxConnectionPending {
 
         // Receive the hello message's frame header
         let mut frame_header = [0; 32];
-        stream.read_exact(&mut frame_header).await.unwrap();
+        stream.read_exact(&mut frame_header).await?;
         // Both are padded to the block's size (16 bytes)
         let (header_ciphertext, header_mac) = frame_header.split_at_mut(16);
 
@@ -160,7 +168,7 @@ impl RLPxConnectionPending {
         // Receive the hello message
         let padded_size = frame_size.next_multiple_of(16);
         let mut frame_data = vec![0; padded_size + 16];
-        stream.read_exact(&mut frame_data).await.unwrap();
+        stream.read_exact(&mut frame_data).await?;
         let (frame_ciphertext, frame_mac) = frame_data.split_at_mut(padded_size);
 
         // check MAC
@@ -193,13 +201,16 @@ impl RLPxConnectionPending {
 
         assert_eq!(protocol_version, 5, "only protocol version 5 is supported");
 
-        let (_client_id, decoder): (String, _) = decoder.decode_field("clientId").unwrap();
-        // TODO: store client id for debugging purposes
+        let (client_id, decoder): (String, _) = decoder.decode_field("clientId").unwrap();
 
         // [[cap1, capVersion1], [cap2, capVersion2], ...]
-        let (_capabilities, decoder): (Vec<(Bytes, u64)>, _) =
+        let (capabilities, decoder): (Vec<(Bytes, u64)>, _) =
             decoder.decode_field("capabilities").unwrap();
         // TODO: derive shared capabilities for further communication
+        let capabilities = capabilities
+            .into_iter()
+            .map(|(name, version)| (String::from_utf8_lossy(&name).into_owned(), version as u8))
+            .collect();
 
         // This field should be ignored
         let (_listen_port, decoder): (u16, _) = decoder.decode_field("listenPort").unwrap();
@@ -210,7 +221,11 @@ impl RLPxConnectionPending {
         // Implementations must ignore any additional list elements
         let _padding = decoder.finish_unchecked();
 
-        RLPxConnection { state }
+        Ok(RLPxConnection {
+            state,
+            client_id,
+            capabilities,
+        })
     }
 }
 
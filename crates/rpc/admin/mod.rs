@@ -1,21 +1,146 @@
-use serde_json::{json, Value};
+use std::{net::SocketAddr, str::FromStr};
 
-use crate::utils::RpcErr;
+use ethereum_rust_core::H512;
+use ethereum_rust_net::{
+    bootnode::BootNode,
+    node::{NetworkHandle, PeerInfo},
+    SUPPORTED_CAPABILITIES,
+};
+use serde_json::{json, Map, Value};
+use sha3::{Digest, Keccak256};
+use tracing::info;
 
-pub fn node_info() -> Result<Value, RpcErr> {
+use crate::{utils::RpcErr, web3::client_version};
+
+pub struct PeerRequest {
+    pub node: BootNode,
+}
+
+impl PeerRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<PeerRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        let url: String = serde_json::from_value(params[0].clone()).ok()?;
+        Some(PeerRequest {
+            node: parse_enode_url(&url)?,
+        })
+    }
+}
+
+// Parses an url with the format "enode://nodeID@IPaddress:port", ignoring any query parameters
+fn parse_enode_url(url: &str) -> Option<BootNode> {
+    let url = url.strip_prefix("enode://")?;
+    let url = url.split_once('?').map_or(url, |(url, _)| url);
+    let (node_id, address) = url.split_once('@')?;
+    if node_id.len() != 128 {
+        return None;
+    }
+    Some(BootNode {
+        node_id: H512::from_str(node_id).ok()?,
+        socket_address: SocketAddr::from_str(address).ok()?,
+    })
+}
+
+pub fn node_info(network: NetworkHandle) -> Result<Value, RpcErr> {
+    let local_node = network.local_node();
     Ok(json!({
-        "enode": "enode://pubkey@ip:port",
-        "id": "pubkey",
-        "name": "node",
+        "enode": local_node.enode_url(),
+        "id": node_hash(local_node.node_id),
+        "ip": local_node.tcp_address.ip(),
+        "listenAddr": local_node.tcp_address,
+        "name": client_version(),
         "ports": {
-            "discovery": 1234,
-            "listener": 1234,
-        },
-        "protocols": {
-            "eth": {
-                "network": 1234,
-                "version": 1234,
-            },
+            "discovery": local_node.udp_address.port(),
+            "listener": local_node.tcp_address.port(),
         },
+        "protocols": protocols(
+            SUPPORTED_CAPABILITIES
+                .iter()
+                .map(|(name, version)| (name.to_string(), *version)),
+        ),
     }))
 }
+
+pub fn peers(network: NetworkHandle) -> Result<Value, RpcErr> {
+    let peers: Vec<Value> = network.peers().into_iter().map(peer_info).collect();
+    Ok(Value::Array(peers))
+}
+
+/// Connects to the node in the background, the peer is listed by `admin_peers` once connected
+pub fn add_peer(request: &PeerRequest, network: NetworkHandle) -> Result<Value, RpcErr> {
+    info!(
+        "Requested connection to peer {}",
+        request.node.socket_address
+    );
+    network.add_peer(request.node);
+    Ok(Value::Bool(true))
+}
+
+pub fn remove_peer(request: &PeerRequest, network: NetworkHandle) -> Result<Value, RpcErr> {
+    info!(
+        "Requested disconnection from peer {:#x}",
+        request.node.node_id
+    );
+    network.remove_peer(request.node.node_id);
+    Ok(Value::Bool(true))
+}
+
+fn peer_info(peer: PeerInfo) -> Value {
+    let caps: Vec<String> = peer
+        .capabilities
+        .iter()
+        .map(|(name, version)| format!("{name}/{version}"))
+        .collect();
+    json!({
+        "enode": format!("enode://{:x}@{}", peer.node_id, peer.address),
+        "id": node_hash(peer.node_id),
+        "name": peer.client_id,
+        "caps": caps,
+        "network": {
+            "remoteAddress": peer.address,
+            // Connections are only started by this node for now
+            "inbound": false,
+        },
+        "protocols": protocols(peer.capabilities),
+    })
+}
+
+// Nodes are identified by the hash of their public key, as in their node records
+fn node_hash(node_id: H512) -> String {
+    hex::encode(Keccak256::digest(node_id))
+}
+
+fn protocols(capabilities: impl IntoIterator<Item = (String, u8)>) -> Value {
+    let protocols: Map<String, Value> = capabilities
+        .into_iter()
+        .map(|(name, version)| (name, json!({ "version": version })))
+        .collect();
+    Value::Object(protocols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_peer_request() {
+        let node_id = "d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666";
+        let url = format!("enode://{node_id}@18.138.108.67:30303?discport=30304");
+        let request = PeerRequest::parse(&Some(vec![json!(url)])).unwrap();
+        assert_eq!(request.node.node_id, H512::from_str(node_id).unwrap());
+        assert_eq!(
+            request.node.socket_address,
+            SocketAddr::from_str("18.138.108.67:30303").unwrap()
+        );
+        for url in [
+            format!("{node_id}@18.138.108.67:30303"),
+            format!("enode://{node_id}"),
+            format!("enode://{}@18.138.108.67:30303", &node_id[2..]),
+            format!("enode://{node_id}@18.138.108.67"),
+        ] {
+            assert!(PeerRequest::parse(&Some(vec![json!(url)])).is_none());
+        }
+    }
+}
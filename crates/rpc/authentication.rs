@@ -0,0 +1,183 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ethereum_rust_core::Bytes;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+/// Maximum difference (in seconds) allowed between the token's issued-at claim and the current time
+const IAT_MAX_DRIFT_SECS: u64 = 60;
+
+/// Length in bytes of the secret shared with the consensus client
+pub const JWT_SECRET_LENGTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum AuthenticationError {
+    MissingAuthentication,
+    InvalidToken,
+    InvalidSignature,
+    InvalidIssuedAtClaim,
+    InvalidSecret,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+struct JwtClaims {
+    iat: u64,
+}
+
+/// Authenticates a request to the Auth-RPC endpoint as defined in the
+/// [Engine API spec](https://github.com/ethereum/execution-apis/blob/main/src/engine/authentication.md)
+/// The request must carry an HS256 JWT signed with the shared secret as a bearer token,
+/// with an issued-at claim within 60 seconds of the current time
+pub fn authenticate(secret: &Bytes, auth_header: Option<&str>) -> Result<(), AuthenticationError> {
+    let token = auth_header
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .ok_or(AuthenticationError::MissingAuthentication)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| AuthenticationError::InvalidIssuedAtClaim)?
+        .as_secs();
+    validate_jwt(secret, token.trim(), now)
+}
+
+/// Decodes the hex encoded secret shared with the consensus client, with an optional 0x prefix
+/// Fails unless the secret is exactly 32 bytes long
+pub fn decode_jwt_secret(secret: &str) -> Result<Bytes, AuthenticationError> {
+    let secret = secret.trim();
+    let secret = secret.strip_prefix("0x").unwrap_or(secret);
+    match hex::decode(secret) {
+        Ok(secret) if secret.len() == JWT_SECRET_LENGTH => Ok(secret.into()),
+        _ => Err(AuthenticationError::InvalidSecret),
+    }
+}
+
+fn validate_jwt(secret: &Bytes, token: &str, now: u64) -> Result<(), AuthenticationError> {
+    if secret.len() != JWT_SECRET_LENGTH {
+        return Err(AuthenticationError::InvalidSecret);
+    }
+    // A token has the form `header.claims.signature`
+    let (signed, signature) = token
+        .rsplit_once('.')
+        .ok_or(AuthenticationError::InvalidToken)?;
+    let (header, claims) = signed
+        .split_once('.')
+        .ok_or(AuthenticationError::InvalidToken)?;
+    // Check the signing algorithm
+    let header: JwtHeader = decode_part(header)?;
+    if header.alg != "HS256" {
+        return Err(AuthenticationError::InvalidToken);
+    }
+    // Check the signature over `header.claims`
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| AuthenticationError::InvalidToken)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .map_err(|_| AuthenticationError::InvalidSignature)?;
+    mac.update(signed.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| AuthenticationError::InvalidSignature)?;
+    // Check the issued-at claim
+    let claims: JwtClaims = decode_part(claims)?;
+    if claims.iat.abs_diff(now) > IAT_MAX_DRIFT_SECS {
+        return Err(AuthenticationError::InvalidIssuedAtClaim);
+    }
+    Ok(())
+}
+
+// Decodes a base64url encoded JSON part of a JWT
+fn decode_part<T: for<'de> Deserialize<'de>>(part: &str) -> Result<T, AuthenticationError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| AuthenticationError::InvalidToken)?;
+    serde_json::from_slice(&bytes).map_err(|_| AuthenticationError::InvalidToken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &Bytes, header: &str, claims: &str) -> String {
+        let signed = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(claims)
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(signed.as_bytes());
+        let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{signed}.{signature}")
+    }
+
+    #[test]
+    fn validate_jwt_tokens() {
+        let secret = Bytes::from(vec![0x42; 32]);
+        let now = 1_718_040_081;
+        let header = r#"{"alg":"HS256","typ":"JWT"}"#;
+        let valid = sign(&secret, header, &format!(r#"{{"iat":{}}}"#, now - 30));
+        assert_eq!(validate_jwt(&secret, &valid, now), Ok(()));
+        // Issued too long ago
+        let expired = sign(&secret, header, &format!(r#"{{"iat":{}}}"#, now - 61));
+        assert_eq!(
+            validate_jwt(&secret, &expired, now),
+            Err(AuthenticationError::InvalidIssuedAtClaim)
+        );
+        // Signed with another secret
+        let other_secret = Bytes::from(vec![0x24; 32]);
+        let forged = sign(&other_secret, header, &format!(r#"{{"iat":{now}}}"#));
+        assert_eq!(
+            validate_jwt(&secret, &forged, now),
+            Err(AuthenticationError::InvalidSignature)
+        );
+        // Unsupported algorithm
+        let none_alg = sign(&secret, r#"{"alg":"none"}"#, &format!(r#"{{"iat":{now}}}"#));
+        assert_eq!(
+            validate_jwt(&secret, &none_alg, now),
+            Err(AuthenticationError::InvalidToken)
+        );
+        // Missing bearer token
+        assert_eq!(
+            authenticate(&secret, Some(&valid)),
+            Err(AuthenticationError::MissingAuthentication)
+        );
+        assert_eq!(
+            authenticate(&secret, None),
+            Err(AuthenticationError::MissingAuthentication)
+        );
+        // Secret shorter than 32 bytes
+        let short_secret = Bytes::from(vec![0x42; 16]);
+        let valid = sign(&short_secret, header, &format!(r#"{{"iat":{now}}}"#));
+        assert_eq!(
+            validate_jwt(&short_secret, &valid, now),
+            Err(AuthenticationError::InvalidSecret)
+        );
+    }
+
+    #[test]
+    fn decode_jwt_secrets() {
+        let secret = "0x".to_string() + &"42".repeat(32) + "\n";
+        assert_eq!(decode_jwt_secret(&secret), Ok(Bytes::from(vec![0x42; 32])));
+        assert_eq!(
+            decode_jwt_secret(&"42".repeat(32)),
+            Ok(Bytes::from(vec![0x42; 32]))
+        );
+        // Too short, too long and not hex encoded secrets
+        assert_eq!(
+            decode_jwt_secret(&"42".repeat(16)),
+            Err(AuthenticationError::InvalidSecret)
+        );
+        assert_eq!(
+            decode_jwt_secret(&"42".repeat(33)),
+            Err(AuthenticationError::InvalidSecret)
+        );
+        assert_eq!(
+            decode_jwt_secret(&"zz".repeat(32)),
+            Err(AuthenticationError::InvalidSecret)
+        );
+    }
+}
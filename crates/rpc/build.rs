@@ -0,0 +1,18 @@
+use std::process::Command;
+
+// Exposes the first four bytes of the current commit hash as `GIT_COMMIT_PREFIX`,
+// which is reported to the consensus client by `engine_getClientVersionV1`
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .filter(|hash| hash.len() >= 8)
+        .map(|hash| hash[..8].to_string())
+        .unwrap_or_else(|| "00000000".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT_PREFIX={commit}");
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/refs");
+}
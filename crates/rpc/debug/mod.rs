@@ -0,0 +1,2 @@
+pub(crate) mod raw;
+pub(crate) mod trace;
@@ -0,0 +1,150 @@
+use ethereum_rust_core::{
+    rlp::encode::RLPEncode,
+    types::{Block, BlockNumber},
+    H256,
+};
+use ethereum_rust_storage::Store;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{eth::block::BlockIdentifierOrHash, utils::RpcErr};
+
+pub struct GetRawBlockRequest {
+    pub block: BlockIdentifierOrHash,
+}
+
+pub struct GetRawTransactionRequest {
+    pub transaction_hash: H256,
+}
+
+impl GetRawBlockRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetRawBlockRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        Some(GetRawBlockRequest {
+            block: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+impl GetRawTransactionRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetRawTransactionRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        Some(GetRawTransactionRequest {
+            transaction_hash: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+/// Returns the RLP encoding of the block's header
+pub fn get_raw_header(request: &GetRawBlockRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested raw header of block {}", request.block);
+    let Some(block_number) = resolve_block_number(&request.block, &storage)? else {
+        return Ok(Value::Null);
+    };
+    match storage.get_block_header(block_number) {
+        Ok(Some(header)) => Ok(encode_hex(header.encode_to_vec())),
+        Ok(_) => Ok(Value::Null),
+        _ => Err(RpcErr::Internal),
+    }
+}
+
+/// Returns the RLP encoding of the block, with its transactions encoded as in the block body
+pub fn get_raw_block(request: &GetRawBlockRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested raw block {}", request.block);
+    let Some(block_number) = resolve_block_number(&request.block, &storage)? else {
+        return Ok(Value::Null);
+    };
+    let header = storage.get_block_header(block_number);
+    let body = storage.get_block_body(block_number);
+    let (header, body) = match (header, body) {
+        (Ok(Some(header)), Ok(Some(body))) => (header, body),
+        // Block not found
+        (Ok(_), Ok(_)) => return Ok(Value::Null),
+        // DB error
+        _ => return Err(RpcErr::Internal),
+    };
+    Ok(encode_hex(Block { header, body }.encode_to_vec()))
+}
+
+/// Returns the EIP-2718 encoding of each receipt in the block
+pub fn get_raw_receipts(request: &GetRawBlockRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested raw receipts of block {}", request.block);
+    let Some(block_number) = resolve_block_number(&request.block, &storage)? else {
+        return Ok(Value::Null);
+    };
+    let body = match storage.get_block_body(block_number) {
+        Ok(Some(body)) => body,
+        Ok(_) => return Ok(Value::Null),
+        _ => return Err(RpcErr::Internal),
+    };
+    let mut receipts = Vec::with_capacity(body.transactions.len());
+    for index in 0..body.transactions.len() as u64 {
+        match storage.get_receipt(block_number, index) {
+            Ok(Some(receipt)) => receipts.push(encode_hex(receipt.encode_to_vec())),
+            Ok(_) => return Ok(Value::Null),
+            _ => return Err(RpcErr::Internal),
+        }
+    }
+    Ok(Value::Array(receipts))
+}
+
+/// Returns the EIP-2718 encoding of the transaction, as accepted by `eth_sendRawTransaction`
+pub fn get_raw_transaction(
+    request: &GetRawTransactionRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!("Requested raw transaction {:#x}", request.transaction_hash);
+    match storage.get_transaction_by_hash(request.transaction_hash) {
+        Ok(Some(transaction)) => Ok(encode_hex(transaction.encode_canonical_to_vec())),
+        Ok(_) => Ok(Value::Null),
+        _ => Err(RpcErr::Internal),
+    }
+}
+
+fn resolve_block_number(
+    block: &BlockIdentifierOrHash,
+    storage: &Store,
+) -> Result<Option<BlockNumber>, RpcErr> {
+    block
+        .resolve_block_number(storage)
+        .map_err(|_| RpcErr::Internal)
+}
+
+fn encode_hex(bytes: Vec<u8>) -> Value {
+    Value::String(format!("0x{}", hex::encode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::eth::block::{BlockIdentifier, BlockTag};
+
+    use super::*;
+
+    #[test]
+    fn parse_block_number_tag_or_hash() {
+        let request = GetRawBlockRequest::parse(&Some(vec![json!("0x10")])).unwrap();
+        assert!(matches!(
+            request.block,
+            BlockIdentifierOrHash::Identifier(BlockIdentifier::Number(16))
+        ));
+        let request = GetRawBlockRequest::parse(&Some(vec![json!("finalized")])).unwrap();
+        assert!(matches!(
+            request.block,
+            BlockIdentifierOrHash::Identifier(BlockIdentifier::Tag(BlockTag::Finalized))
+        ));
+        let hash = H256::repeat_byte(1);
+        let request = GetRawBlockRequest::parse(&Some(vec![json!(hash)])).unwrap();
+        assert!(
+            matches!(request.block, BlockIdentifierOrHash::Hash(block_hash) if block_hash == hash)
+        );
+        assert!(GetRawBlockRequest::parse(&Some(vec![json!("unknown")])).is_none());
+    }
+}
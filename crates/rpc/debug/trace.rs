@@ -0,0 +1,248 @@
+use ethereum_rust_core::{
+    types::{Block, BlockHash, BlockNumber, GenericTransaction},
+    H256,
+};
+use ethereum_rust_evm::{
+    evm_state_at_block, spec_id,
+    trace::{self, TracingOptions},
+    EvmState, SpecId,
+};
+use ethereum_rust_storage::Store;
+use serde_json::{json, Value};
+use tracing::info;
+
+use crate::{
+    eth::{
+        block::{resolve_block_number, BlockIdentifier},
+        transaction::resolve_header_and_spec,
+    },
+    utils::RpcErr,
+};
+
+pub struct TraceTransactionRequest {
+    pub transaction_hash: H256,
+    pub options: TracingOptions,
+}
+
+pub struct TraceBlockByNumberRequest {
+    pub block: BlockIdentifier,
+    pub options: TracingOptions,
+}
+
+pub struct TraceBlockByHashRequest {
+    pub block: BlockHash,
+    pub options: TracingOptions,
+}
+
+pub struct TraceCallRequest {
+    pub transaction: GenericTransaction,
+    pub block: BlockIdentifier,
+    pub options: TracingOptions,
+}
+
+impl TraceTransactionRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceTransactionRequest> {
+        let params = params.as_ref()?;
+        if params.is_empty() || params.len() > 2 {
+            return None;
+        };
+        Some(TraceTransactionRequest {
+            transaction_hash: serde_json::from_value(params[0].clone()).ok()?,
+            options: parse_options(params.get(1))?,
+        })
+    }
+}
+
+impl TraceBlockByNumberRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceBlockByNumberRequest> {
+        let params = params.as_ref()?;
+        if params.is_empty() || params.len() > 2 {
+            return None;
+        };
+        Some(TraceBlockByNumberRequest {
+            block: serde_json::from_value(params[0].clone()).ok()?,
+            options: parse_options(params.get(1))?,
+        })
+    }
+}
+
+impl TraceBlockByHashRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceBlockByHashRequest> {
+        let params = params.as_ref()?;
+        if params.is_empty() || params.len() > 2 {
+            return None;
+        };
+        Some(TraceBlockByHashRequest {
+            block: serde_json::from_value(params[0].clone()).ok()?,
+            options: parse_options(params.get(1))?,
+        })
+    }
+}
+
+impl TraceCallRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceCallRequest> {
+        let params = params.as_ref()?;
+        if params.len() < 2 || params.len() > 3 {
+            return None;
+        };
+        Some(TraceCallRequest {
+            transaction: serde_json::from_value(params[0].clone()).ok()?,
+            block: serde_json::from_value(params[1].clone()).ok()?,
+            options: parse_options(params.get(2))?,
+        })
+    }
+}
+
+// Transactions are traced with the struct logger if no options are given
+fn parse_options(value: Option<&Value>) -> Option<TracingOptions> {
+    let options = match value {
+        Some(value) => serde_json::from_value(value.clone()).ok()?,
+        None => TracingOptions::default(),
+    };
+    trace::is_supported(&options).then_some(options)
+}
+
+/// Re-executes the block the transaction was included in up to the transaction, and traces it
+pub fn trace_transaction(
+    request: &TraceTransactionRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!(
+        "Requested trace of transaction {:#x}",
+        request.transaction_hash
+    );
+    let (block_number, index) = match storage.get_transaction_location(request.transaction_hash) {
+        Ok(Some(location)) => location,
+        // Transaction not found
+        Ok(_) => return Err(RpcErr::BadParams),
+        // DB error
+        _ => return Err(RpcErr::Internal),
+    };
+    let (block, spec_id) = block_and_spec(block_number, &storage)?.ok_or(RpcErr::Internal)?;
+    let mut state = parent_state(&block, storage);
+    let trace = trace::trace_transaction(
+        &block,
+        index as usize,
+        &mut state,
+        spec_id,
+        &request.options,
+    )
+    .map_err(|_| RpcErr::Vm)?;
+    serde_json::to_value(trace).map_err(|_| RpcErr::Internal)
+}
+
+pub fn trace_block_by_number(
+    request: &TraceBlockByNumberRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!("Requested trace of block {}", request.block);
+    let block_number = match resolve_block_number(&request.block, &storage) {
+        Ok(Some(block_number)) => block_number,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    trace_block(block_number, &request.options, storage)
+}
+
+pub fn trace_block_by_hash(
+    request: &TraceBlockByHashRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!("Requested trace of block {:#x}", request.block);
+    let block_number = match storage.get_block_number(request.block) {
+        Ok(Some(block_number)) => block_number,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    trace_block(block_number, &request.options, storage)
+}
+
+/// Traces the call on top of the state of the requested block, without creating a transaction on chain
+pub fn trace_call(request: &TraceCallRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested trace of call on block {}", request.block);
+    let (header, spec_id) = resolve_header_and_spec(&request.block, &storage)?;
+    let mut state = evm_state_at_block(storage, header.number);
+    let trace = trace::trace_call(
+        &request.transaction,
+        &header,
+        &mut state,
+        spec_id,
+        &request.options,
+    )
+    .map_err(|_| RpcErr::Vm)?;
+    serde_json::to_value(trace).map_err(|_| RpcErr::Internal)
+}
+
+/// Re-executes the block and returns the trace of each transaction along with its hash
+fn trace_block(
+    block_number: BlockNumber,
+    options: &TracingOptions,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    let Some((block, spec_id)) = block_and_spec(block_number, &storage)? else {
+        return Err(RpcErr::BadParams);
+    };
+    let mut state = parent_state(&block, storage);
+    let traces =
+        trace::trace_block(&block, &mut state, spec_id, options).map_err(|_| RpcErr::Vm)?;
+    let traces: Vec<Value> = block
+        .body
+        .transactions
+        .iter()
+        .zip(traces)
+        .map(|(transaction, trace)| {
+            json!({
+                "txHash": transaction.compute_hash(),
+                "result": trace,
+            })
+        })
+        .collect();
+    Ok(Value::Array(traces))
+}
+
+// Returns the stored block along with the spec it was executed with
+pub(crate) fn block_and_spec(
+    block_number: BlockNumber,
+    storage: &Store,
+) -> Result<Option<(Block, SpecId)>, RpcErr> {
+    let header = storage.get_block_header(block_number);
+    let body = storage.get_block_body(block_number);
+    let (header, body) = match (header, body) {
+        (Ok(Some(header)), Ok(Some(body))) => (header, body),
+        // Block not found
+        (Ok(_), Ok(_)) => return Ok(None),
+        // DB error
+        _ => return Err(RpcErr::Internal),
+    };
+    let chain_config = match storage.get_chain_config() {
+        Ok(Some(chain_config)) => chain_config,
+        _ => return Err(RpcErr::Internal),
+    };
+    let spec_id = spec_id(&chain_config, header.timestamp);
+    Ok(Some((Block { header, body }, spec_id)))
+}
+
+// Blocks are executed on top of the state resulting from their parent
+pub(crate) fn parent_state(block: &Block, storage: Store) -> EvmState {
+    evm_state_at_block(storage, block.header.number.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tracing_options() {
+        let hash = Value::String(format!("{:#x}", H256::repeat_byte(1)));
+        let request = TraceTransactionRequest::parse(&Some(vec![hash.clone()])).unwrap();
+        assert!(request.options.tracer.is_none());
+        let call_tracer =
+            serde_json::json!({"tracer": "callTracer", "tracerConfig": {"onlyTopCall": true}});
+        let request =
+            TraceTransactionRequest::parse(&Some(vec![hash.clone(), call_tracer])).unwrap();
+        assert!(request.options.tracer.is_some());
+        // Javascript tracers are not supported
+        let js_tracer = serde_json::json!({"tracer": "{ result: function() { return 1; } }"});
+        assert!(TraceTransactionRequest::parse(&Some(vec![hash, js_tracer])).is_none());
+    }
+}
@@ -0,0 +1,289 @@
+use ethereum_rust_core::{
+    types::{
+        BlockHeader, BlockNumber, Fork, ForkChoiceResponse, ForkChoiceState, PayloadAttributesV1,
+        PayloadAttributesV2, PayloadAttributesV3, PayloadStatus,
+    },
+    H256,
+};
+use ethereum_rust_evm::payload::{create_payload, BuildPayloadArgs};
+use ethereum_rust_storage::{error::StoreError, Store};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::info;
+
+use super::chain_config;
+use crate::RpcErr;
+
+pub struct ForkChoiceUpdatedV1Request {
+    pub fork_choice_state: ForkChoiceState,
+    pub payload_attributes: Option<PayloadAttributesV1>,
+}
+
+pub struct ForkChoiceUpdatedV2Request {
+    pub fork_choice_state: ForkChoiceState,
+    pub payload_attributes: Option<PayloadAttributesV2>,
+}
+
+pub struct ForkChoiceUpdatedV3Request {
+    pub fork_choice_state: ForkChoiceState,
+    pub payload_attributes: Option<PayloadAttributesV3>,
+}
+
+impl ForkChoiceUpdatedV1Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<ForkChoiceUpdatedV1Request> {
+        let (fork_choice_state, payload_attributes) = parse_fork_choice_params(params)?;
+        Some(ForkChoiceUpdatedV1Request {
+            fork_choice_state,
+            payload_attributes,
+        })
+    }
+}
+
+impl ForkChoiceUpdatedV2Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<ForkChoiceUpdatedV2Request> {
+        let (fork_choice_state, payload_attributes) = parse_fork_choice_params(params)?;
+        Some(ForkChoiceUpdatedV2Request {
+            fork_choice_state,
+            payload_attributes,
+        })
+    }
+}
+
+impl ForkChoiceUpdatedV3Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<ForkChoiceUpdatedV3Request> {
+        let (fork_choice_state, payload_attributes) = parse_fork_choice_params(params)?;
+        Some(ForkChoiceUpdatedV3Request {
+            fork_choice_state,
+            payload_attributes,
+        })
+    }
+}
+
+fn parse_fork_choice_params<T: DeserializeOwned>(
+    params: &Option<Vec<Value>>,
+) -> Option<(ForkChoiceState, Option<T>)> {
+    let params = params.as_ref()?;
+    // Payload attributes are optional and may be sent as null
+    if params.is_empty() || params.len() > 2 {
+        return None;
+    }
+    let payload_attributes = match params.get(1) {
+        Some(attributes) => serde_json::from_value(attributes.clone()).ok()?,
+        None => None,
+    };
+    Some((
+        serde_json::from_value(params[0].clone()).ok()?,
+        payload_attributes,
+    ))
+}
+
+pub fn forkchoice_updated_v1(
+    request: ForkChoiceUpdatedV1Request,
+    storage: Store,
+) -> Result<ForkChoiceResponse, RpcErr> {
+    let state = &request.fork_choice_state;
+    let (mut response, head) = apply_fork_choice(state, &storage)?;
+    if let (Some(head), Some(attributes)) = (head, &request.payload_attributes) {
+        // V1 attributes can only be used to build Paris payloads
+        if chain_config(&storage)?.get_fork(attributes.timestamp) != Fork::Paris {
+            return Err(RpcErr::UnsuportedFork);
+        }
+        let args = BuildPayloadArgs {
+            parent: state.head_block_hash,
+            timestamp: attributes.timestamp,
+            fee_recipient: attributes.suggested_fee_recipient,
+            random: attributes.prev_randao,
+            withdrawals: None,
+            beacon_root: None,
+        };
+        response.payload_id = Some(start_payload(&head, args, &storage)?);
+    }
+    Ok(response)
+}
+
+pub fn forkchoice_updated_v2(
+    request: ForkChoiceUpdatedV2Request,
+    storage: Store,
+) -> Result<ForkChoiceResponse, RpcErr> {
+    let state = &request.fork_choice_state;
+    let (mut response, head) = apply_fork_choice(state, &storage)?;
+    if let (Some(head), Some(attributes)) = (head, &request.payload_attributes) {
+        // Withdrawals must be present if and only if Shanghai is active
+        match (
+            chain_config(&storage)?.get_fork(attributes.timestamp),
+            &attributes.withdrawals,
+        ) {
+            (Fork::Paris, None) | (Fork::Shanghai, Some(_)) => {}
+            (Fork::Cancun, _) => return Err(RpcErr::UnsuportedFork),
+            _ => return Err(RpcErr::BadParams),
+        }
+        let args = BuildPayloadArgs {
+            parent: state.head_block_hash,
+            timestamp: attributes.timestamp,
+            fee_recipient: attributes.suggested_fee_recipient,
+            random: attributes.prev_randao,
+            withdrawals: attributes.withdrawals.clone(),
+            beacon_root: None,
+        };
+        response.payload_id = Some(start_payload(&head, args, &storage)?);
+    }
+    Ok(response)
+}
+
+pub fn forkchoice_updated_v3(
+    request: ForkChoiceUpdatedV3Request,
+    storage: Store,
+) -> Result<ForkChoiceResponse, RpcErr> {
+    let state = &request.fork_choice_state;
+    let (mut response, head) = apply_fork_choice(state, &storage)?;
+    if let (Some(head), Some(attributes)) = (head, &request.payload_attributes) {
+        if chain_config(&storage)?.get_fork(attributes.timestamp) != Fork::Cancun {
+            return Err(RpcErr::UnsuportedFork);
+        }
+        let args = BuildPayloadArgs {
+            parent: state.head_block_hash,
+            timestamp: attributes.timestamp,
+            fee_recipient: attributes.suggested_fee_recipient,
+            random: attributes.prev_randao,
+            withdrawals: Some(attributes.withdrawals.clone()),
+            beacon_root: Some(attributes.parent_beacon_block_root),
+        };
+        response.payload_id = Some(start_payload(&head, args, &storage)?);
+    }
+    Ok(response)
+}
+
+/// Applies the fork choice update, returning the response along with the new head block
+/// The head block is only returned if the update was applied
+fn apply_fork_choice(
+    state: &ForkChoiceState,
+    storage: &Store,
+) -> Result<(ForkChoiceResponse, Option<BlockHeader>), RpcErr> {
+    info!(
+        "New fork choice request with head: {}, safe: {}, finalized: {}",
+        state.head_block_hash, state.safe_block_hash, state.finalized_block_hash
+    );
+
+    if state.head_block_hash.is_zero() {
+        return Ok((
+            PayloadStatus::invalid_with_err("forkchoice requested update to zero hash").into(),
+            None,
+        ));
+    }
+
+    // The head block was already rejected
+    if let Some(latest_valid_hash) = storage.get_latest_valid_ancestor(state.head_block_hash) {
+        return Ok((
+            PayloadStatus::invalid_with(latest_valid_hash, "Head block was previously rejected")
+                .into(),
+            None,
+        ));
+    }
+
+    // If we don't know the head block we can't apply the update until we sync up to it
+    let Some(head_number) = storage
+        .get_block_number(state.head_block_hash)
+        .map_err(|_| RpcErr::Internal)?
+    else {
+        return Ok((PayloadStatus::syncing().into(), None));
+    };
+    let head = match storage
+        .get_block_header(head_number)
+        .map_err(|_| RpcErr::Internal)?
+    {
+        Some(header) if header.compute_block_hash() == state.head_block_hash => header,
+        _ => return Ok((PayloadStatus::syncing().into(), None)),
+    };
+
+    // Safe and finalized blocks must be ancestors of the head block (a zero hash means they are not set yet)
+    let finalized_number = canonical_ancestor_number(&head, state.finalized_block_hash, storage)?;
+    let safe_number = canonical_ancestor_number(&head, state.safe_block_hash, storage)?;
+    if let (Some(finalized), Some(safe)) = (finalized_number, safe_number) {
+        if finalized > safe {
+            return Err(RpcErr::InvalidForkChoiceState);
+        }
+    }
+
+    // Apply the update
+    storage
+        .update_latest_block_number(head_number)
+        .map_err(|_| RpcErr::Internal)?;
+    if let Some(safe) = safe_number {
+        storage
+            .update_safe_block_number(safe)
+            .map_err(|_| RpcErr::Internal)?;
+    }
+    if let Some(finalized) = finalized_number {
+        storage
+            .update_finalized_block_number(finalized)
+            .map_err(|_| RpcErr::Internal)?;
+    }
+    info!("Fork choice updated to head block number {head_number}");
+
+    Ok((
+        PayloadStatus::valid_with_hash(state.head_block_hash).into(),
+        Some(head),
+    ))
+}
+
+/// Starts building a payload on top of the head block and returns its id
+fn start_payload(
+    head: &BlockHeader,
+    args: BuildPayloadArgs,
+    storage: &Store,
+) -> Result<u64, RpcErr> {
+    if args.timestamp <= head.timestamp {
+        return Err(RpcErr::InvalidPayloadAttributes);
+    }
+    let payload_id = args.id();
+    let payload = create_payload(&args, storage).map_err(|_| RpcErr::Internal)?;
+    storage.add_payload(payload_id, payload);
+    info!("Started building payload with id {payload_id:#018x}");
+    Ok(payload_id)
+}
+
+/// Returns the number of the block with the given hash if it belongs to the chain ending in `head`
+/// Returns None if the hash is zero and an InvalidForkChoiceState error if the block is not part of the chain
+fn canonical_ancestor_number(
+    head: &BlockHeader,
+    hash: H256,
+    storage: &Store,
+) -> Result<Option<BlockNumber>, RpcErr> {
+    if hash.is_zero() {
+        return Ok(None);
+    }
+    let number = match storage.get_block_number(hash) {
+        Ok(Some(number)) if number <= head.number => number,
+        Ok(_) => return Err(RpcErr::InvalidForkChoiceState),
+        Err(_) => return Err(RpcErr::Internal),
+    };
+    match is_ancestor(head, number, hash, storage) {
+        Ok(true) => Ok(Some(number)),
+        Ok(false) => Err(RpcErr::InvalidForkChoiceState),
+        Err(_) => Err(RpcErr::Internal),
+    }
+}
+
+/// Walks back from `head` following parent hashes and checks if the block at `number` has hash `hash`
+fn is_ancestor(
+    head: &BlockHeader,
+    number: BlockNumber,
+    hash: H256,
+    storage: &Store,
+) -> Result<bool, StoreError> {
+    let mut current_hash = head.compute_block_hash();
+    let mut parent_hash = head.parent_hash;
+    let mut current_number = head.number;
+    while current_number > number {
+        current_number -= 1;
+        let Some(parent) = storage.get_block_header(current_number)? else {
+            return Ok(false);
+        };
+        current_hash = parent.compute_block_hash();
+        if current_hash != parent_hash {
+            return Ok(false);
+        }
+        parent_hash = parent.parent_hash;
+    }
+    Ok(current_hash == hash)
+}
@@ -1,22 +1,110 @@
+use ethereum_rust_consensus::validate_block;
 use ethereum_rust_core::{
-    types::{validate_block_header, ExecutionPayloadV3, PayloadStatus},
+    types::{
+        Block, BlockHash, BlockHeader, ChainConfig, ExecutionPayloadV1, ExecutionPayloadV2,
+        ExecutionPayloadV3, Fork, PayloadStatus,
+    },
     H256,
 };
-use ethereum_rust_evm::{evm_state, execute_block, SpecId};
+use ethereum_rust_evm::{evm_state_at_block, execute_and_validate_block, spec_id, EvmError};
 use ethereum_rust_storage::Store;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::RpcErr;
 
+pub mod fork_choice;
+pub mod payload;
+
 pub type ExchangeCapabilitiesRequest = Vec<String>;
 
+/// Engine API methods implemented by this client, as reported by `engine_exchangeCapabilities`
+/// Must be kept in sync with the methods handled by `map_engine_requests`
+pub const ENGINE_CAPABILITIES: [&str; 12] = [
+    "engine_forkchoiceUpdatedV1",
+    "engine_forkchoiceUpdatedV2",
+    "engine_forkchoiceUpdatedV3",
+    "engine_newPayloadV1",
+    "engine_newPayloadV2",
+    "engine_newPayloadV3",
+    "engine_getPayloadV1",
+    "engine_getPayloadV2",
+    "engine_getPayloadV3",
+    "engine_getPayloadBodiesByHashV1",
+    "engine_getPayloadBodiesByRangeV1",
+    "engine_getClientVersionV1",
+];
+
+/// Two letter code identifying this client as defined in the
+/// [Engine API spec](https://github.com/ethereum/execution-apis/blob/main/src/engine/identification.md#clientcode)
+const CLIENT_CODE: &str = "ER";
+pub(crate) const CLIENT_NAME: &str = "ethereum_rust";
+
+/// Identifies a client implementation, as exchanged by `engine_getClientVersionV1`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ClientVersionV1 {
+    pub code: String,
+    pub name: String,
+    pub version: String,
+    /// First four bytes of the latest commit hash of the client's build
+    pub commit: String,
+}
+
+pub struct GetClientVersionV1Request {
+    pub client_version: ClientVersionV1,
+}
+
+impl GetClientVersionV1Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetClientVersionV1Request> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        }
+        Some(GetClientVersionV1Request {
+            client_version: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+pub struct NewPayloadV1Request {
+    pub payload: ExecutionPayloadV1,
+}
+
+pub struct NewPayloadV2Request {
+    pub payload: ExecutionPayloadV2,
+}
+
 pub struct NewPayloadV3Request {
     pub payload: ExecutionPayloadV3,
     pub expected_blob_versioned_hashes: Vec<H256>,
     pub parent_beacon_block_root: H256,
 }
 
+impl NewPayloadV1Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<NewPayloadV1Request> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        }
+        Some(NewPayloadV1Request {
+            payload: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+impl NewPayloadV2Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<NewPayloadV2Request> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        }
+        Some(NewPayloadV2Request {
+            payload: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
 impl NewPayloadV3Request {
     pub fn parse(params: &Option<Vec<Value>>) -> Option<NewPayloadV3Request> {
         let params = params.as_ref()?;
@@ -31,47 +119,97 @@ impl NewPayloadV3Request {
     }
 }
 
+/// Returns the engine methods supported by this client, regardless of the ones supported by the consensus client
 pub fn exchange_capabilities(capabilities: &ExchangeCapabilitiesRequest) -> Result<Value, RpcErr> {
-    Ok(json!(capabilities))
+    // Report methods the consensus client expects us to support so mismatches can be spotted on startup
+    for capability in capabilities {
+        if !ENGINE_CAPABILITIES.contains(&capability.as_str()) {
+            warn!("Consensus client requested unsupported capability: {capability}");
+        }
+    }
+    Ok(json!(ENGINE_CAPABILITIES))
 }
 
-pub fn forkchoice_updated_v3() -> Result<Value, RpcErr> {
-    Ok(json!({
-        "payloadId": null,
-        "payloadStatus": {
-            "latestValidHash": null,
-            "status": "SYNCING",
-            "validationError": null
-        }
-    }))
+pub fn get_client_version_v1(
+    request: &GetClientVersionV1Request,
+) -> Result<Vec<ClientVersionV1>, RpcErr> {
+    let consensus_client = &request.client_version;
+    info!(
+        "Consensus client version: {} {} {} ({})",
+        consensus_client.code,
+        consensus_client.name,
+        consensus_client.version,
+        consensus_client.commit
+    );
+    Ok(vec![ClientVersionV1 {
+        code: CLIENT_CODE.to_string(),
+        name: CLIENT_NAME.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: format!("0x{}", env!("GIT_COMMIT_PREFIX")),
+    }])
 }
 
-pub fn new_payload_v3(
-    request: NewPayloadV3Request,
+pub fn new_payload_v1(
+    request: NewPayloadV1Request,
     storage: Store,
 ) -> Result<PayloadStatus, RpcErr> {
     let block_hash = request.payload.block_hash;
     info!("Received new payload with block hash: {block_hash}");
 
-    let block = match request.payload.into_block(request.parent_beacon_block_root) {
+    // Check timestamp falls within the time frame of the Paris fork
+    if chain_config(&storage)?.get_fork(request.payload.timestamp) != Fork::Paris {
+        return Err(RpcErr::UnsuportedFork);
+    }
+
+    let block = match request.payload.into_block() {
         Ok(block) => block,
         Err(error) => return Ok(PayloadStatus::invalid_with_err(&error.to_string())),
     };
+    execute_payload(block, block_hash, storage)
+}
 
-    // Payload Validation
+pub fn new_payload_v2(
+    request: NewPayloadV2Request,
+    storage: Store,
+) -> Result<PayloadStatus, RpcErr> {
+    let block_hash = request.payload.block_hash;
+    info!("Received new payload with block hash: {block_hash}");
 
-    // Check timestamp does not fall within the time frame of the Cancun fork
-    match storage.get_cancun_time().map_err(|_| RpcErr::Internal)? {
-        Some(cancun_time) if block.header.timestamp > cancun_time => {}
-        _ => return Err(RpcErr::UnsuportedFork),
+    // Check the payload shape matches the fork active at its timestamp:
+    // Paris payloads have no withdrawals while Shanghai payloads must contain them
+    match (
+        chain_config(&storage)?.get_fork(request.payload.timestamp),
+        &request.payload.withdrawals,
+    ) {
+        (Fork::Paris, None) | (Fork::Shanghai, Some(_)) => {}
+        (Fork::Cancun, _) => return Err(RpcErr::UnsuportedFork),
+        _ => return Err(RpcErr::BadParams),
     }
 
-    // Check that block_hash is valid
-    let actual_block_hash = block.header.compute_block_hash();
-    if block_hash != actual_block_hash {
-        return Ok(PayloadStatus::invalid_with_err("Invalid block hash"));
+    let block = match request.payload.into_block() {
+        Ok(block) => block,
+        Err(error) => return Ok(PayloadStatus::invalid_with_err(&error.to_string())),
+    };
+    execute_payload(block, block_hash, storage)
+}
+
+pub fn new_payload_v3(
+    request: NewPayloadV3Request,
+    storage: Store,
+) -> Result<PayloadStatus, RpcErr> {
+    let block_hash = request.payload.block_hash;
+    info!("Received new payload with block hash: {block_hash}");
+
+    // Check timestamp falls within the time frame of the Cancun fork
+    if chain_config(&storage)?.get_fork(request.payload.timestamp) != Fork::Cancun {
+        return Err(RpcErr::UnsuportedFork);
     }
-    info!("Block hash {block_hash} is valid");
+
+    let block = match request.payload.into_block(request.parent_beacon_block_root) {
+        Ok(block) => block,
+        Err(error) => return Ok(PayloadStatus::invalid_with_err(&error.to_string())),
+    };
+
     // Concatenate blob versioned hashes lists (tx.blob_versioned_hashes) of each blob transaction included in the payload, respecting the order of inclusion
     // and check that the resulting array matches expected_blob_versioned_hashes
     let blob_versioned_hashes: Vec<H256> = block
@@ -86,30 +224,100 @@ pub fn new_payload_v3(
         ));
     }
 
-    // Fetch parent block header and validate current header
-    if let Some(parent_header) = storage
-        .get_block_header(block.header.number.saturating_sub(1))
-        .map_err(|_| RpcErr::Internal)?
+    execute_payload(block, block_hash, storage)
+}
+
+/// Validates the block built from a payload against its parent, executes it and stores it
+fn execute_payload(
+    block: Block,
+    block_hash: BlockHash,
+    storage: Store,
+) -> Result<PayloadStatus, RpcErr> {
+    // Check that block_hash is valid
+    let actual_block_hash = block.header.compute_block_hash();
+    if block_hash != actual_block_hash {
+        return Ok(PayloadStatus::invalid_with_err("Invalid block hash"));
+    }
+    info!("Block hash {block_hash} is valid");
+
+    // Blocks that were already rejected, or that descend from one, are invalid
+    let parent_hash = block.header.parent_hash;
+    if let Some(latest_valid_hash) = storage
+        .get_latest_valid_ancestor(block_hash)
+        .or_else(|| storage.get_latest_valid_ancestor(parent_hash))
     {
-        if !validate_block_header(&block.header, &parent_header) {
-            return Ok(PayloadStatus::invalid_with_hash(
-                parent_header.compute_block_hash(),
-            ));
-        }
-    } else {
+        warn!("Payload with block hash {block_hash} links to a previously rejected block");
+        storage.add_invalid_ancestor(block_hash, latest_valid_hash);
+        return Ok(PayloadStatus::invalid_with(
+            latest_valid_hash,
+            "Links to previously rejected block",
+        ));
+    }
+
+    // Fetch parent block header and validate current header
+    let Some(parent_header) = get_header_by_hash(parent_hash, &storage)? else {
         return Ok(PayloadStatus::syncing());
+    };
+    let chain_config = chain_config(&storage)?;
+    if let Err(error) = validate_block(&block, &parent_header, &chain_config) {
+        warn!("Payload with block hash {block_hash} is invalid: {error}");
+        storage.add_invalid_ancestor(block_hash, parent_hash);
+        return Ok(PayloadStatus::invalid_with(parent_hash, &error.to_string()));
     }
 
-    // Execute and store the block
+    // Execute the block and check the execution results against the header before storing it
     info!("Executing payload with block hash: {block_hash}");
-    execute_block(&block, &mut evm_state(storage.clone()), SpecId::CANCUN)
-        .map_err(|_| RpcErr::Vm)?;
+    let spec_id = spec_id(&chain_config, block.header.timestamp);
+    // The parent may not be the current head, so the block runs on the state resulting from it
+    let mut state = evm_state_at_block(storage.clone(), parent_header.number);
+    let validation_result = match execute_and_validate_block(&block, &mut state, spec_id) {
+        Ok(receipts) => Ok(receipts),
+        Err(EvmError::InvalidBlock(error)) => Err(error),
+        Err(error @ (EvmError::Transaction(_) | EvmError::Header(_))) => Err(error.to_string()),
+        Err(_) => return Err(RpcErr::Vm),
+    };
+    let receipts = match validation_result {
+        Ok(receipts) => receipts,
+        Err(error) => {
+            warn!("Payload with block hash {block_hash} is invalid: {error}");
+            storage.add_invalid_ancestor(block_hash, parent_hash);
+            return Ok(PayloadStatus::invalid_with(parent_hash, &error));
+        }
+    };
     info!("Block with hash {block_hash} executed succesfully");
     storage
         .add_block_number(block_hash, block.header.number)
         .map_err(|_| RpcErr::Internal)?;
+    for (index, receipt) in receipts.into_iter().enumerate() {
+        storage
+            .add_receipt(block.header.number, index as u64, receipt)
+            .map_err(|_| RpcErr::Internal)?;
+    }
+    storage.remove_mined_transactions(&block.body.transactions);
     storage.add_block(block).map_err(|_| RpcErr::Internal)?;
     info!("Block with hash {block_hash} added to storage");
 
     Ok(PayloadStatus::valid_with_hash(block_hash))
 }
+
+/// Returns the header of the block with the given hash if it is stored
+fn get_header_by_hash(hash: BlockHash, storage: &Store) -> Result<Option<BlockHeader>, RpcErr> {
+    let Some(number) = storage
+        .get_block_number(hash)
+        .map_err(|_| RpcErr::Internal)?
+    else {
+        return Ok(None);
+    };
+    Ok(storage
+        .get_block_header(number)
+        .map_err(|_| RpcErr::Internal)?
+        .filter(|header| header.compute_block_hash() == hash))
+}
+
+/// Returns the chain config stored from the genesis file
+fn chain_config(storage: &Store) -> Result<ChainConfig, RpcErr> {
+    storage
+        .get_chain_config()
+        .map_err(|_| RpcErr::Internal)?
+        .ok_or(RpcErr::Internal)
+}
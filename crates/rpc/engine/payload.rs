@@ -0,0 +1,192 @@
+use ethereum_rust_core::{
+    types::{
+        BlobsBundleV1, Block, BlockHash, BlockNumber, ExecutionPayloadBodyV1,
+        ExecutionPayloadResponseV2, ExecutionPayloadResponseV3, ExecutionPayloadV1,
+        ExecutionPayloadV2, ExecutionPayloadV3, Fork,
+    },
+    U256,
+};
+use ethereum_rust_evm::payload::build_payload;
+use ethereum_rust_storage::Store;
+use serde_json::Value;
+use tracing::info;
+
+use super::chain_config;
+use crate::RpcErr;
+
+/// Maximum amount of payload bodies that can be requested at once
+const GET_PAYLOAD_BODIES_REQUEST_MAX_SIZE: u64 = 1024;
+
+/// Request for `engine_getPayloadV1`, `engine_getPayloadV2` and `engine_getPayloadV3`
+pub struct GetPayloadRequest {
+    pub payload_id: u64,
+}
+
+impl GetPayloadRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetPayloadRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        }
+        let payload_id: String = serde_json::from_value(params[0].clone()).ok()?;
+        Some(GetPayloadRequest {
+            payload_id: u64::from_str_radix(payload_id.strip_prefix("0x")?, 16).ok()?,
+        })
+    }
+}
+
+pub struct GetPayloadBodiesByHashV1Request {
+    pub hashes: Vec<BlockHash>,
+}
+
+impl GetPayloadBodiesByHashV1Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetPayloadBodiesByHashV1Request> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        }
+        Some(GetPayloadBodiesByHashV1Request {
+            hashes: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+pub struct GetPayloadBodiesByRangeV1Request {
+    pub start: BlockNumber,
+    pub count: u64,
+}
+
+impl GetPayloadBodiesByRangeV1Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetPayloadBodiesByRangeV1Request> {
+        let params = params.as_ref()?;
+        if params.len() != 2 {
+            return None;
+        }
+        let start: String = serde_json::from_value(params[0].clone()).ok()?;
+        let count: String = serde_json::from_value(params[1].clone()).ok()?;
+        Some(GetPayloadBodiesByRangeV1Request {
+            start: u64::from_str_radix(start.strip_prefix("0x")?, 16).ok()?,
+            count: u64::from_str_radix(count.strip_prefix("0x")?, 16).ok()?,
+        })
+    }
+}
+
+pub fn get_payload_v1(
+    request: &GetPayloadRequest,
+    storage: Store,
+) -> Result<ExecutionPayloadV1, RpcErr> {
+    let (payload, _) = get_built_payload(request, &storage, &[Fork::Paris])?;
+    Ok(ExecutionPayloadV1::from_block(payload))
+}
+
+pub fn get_payload_v2(
+    request: &GetPayloadRequest,
+    storage: Store,
+) -> Result<ExecutionPayloadResponseV2, RpcErr> {
+    let (payload, block_value) =
+        get_built_payload(request, &storage, &[Fork::Paris, Fork::Shanghai])?;
+    Ok(ExecutionPayloadResponseV2 {
+        execution_payload: ExecutionPayloadV2::from_block(payload),
+        block_value,
+    })
+}
+
+pub fn get_payload_v3(
+    request: &GetPayloadRequest,
+    storage: Store,
+) -> Result<ExecutionPayloadResponseV3, RpcErr> {
+    let (payload, block_value) = get_built_payload(request, &storage, &[Fork::Cancun])?;
+    Ok(ExecutionPayloadResponseV3 {
+        execution_payload: ExecutionPayloadV3::from_block(payload),
+        block_value,
+        // Blob transactions are not included in the payloads we build
+        blobs_bundle: BlobsBundleV1::default(),
+        should_override_builder: false,
+    })
+}
+
+/// Fetches the payload with the requested id and fills it with transactions
+/// Fails if the payload doesn't belong to one of the given forks
+fn get_built_payload(
+    request: &GetPayloadRequest,
+    storage: &Store,
+    supported_forks: &[Fork],
+) -> Result<(Block, U256), RpcErr> {
+    let payload_id = request.payload_id;
+    info!("Requested payload with id: {payload_id:#018x}");
+    let Some(mut payload) = storage.get_payload(payload_id) else {
+        return Err(RpcErr::UnknownPayload);
+    };
+    if !supported_forks.contains(&chain_config(storage)?.get_fork(payload.header.timestamp)) {
+        return Err(RpcErr::UnsuportedFork);
+    }
+    // Fill the payload with the best executable transactions from the mempool
+    let transactions = storage.best_pool_transactions(Some(payload.header.base_fee_per_gas));
+    let block_value = build_payload(&mut payload, storage, transactions).map_err(|_| RpcErr::Vm)?;
+    Ok((payload, block_value))
+}
+
+/// Returns the bodies of the requested blocks, with null entries for unknown blocks
+pub fn get_payload_bodies_by_hash_v1(
+    request: &GetPayloadBodiesByHashV1Request,
+    storage: Store,
+) -> Result<Vec<Option<ExecutionPayloadBodyV1>>, RpcErr> {
+    info!(
+        "Requested payload bodies for {} block hashes",
+        request.hashes.len()
+    );
+    if request.hashes.len() as u64 > GET_PAYLOAD_BODIES_REQUEST_MAX_SIZE {
+        return Err(RpcErr::TooLargeRequest);
+    }
+    request
+        .hashes
+        .iter()
+        .map(|hash| {
+            let Some(number) = storage
+                .get_block_number(*hash)
+                .map_err(|_| RpcErr::Internal)?
+            else {
+                return Ok(None);
+            };
+            get_payload_body(number, &storage)
+        })
+        .collect()
+}
+
+/// Returns the bodies of the blocks in the requested range, with null entries for unknown blocks
+/// The range is cut short at the latest block so that there are no trailing null entries
+pub fn get_payload_bodies_by_range_v1(
+    request: &GetPayloadBodiesByRangeV1Request,
+    storage: Store,
+) -> Result<Vec<Option<ExecutionPayloadBodyV1>>, RpcErr> {
+    info!(
+        "Requested {} payload bodies starting from block number {}",
+        request.count, request.start
+    );
+    if request.start < 1 || request.count < 1 {
+        return Err(RpcErr::BadParams);
+    }
+    if request.count > GET_PAYLOAD_BODIES_REQUEST_MAX_SIZE {
+        return Err(RpcErr::TooLargeRequest);
+    }
+    let Some(latest) = storage
+        .get_latest_block_number()
+        .map_err(|_| RpcErr::Internal)?
+    else {
+        return Ok(Vec::new());
+    };
+    let last = latest.min(request.start.saturating_add(request.count - 1));
+    (request.start..=last)
+        .map(|number| get_payload_body(number, &storage))
+        .collect()
+}
+
+fn get_payload_body(
+    number: BlockNumber,
+    storage: &Store,
+) -> Result<Option<ExecutionPayloadBodyV1>, RpcErr> {
+    Ok(storage
+        .get_block_body(number)
+        .map_err(|_| RpcErr::Internal)?
+        .map(ExecutionPayloadBodyV1::from))
+}
@@ -1,11 +1,12 @@
-use ethereum_rust_storage::Store;
+use ethereum_rust_storage::{AccountProof, Store};
+use serde::Serialize;
 use serde_json::Value;
 use tracing::info;
 
 use crate::utils::RpcErr;
-use ethereum_rust_core::{Address, H256};
+use ethereum_rust_core::{types::BlockNumber, Address, Bytes, H256, U256};
 
-use super::block::BlockIdentifier;
+use super::block::{resolve_block_number, BlockIdentifier};
 
 pub struct GetBalanceRequest {
     pub address: Address,
@@ -23,6 +24,35 @@ pub struct GetStorageAtRequest {
     pub block: BlockIdentifier,
 }
 
+pub struct GetProofRequest {
+    pub address: Address,
+    pub storage_keys: Vec<H256>,
+    pub block: BlockIdentifier,
+}
+
+/// Account and storage proofs as defined in EIP-1186
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountProofResponse {
+    address: Address,
+    #[serde(with = "ethereum_rust_core::serde_utils::bytes::vec")]
+    account_proof: Vec<Bytes>,
+    balance: U256,
+    code_hash: H256,
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    nonce: u64,
+    storage_hash: H256,
+    storage_proof: Vec<StorageProofResponse>,
+}
+
+#[derive(Serialize)]
+struct StorageProofResponse {
+    key: H256,
+    value: U256,
+    #[serde(with = "ethereum_rust_core::serde_utils::bytes::vec")]
+    proof: Vec<Bytes>,
+}
+
 impl GetBalanceRequest {
     pub fn parse(params: &Option<Vec<Value>>) -> Option<GetBalanceRequest> {
         let params = params.as_ref()?;
@@ -63,12 +93,27 @@ impl GetStorageAtRequest {
     }
 }
 
+impl GetProofRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetProofRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 3 {
+            return None;
+        };
+        Some(GetProofRequest {
+            address: serde_json::from_value(params[0].clone()).ok()?,
+            storage_keys: serde_json::from_value(params[1].clone()).ok()?,
+            block: serde_json::from_value(params[2].clone()).ok()?,
+        })
+    }
+}
+
 pub fn get_balance(request: &GetBalanceRequest, storage: Store) -> Result<Value, RpcErr> {
     info!(
         "Requested balance of account {} at block {}",
         request.address, request.block
     );
-    let account = match storage.get_account_info(request.address) {
+    let block_number = resolve_state_block(&request.block, &storage)?;
+    let account = match storage.get_account_info_by_block(block_number, request.address) {
         Ok(Some(account)) => account,
         // Account not found
         Ok(_) => return Ok(Value::Null),
@@ -84,7 +129,8 @@ pub fn get_code(request: &GetCodeRequest, storage: Store) -> Result<Value, RpcEr
         "Requested code of account {} at block {}",
         request.address, request.block
     );
-    let code = match storage.get_code_by_account_address(request.address) {
+    let block_number = resolve_state_block(&request.block, &storage)?;
+    let code = match storage.get_code_by_account_address_by_block(block_number, request.address) {
         Ok(Some(code)) => code,
         // Account not found
         Ok(_) => return Ok(Value::Null),
@@ -100,7 +146,12 @@ pub fn get_storage_at(request: &GetStorageAtRequest, storage: Store) -> Result<V
         "Requested storage sot {} of account {} at block {}",
         request.storage_slot, request.address, request.block
     );
-    let storage_value = match storage.get_storage_at(request.address, request.storage_slot) {
+    let block_number = resolve_state_block(&request.block, &storage)?;
+    let storage_value = match storage.get_storage_at_by_block(
+        block_number,
+        request.address,
+        request.storage_slot,
+    ) {
         Ok(Some(storage_value)) => storage_value,
         // Account not found
         Ok(_) => return Ok(Value::Null),
@@ -110,3 +161,53 @@ pub fn get_storage_at(request: &GetStorageAtRequest, storage: Store) -> Result<V
 
     serde_json::to_value(format!("{:#x}", storage_value)).map_err(|_| RpcErr::Internal)
 }
+
+/// Returns the account along with the merkle proofs of it and the requested storage slots,
+/// which verify against the state root of the block
+pub fn get_proof(request: &GetProofRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!(
+        "Requested proof of account {} and {} storage slots at block {}",
+        request.address,
+        request.storage_keys.len(),
+        request.block
+    );
+    let block_number = resolve_state_block(&request.block, &storage)?;
+    let AccountProof {
+        state,
+        proof,
+        storage_proofs,
+    } = storage
+        .get_account_proof(block_number, request.address, &request.storage_keys)
+        .map_err(|_| RpcErr::Internal)?;
+    let response = AccountProofResponse {
+        address: request.address,
+        account_proof: proof,
+        balance: state.balance,
+        code_hash: state.code_hash,
+        nonce: state.nonce,
+        storage_hash: state.storage_root,
+        storage_proof: storage_proofs
+            .into_iter()
+            .map(|storage_proof| StorageProofResponse {
+                key: storage_proof.key,
+                value: storage_proof.value,
+                proof: storage_proof.proof,
+            })
+            .collect(),
+    };
+    serde_json::to_value(response).map_err(|_| RpcErr::Internal)
+}
+
+/// Returns the number of the block whose state is requested, failing if the block is not stored
+fn resolve_state_block(block: &BlockIdentifier, storage: &Store) -> Result<BlockNumber, RpcErr> {
+    let block_number = match resolve_block_number(block, storage) {
+        Ok(Some(block_number)) => block_number,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    match storage.get_block_header(block_number) {
+        Ok(Some(_)) => Ok(block_number),
+        Ok(_) => Err(RpcErr::BadParams),
+        _ => Err(RpcErr::Internal),
+    }
+}
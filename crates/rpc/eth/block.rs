@@ -1,11 +1,12 @@
 use std::fmt::Display;
 
-use ethereum_rust_evm::{evm_state, ExecutionResult, SpecId};
+use ethereum_rust_evm::{evm_state_at_block, ExecutionResult};
 use ethereum_rust_storage::{error::StoreError, Store};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::info;
 
+use super::transaction::resolve_header_and_spec;
 use crate::utils::RpcErr;
 use ethereum_rust_core::{
     types::{
@@ -101,6 +102,28 @@ pub(crate) fn resolve_block_number(
     }
 }
 
+/// Identifies a block by either its hash, its number or a tag
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum BlockIdentifierOrHash {
+    Hash(BlockHash),
+    Identifier(BlockIdentifier),
+}
+
+impl BlockIdentifierOrHash {
+    pub(crate) fn resolve_block_number(
+        &self,
+        storage: &Store,
+    ) -> Result<Option<BlockNumber>, StoreError> {
+        match self {
+            BlockIdentifierOrHash::Hash(hash) => storage.get_block_number(*hash),
+            BlockIdentifierOrHash::Identifier(identifier) => {
+                resolve_block_number(identifier, storage)
+            }
+        }
+    }
+}
+
 impl GetBlockByNumberRequest {
     pub fn parse(params: &Option<Vec<Value>>) -> Option<GetBlockByNumberRequest> {
         let params = params.as_ref()?;
@@ -452,35 +475,17 @@ pub fn create_access_list(
 ) -> Result<Value, RpcErr> {
     let block = request.block.clone().unwrap_or_default();
     info!("Requested access list creation for tx on block: {}", block);
-    let block_number = match block {
-        BlockIdentifier::Tag(_) => unimplemented!("Obtain block number from tag"),
-        BlockIdentifier::Number(block_number) => block_number,
-    };
-    let header = match storage.get_block_header(block_number) {
-        Ok(Some(header)) => header,
-        // Block not found
-        Ok(_) => return Ok(Value::Null),
-        // DB error
-        _ => return Err(RpcErr::Internal),
-    };
-    // Run transaction and obtain access list
+    let (header, spec_id) = resolve_header_and_spec(&block, &storage)?;
+    // Run transaction on top of the state resulting from the block and obtain access list
     let (gas_used, access_list, error) = match ethereum_rust_evm::create_access_list(
         &request.transaction,
         &header,
-        &mut evm_state(storage),
-        SpecId::CANCUN,
+        &mut evm_state_at_block(storage, header.number),
+        spec_id,
     )
     .map_err(|_| RpcErr::Vm)?
     {
-        (
-            ExecutionResult::Success {
-                reason: _,
-                gas_used,
-                gas_refunded: _,
-                output: _,
-            },
-            access_list,
-        ) => (gas_used, access_list, None),
+        (ExecutionResult::Success { gas_used, .. }, access_list) => (gas_used, access_list, None),
         (
             ExecutionResult::Revert {
                 gas_used,
@@ -536,6 +541,15 @@ impl Display for BlockIdentifier {
     }
 }
 
+impl Display for BlockIdentifierOrHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockIdentifierOrHash::Hash(hash) => write!(f, "{hash:#x}"),
+            BlockIdentifierOrHash::Identifier(identifier) => identifier.fmt(f),
+        }
+    }
+}
+
 impl Default for BlockIdentifier {
     fn default() -> BlockIdentifier {
         BlockIdentifier::Tag(BlockTag::default())
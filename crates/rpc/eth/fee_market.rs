@@ -0,0 +1,367 @@
+use ethereum_rust_core::{
+    types::{
+        calc_excess_blob_gas, calculate_base_fee_per_blob_gas, calculate_base_fee_per_gas,
+        BlockHeader, BlockNumber, Transaction, GWEI_TO_WEI, MAX_BLOB_GAS_PER_BLOCK,
+    },
+    U256,
+};
+use ethereum_rust_storage::Store;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::utils::RpcErr;
+
+use super::block::{resolve_block_number, BlockIdentifier};
+
+/// Maximum amount of blocks eth_feeHistory reports on, larger requests are truncated
+const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+/// Amount of recent blocks sampled by the gas price oracle
+const ORACLE_BLOCKS: u64 = 20;
+/// Amount of transactions with the lowest tips sampled from each block by the gas price oracle
+const ORACLE_SAMPLES_PER_BLOCK: usize = 3;
+/// Percentile of the sampled tips suggested by the gas price oracle
+const ORACLE_PERCENTILE: usize = 60;
+/// Tips this low are ignored by the gas price oracle, as they are usually sent by the block builder
+const ORACLE_IGNORE_PRICE: u64 = 2;
+/// Priority fee suggested when there are no recent transactions to sample
+const DEFAULT_MAX_PRIORITY_FEE: u64 = GWEI_TO_WEI;
+
+pub struct FeeHistoryRequest {
+    pub block_count: u64,
+    pub newest_block: BlockIdentifier,
+    pub reward_percentiles: Option<Vec<f64>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeHistory {
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    oldest_block: BlockNumber,
+    /// Base fee of each block, followed by the base fee of the block after the newest one
+    base_fee_per_gas: Vec<U256>,
+    gas_used_ratio: Vec<f64>,
+    /// Blob base fee of each block, followed by the blob base fee of the block after the newest one
+    base_fee_per_blob_gas: Vec<U256>,
+    blob_gas_used_ratio: Vec<f64>,
+    /// Tip paid at each of the requested percentiles of gas used in each block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reward: Option<Vec<Vec<U256>>>,
+}
+
+impl FeeHistoryRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<FeeHistoryRequest> {
+        let params = params.as_ref()?;
+        if params.len() < 2 || params.len() > 3 {
+            return None;
+        };
+        // The block count may be sent as a quantity or as a plain number
+        let block_count = match &params[0] {
+            Value::String(count) => u64::from_str_radix(count.strip_prefix("0x")?, 16).ok()?,
+            count => count.as_u64()?,
+        };
+        let reward_percentiles: Option<Vec<f64>> = match params.get(2) {
+            Some(percentiles) => serde_json::from_value(percentiles.clone()).ok()?,
+            None => None,
+        };
+        // Percentiles must be increasing and between 0 and 100
+        if let Some(percentiles) = &reward_percentiles {
+            if percentiles.iter().any(|p| !(0.0..=100.0).contains(p))
+                || percentiles.windows(2).any(|pair| pair[0] > pair[1])
+            {
+                return None;
+            }
+        }
+        Some(FeeHistoryRequest {
+            block_count,
+            newest_block: serde_json::from_value(params[1].clone()).ok()?,
+            reward_percentiles,
+        })
+    }
+}
+
+/// Returns the base fees, gas usage and tips paid over a range of blocks ending in the requested one
+pub fn fee_history(request: &FeeHistoryRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!(
+        "Requested fee history for {} blocks up to block: {}",
+        request.block_count, request.newest_block
+    );
+    let newest_block = match resolve_block_number(&request.newest_block, &storage) {
+        Ok(Some(number)) => number,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    let block_count = request
+        .block_count
+        .min(MAX_FEE_HISTORY_BLOCKS)
+        .min(newest_block + 1);
+    let oldest_block = newest_block + 1 - block_count;
+
+    let mut history = FeeHistory {
+        oldest_block,
+        base_fee_per_gas: Vec::new(),
+        gas_used_ratio: Vec::new(),
+        base_fee_per_blob_gas: Vec::new(),
+        blob_gas_used_ratio: Vec::new(),
+        reward: request.reward_percentiles.as_ref().map(|_| Vec::new()),
+    };
+    let mut last_header = None;
+    for number in oldest_block..=newest_block {
+        let header = get_header(number, &storage)?;
+        history
+            .base_fee_per_gas
+            .push(U256::from(header.base_fee_per_gas));
+        history
+            .gas_used_ratio
+            .push(ratio(header.gas_used, header.gas_limit));
+        history
+            .base_fee_per_blob_gas
+            .push(calculate_base_fee_per_blob_gas(
+                header.excess_blob_gas.unwrap_or_default(),
+            ));
+        history.blob_gas_used_ratio.push(ratio(
+            header.blob_gas_used.unwrap_or_default(),
+            MAX_BLOB_GAS_PER_BLOCK,
+        ));
+        if let (Some(rewards), Some(percentiles)) =
+            (&mut history.reward, &request.reward_percentiles)
+        {
+            let transactions = transactions_gas_used(&header, &storage)?;
+            rewards.push(block_rewards(&header, &transactions, percentiles));
+        }
+        last_header = Some(header);
+    }
+    // Fees of the block that will be built on top of the newest one
+    if let Some(header) = last_header {
+        history
+            .base_fee_per_gas
+            .push(U256::from(next_base_fee(&header)));
+        history
+            .base_fee_per_blob_gas
+            .push(next_blob_base_fee(&header));
+    }
+    serde_json::to_value(history).map_err(|_| RpcErr::Internal)
+}
+
+/// Suggests a priority fee based on the tips paid in recent blocks
+pub fn max_priority_fee_per_gas(storage: Store) -> Result<Value, RpcErr> {
+    let tip = suggest_tip(&storage)?;
+    serde_json::to_value(format!("{tip:#x}")).map_err(|_| RpcErr::Internal)
+}
+
+/// Suggests a legacy gas price, the suggested tip on top of the latest base fee
+pub fn gas_price(storage: Store) -> Result<Value, RpcErr> {
+    let latest = get_header(latest_block_number(&storage)?, &storage)?;
+    let gas_price = latest.base_fee_per_gas + suggest_tip(&storage)?;
+    serde_json::to_value(format!("{gas_price:#x}")).map_err(|_| RpcErr::Internal)
+}
+
+/// Returns the blob base fee of the block that will be built on top of the latest one
+pub fn blob_base_fee(storage: Store) -> Result<Value, RpcErr> {
+    let latest = get_header(latest_block_number(&storage)?, &storage)?;
+    serde_json::to_value(next_blob_base_fee(&latest)).map_err(|_| RpcErr::Internal)
+}
+
+fn latest_block_number(storage: &Store) -> Result<BlockNumber, RpcErr> {
+    storage
+        .get_latest_block_number()
+        .map_err(|_| RpcErr::Internal)?
+        .ok_or(RpcErr::Internal)
+}
+
+fn get_header(number: BlockNumber, storage: &Store) -> Result<BlockHeader, RpcErr> {
+    storage
+        .get_block_header(number)
+        .map_err(|_| RpcErr::Internal)?
+        .ok_or(RpcErr::Internal)
+}
+
+fn ratio(used: u64, limit: u64) -> f64 {
+    if limit == 0 {
+        return 0.0;
+    }
+    used as f64 / limit as f64
+}
+
+// The gas limit of the next block is unknown, so it is assumed to stay the same
+pub(crate) fn next_base_fee(header: &BlockHeader) -> u64 {
+    calculate_base_fee_per_gas(
+        header.gas_limit,
+        header.gas_limit,
+        header.gas_used,
+        header.base_fee_per_gas,
+    )
+    .unwrap_or(header.base_fee_per_gas)
+}
+
+fn next_blob_base_fee(header: &BlockHeader) -> U256 {
+    calculate_base_fee_per_blob_gas(calc_excess_blob_gas(
+        header.excess_blob_gas.unwrap_or_default(),
+        header.blob_gas_used.unwrap_or_default(),
+    ))
+}
+
+/// Returns the transactions of a block along with the gas each of them used
+fn transactions_gas_used(
+    header: &BlockHeader,
+    storage: &Store,
+) -> Result<Vec<(Transaction, u64)>, RpcErr> {
+    let body = storage
+        .get_block_body(header.number)
+        .map_err(|_| RpcErr::Internal)?
+        .ok_or(RpcErr::Internal)?;
+    let mut cumulative_gas_used = 0;
+    let mut transactions = Vec::new();
+    for (index, transaction) in body.transactions.into_iter().enumerate() {
+        let receipt = storage
+            .get_receipt(header.number, index as u64)
+            .map_err(|_| RpcErr::Internal)?
+            .ok_or(RpcErr::Internal)?;
+        let gas_used = receipt.cumulative_gas_used - cumulative_gas_used;
+        cumulative_gas_used = receipt.cumulative_gas_used;
+        transactions.push((transaction, gas_used));
+    }
+    Ok(transactions)
+}
+
+/// Returns the tip paid at each percentile of the gas used in the block
+/// Transactions are sorted by tip, and the tip at a percentile is the one of the transaction
+/// that, added to the cheaper ones, used that percentage of the block's gas
+fn block_rewards(
+    header: &BlockHeader,
+    transactions: &[(Transaction, u64)],
+    percentiles: &[f64],
+) -> Vec<U256> {
+    let mut tips: Vec<(u64, u64)> = transactions
+        .iter()
+        .map(|(transaction, gas_used)| {
+            let tip = transaction
+                .effective_gas_tip(Some(header.base_fee_per_gas))
+                .unwrap_or_default();
+            (tip, *gas_used)
+        })
+        .collect();
+    if tips.is_empty() {
+        return vec![U256::zero(); percentiles.len()];
+    }
+    tips.sort_unstable();
+    let mut index = 0;
+    let mut gas_used = tips[0].1;
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let threshold = (header.gas_used as f64 * percentile / 100.0) as u64;
+            while gas_used < threshold && index < tips.len() - 1 {
+                index += 1;
+                gas_used += tips[index].1;
+            }
+            U256::from(tips[index].0)
+        })
+        .collect()
+}
+
+/// Samples the lowest tips paid in recent blocks and returns a percentile of them
+fn suggest_tip(storage: &Store) -> Result<u64, RpcErr> {
+    let latest = latest_block_number(storage)?;
+    let mut samples = Vec::new();
+    for number in latest.saturating_sub(ORACLE_BLOCKS - 1)..=latest {
+        let header = get_header(number, storage)?;
+        let Some(body) = storage
+            .get_block_body(number)
+            .map_err(|_| RpcErr::Internal)?
+        else {
+            continue;
+        };
+        let mut tips: Vec<u64> = body
+            .transactions
+            .iter()
+            .filter_map(|transaction| transaction.effective_gas_tip(Some(header.base_fee_per_gas)))
+            .filter(|tip| *tip >= ORACLE_IGNORE_PRICE)
+            .collect();
+        tips.sort_unstable();
+        samples.extend(tips.into_iter().take(ORACLE_SAMPLES_PER_BLOCK));
+    }
+    if samples.is_empty() {
+        return Ok(DEFAULT_MAX_PRIORITY_FEE);
+    }
+    samples.sort_unstable();
+    Ok(samples[(samples.len() - 1) * ORACLE_PERCENTILE / 100])
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_rust_core::{
+        types::{EIP1559Transaction, TxKind},
+        Address, Bytes,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn transaction(max_fee_per_gas: u64, max_priority_fee_per_gas: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            chain_id: 1,
+            nonce: 0,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::repeat_byte(0xaa)),
+            value: U256::zero(),
+            data: Bytes::new(),
+            access_list: Vec::new(),
+            signature_y_parity: false,
+            signature_r: U256::zero(),
+            signature_s: U256::zero(),
+        })
+    }
+
+    #[test]
+    fn parse_fee_history_request() {
+        let parse =
+            |params: Value| FeeHistoryRequest::parse(&serde_json::from_value(params).unwrap());
+        let request = parse(json!(["0xa", "latest", [10.0, 50.0]])).unwrap();
+        assert_eq!(request.block_count, 10);
+        assert_eq!(request.reward_percentiles, Some(vec![10.0, 50.0]));
+        assert_eq!(parse(json!([4, "0x1"])).unwrap().block_count, 4);
+        // Percentiles must be increasing and within range
+        assert!(parse(json!(["0x1", "latest", [50.0, 10.0]])).is_none());
+        assert!(parse(json!(["0x1", "latest", [101.0]])).is_none());
+    }
+
+    #[test]
+    fn rewards_are_weighted_by_gas_used() {
+        let header = BlockHeader {
+            base_fee_per_gas: 10,
+            gas_used: 100_000,
+            ..Default::default()
+        };
+        let transactions = vec![
+            (transaction(100, 30), 50_000),
+            (transaction(100, 5), 20_000),
+            // Tips are capped by the fee cap
+            (transaction(30, 25), 30_000),
+        ];
+        let rewards = block_rewards(&header, &transactions, &[0.0, 20.0, 30.0, 50.0, 100.0]);
+        let expected: Vec<U256> = [5, 5, 20, 20, 30].into_iter().map(U256::from).collect();
+        assert_eq!(rewards, expected);
+        assert_eq!(block_rewards(&header, &[], &[50.0]), vec![U256::zero()]);
+    }
+
+    #[test]
+    fn next_block_fees() {
+        let header = BlockHeader {
+            gas_limit: 30_000_000,
+            gas_used: 30_000_000,
+            base_fee_per_gas: 1_000_000_000,
+            excess_blob_gas: Some(0),
+            blob_gas_used: Some(MAX_BLOB_GAS_PER_BLOCK),
+            ..Default::default()
+        };
+        // A full block raises the base fee by 12.5%
+        assert_eq!(next_base_fee(&header), 1_125_000_000);
+        assert_eq!(
+            next_blob_base_fee(&header),
+            calculate_base_fee_per_blob_gas(MAX_BLOB_GAS_PER_BLOCK / 2)
+        );
+    }
+}
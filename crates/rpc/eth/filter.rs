@@ -0,0 +1,484 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use ethereum_rust_core::{types::BlockNumber, H256};
+use ethereum_rust_storage::Store;
+use serde_json::Value;
+use tracing::info;
+
+use crate::utils::RpcErr;
+
+use super::{
+    block::resolve_block_number,
+    logs::{logs_in_range, resolve_logs_range, LogsFilter, LogsLimits, LogsRange},
+};
+
+/// Filters are removed if they are not polled for this long
+pub const DEFAULT_FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+pub struct NewFilterRequest {
+    pub filter: LogsFilter,
+}
+
+/// Request for eth_getFilterChanges, eth_getFilterLogs and eth_uninstallFilter
+pub struct FilterIdRequest {
+    pub id: u64,
+}
+
+/// What a filter reports, along with the position up to which changes were already reported
+#[derive(Debug)]
+enum FilterKind {
+    Logs {
+        filter: LogsFilter,
+        /// Starts right before the filter's first block, which is resolved when the filter is installed
+        last_block: BlockNumber,
+    },
+    Blocks {
+        last_block: BlockNumber,
+    },
+    PendingTransactions {
+        seen: HashSet<H256>,
+    },
+}
+
+#[derive(Debug)]
+struct ActiveFilter {
+    kind: FilterKind,
+    last_poll: Instant,
+}
+
+/// Filters installed by clients that poll for changes
+/// Filters that are not polled within the timeout are removed
+#[derive(Debug, Clone)]
+pub struct ActiveFilters {
+    filters: Arc<Mutex<HashMap<u64, ActiveFilter>>>,
+    next_id: Arc<Mutex<u64>>,
+    timeout: Duration,
+}
+
+impl Default for ActiveFilters {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILTER_TIMEOUT)
+    }
+}
+
+impl ActiveFilters {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            filters: Default::default(),
+            next_id: Arc::new(Mutex::new(1)),
+            timeout,
+        }
+    }
+
+    fn install(&self, kind: FilterKind) -> u64 {
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = *next_id;
+        *next_id += 1;
+        let mut filters = self.filters.lock().unwrap();
+        self.remove_expired(&mut filters);
+        filters.insert(
+            id,
+            ActiveFilter {
+                kind,
+                last_poll: Instant::now(),
+            },
+        );
+        id
+    }
+
+    fn remove_expired(&self, filters: &mut HashMap<u64, ActiveFilter>) {
+        filters.retain(|_, filter| filter.last_poll.elapsed() <= self.timeout);
+    }
+
+    // Moves the cursor of a logs or blocks filter, as long as it is still where it was when the changes were looked up
+    // Returns false if a concurrent poll already moved it or the filter was uninstalled, in which case the changes
+    // must not be reported again
+    fn advance_cursor(&self, id: u64, expected: BlockNumber, block_number: BlockNumber) -> bool {
+        let mut filters = self.filters.lock().unwrap();
+        let last_block = match filters.get_mut(&id).map(|filter| &mut filter.kind) {
+            Some(FilterKind::Logs { last_block, .. } | FilterKind::Blocks { last_block }) => {
+                last_block
+            }
+            _ => return false,
+        };
+        if *last_block != expected {
+            return false;
+        }
+        *last_block = block_number;
+        true
+    }
+}
+
+impl NewFilterRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<NewFilterRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        let filter = LogsFilter::parse(&params[0])?;
+        // Filters follow the chain, so they can't be pinned to a single block
+        if matches!(filter.range, LogsRange::Hash(_)) {
+            return None;
+        }
+        Some(NewFilterRequest { filter })
+    }
+}
+
+impl FilterIdRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<FilterIdRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        let id: String = serde_json::from_value(params[0].clone()).ok()?;
+        Some(FilterIdRequest {
+            id: u64::from_str_radix(id.strip_prefix("0x")?, 16).ok()?,
+        })
+    }
+}
+
+fn latest_block_number(storage: &Store) -> Result<BlockNumber, RpcErr> {
+    storage
+        .get_latest_block_number()
+        .map_err(|_| RpcErr::Internal)?
+        .ok_or(RpcErr::Internal)
+}
+
+fn filter_id(id: u64) -> Value {
+    Value::String(format!("{id:#x}"))
+}
+
+/// Installs a filter reporting the logs matching the criteria in blocks added after its creation
+pub fn new_filter(
+    request: NewFilterRequest,
+    storage: Store,
+    filters: ActiveFilters,
+) -> Result<Value, RpcErr> {
+    info!("Installing logs filter: {:?}", request.filter);
+    let LogsRange::Numbers { from, .. } = &request.filter.range else {
+        return Err(RpcErr::BadParams);
+    };
+    let from = match resolve_block_number(from, &storage) {
+        Ok(Some(from)) => from,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    // Only logs from blocks added after the filter was installed are reported
+    let last_block = latest_block_number(&storage)?.max(from.saturating_sub(1));
+    let id = filters.install(FilterKind::Logs {
+        filter: request.filter,
+        last_block,
+    });
+    Ok(filter_id(id))
+}
+
+/// Installs a filter reporting the hashes of the blocks added after its creation
+pub fn new_block_filter(storage: Store, filters: ActiveFilters) -> Result<Value, RpcErr> {
+    let last_block = latest_block_number(&storage)?;
+    let id = filters.install(FilterKind::Blocks { last_block });
+    Ok(filter_id(id))
+}
+
+/// Installs a filter reporting the hashes of the transactions added to the pool after its creation
+pub fn new_pending_transaction_filter(
+    storage: Store,
+    filters: ActiveFilters,
+) -> Result<Value, RpcErr> {
+    let seen = storage.get_pool_transaction_hashes().into_iter().collect();
+    let id = filters.install(FilterKind::PendingTransactions { seen });
+    Ok(filter_id(id))
+}
+
+/// Returns what the filter matched since it was last polled and moves its cursor forward
+pub fn get_filter_changes(
+    request: &FilterIdRequest,
+    storage: Store,
+    filters: ActiveFilters,
+    limits: LogsLimits,
+) -> Result<Value, RpcErr> {
+    // Only the cursor is read while holding the lock, as looking up the changes may take a while
+    let (filter, last_block) = {
+        let mut active_filters = filters.filters.lock().unwrap();
+        filters.remove_expired(&mut active_filters);
+        let active_filter = active_filters
+            .get_mut(&request.id)
+            .ok_or(RpcErr::FilterNotFound)?;
+        active_filter.last_poll = Instant::now();
+        match &mut active_filter.kind {
+            FilterKind::Logs { filter, last_block } => (Some(filter.clone()), *last_block),
+            FilterKind::Blocks { last_block } => (None, *last_block),
+            FilterKind::PendingTransactions { seen } => {
+                let current: HashSet<H256> =
+                    storage.get_pool_transaction_hashes().into_iter().collect();
+                let hashes: Vec<H256> = current.difference(seen).copied().collect();
+                *seen = current;
+                return serde_json::to_value(hashes).map_err(|_| RpcErr::Internal);
+            }
+        }
+    };
+    let latest = latest_block_number(&storage)?;
+
+    let Some(filter) = filter else {
+        let mut hashes = Vec::new();
+        for number in (last_block + 1)..=latest {
+            let Some(header) = storage
+                .get_block_header(number)
+                .map_err(|_| RpcErr::Internal)?
+            else {
+                continue;
+            };
+            hashes.push(header.compute_block_hash());
+        }
+        if !filters.advance_cursor(request.id, last_block, latest) {
+            return Ok(Value::Array(Vec::new()));
+        }
+        return serde_json::to_value(hashes).map_err(|_| RpcErr::Internal);
+    };
+
+    let LogsRange::Numbers { to, .. } = &filter.range else {
+        return Err(RpcErr::Internal);
+    };
+    let to = match resolve_block_number(to, &storage) {
+        Ok(Some(to)) => to,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    let from = last_block + 1;
+    // Clients that fall too far behind catch up over several polls instead of failing
+    let to = to
+        .min(latest)
+        .min(from.saturating_add(limits.max_block_range.saturating_sub(1)));
+    if from > to {
+        filters.advance_cursor(request.id, last_block, latest.min(last_block));
+        return Ok(Value::Array(Vec::new()));
+    }
+    let logs = logs_in_range(&filter, from, to, &storage, limits)?;
+    if !filters.advance_cursor(request.id, last_block, to) {
+        return Ok(Value::Array(Vec::new()));
+    }
+    serde_json::to_value(logs).map_err(|_| RpcErr::Internal)
+}
+
+/// Returns all the logs matching a logs filter, regardless of what was already reported
+pub fn get_filter_logs(
+    request: &FilterIdRequest,
+    storage: Store,
+    filters: ActiveFilters,
+    limits: LogsLimits,
+) -> Result<Value, RpcErr> {
+    let filter = {
+        let mut active_filters = filters.filters.lock().unwrap();
+        filters.remove_expired(&mut active_filters);
+        let filter = active_filters
+            .get_mut(&request.id)
+            .ok_or(RpcErr::FilterNotFound)?;
+        filter.last_poll = Instant::now();
+        match &filter.kind {
+            FilterKind::Logs { filter, .. } => filter.clone(),
+            _ => return Err(RpcErr::BadParams),
+        }
+    };
+    let (from, to) = resolve_logs_range(&filter.range, &storage)?;
+    let logs = logs_in_range(&filter, from, to, &storage, limits)?;
+    serde_json::to_value(logs).map_err(|_| RpcErr::Internal)
+}
+
+/// Removes the filter, returns whether it existed
+pub fn uninstall_filter(
+    request: &FilterIdRequest,
+    filters: ActiveFilters,
+) -> Result<Value, RpcErr> {
+    let mut active_filters = filters.filters.lock().unwrap();
+    filters.remove_expired(&mut active_filters);
+    Ok(Value::Bool(active_filters.remove(&request.id).is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_rust_core::{
+        types::{BlockBody, BlockHeader, Log, Receipt, Transaction, TxType},
+        Address, Bytes,
+    };
+    use ethereum_rust_storage::EngineType;
+    use serde_json::json;
+
+    use super::*;
+
+    fn add_block(storage: &Store, number: BlockNumber) -> H256 {
+        let header = BlockHeader {
+            number,
+            ..Default::default()
+        };
+        let hash = header.compute_block_hash();
+        storage.add_block_number(hash, number).unwrap();
+        storage.add_block_header(number, header).unwrap();
+        storage.update_latest_block_number(number).unwrap();
+        hash
+    }
+
+    // Adds a block with a single transaction emitting a log from the given address
+    fn add_block_with_log(storage: &Store, number: BlockNumber, address: Address) {
+        let logs = vec![Log {
+            address,
+            topics: vec![],
+            data: Bytes::new(),
+        }];
+        let transaction = Transaction::decode_canonical(&hex::decode("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap()).unwrap();
+        let header = BlockHeader {
+            number,
+            logs_bloom: Log::bloom(&logs),
+            ..Default::default()
+        };
+        storage
+            .add_block_number(header.compute_block_hash(), number)
+            .unwrap();
+        storage
+            .add_block_body(
+                number,
+                BlockBody {
+                    transactions: vec![transaction],
+                    ommers: vec![],
+                    withdrawals: None,
+                },
+            )
+            .unwrap();
+        storage.add_block_header(number, header).unwrap();
+        storage
+            .add_receipt(
+                number,
+                0,
+                Receipt::new(TxType::Legacy, true, 21_000, Log::bloom(&logs), logs),
+            )
+            .unwrap();
+        storage.update_latest_block_number(number).unwrap();
+    }
+
+    fn id_request(id: &Value) -> FilterIdRequest {
+        FilterIdRequest::parse(&Some(vec![id.clone()])).unwrap()
+    }
+
+    #[test]
+    fn block_filter_reports_new_blocks_once() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let filters = ActiveFilters::default();
+        add_block(&storage, 0);
+        let id = new_block_filter(storage.clone(), filters.clone()).unwrap();
+        let changes = |filters: &ActiveFilters| {
+            get_filter_changes(
+                &id_request(&id),
+                storage.clone(),
+                filters.clone(),
+                LogsLimits::default(),
+            )
+            .unwrap()
+        };
+        assert_eq!(changes(&filters), json!([]));
+        let first = add_block(&storage, 1);
+        let second = add_block(&storage, 2);
+        assert_eq!(changes(&filters), json!([first, second]));
+        assert_eq!(changes(&filters), json!([]));
+        // Logs can only be fetched from logs filters
+        assert!(matches!(
+            get_filter_logs(
+                &id_request(&id),
+                storage.clone(),
+                filters.clone(),
+                LogsLimits::default()
+            ),
+            Err(RpcErr::BadParams)
+        ));
+        assert_eq!(
+            uninstall_filter(&id_request(&id), filters.clone()).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            uninstall_filter(&id_request(&id), filters).unwrap(),
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn logs_filter_reports_every_new_block() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let filters = ActiveFilters::default();
+        let address = Address::repeat_byte(0xaa);
+        add_block_with_log(&storage, 0, address);
+        let request = NewFilterRequest::parse(&Some(vec![json!({"address": address})])).unwrap();
+        let id = new_filter(request, storage.clone(), filters.clone()).unwrap();
+        let changes = || {
+            get_filter_changes(
+                &id_request(&id),
+                storage.clone(),
+                filters.clone(),
+                LogsLimits::default(),
+            )
+            .unwrap()
+        };
+        // Logs emitted before the filter was installed are not reported
+        assert_eq!(changes(), json!([]));
+        // Every block added between polls is reported, not just the latest one
+        add_block_with_log(&storage, 1, address);
+        add_block_with_log(&storage, 2, address);
+        add_block_with_log(&storage, 3, Address::repeat_byte(0xbb));
+        let logs = changes();
+        let block_numbers: Vec<&Value> = logs
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|log| &log["blockNumber"])
+            .collect();
+        assert_eq!(block_numbers, vec!["0x1", "0x2"]);
+        assert_eq!(changes(), json!([]));
+        add_block_with_log(&storage, 4, address);
+        assert_eq!(changes().as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cursor_only_advances_from_its_expected_position() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let filters = ActiveFilters::default();
+        add_block(&storage, 0);
+        let id = new_block_filter(storage.clone(), filters.clone()).unwrap();
+        add_block(&storage, 1);
+        let id_number = id_request(&id).id;
+        // A concurrent poll that read the same cursor and moved it first reports the changes
+        assert!(filters.advance_cursor(id_number, 0, 1));
+        assert!(!filters.advance_cursor(id_number, 0, 1));
+        let changes = || {
+            get_filter_changes(
+                &id_request(&id),
+                storage.clone(),
+                filters.clone(),
+                LogsLimits::default(),
+            )
+            .unwrap()
+        };
+        assert_eq!(changes(), json!([]));
+        let second = add_block(&storage, 2);
+        assert_eq!(changes(), json!([second]));
+        // Uninstalled filters have no cursor to move
+        uninstall_filter(&id_request(&id), filters.clone()).unwrap();
+        assert!(!filters.advance_cursor(id_number, 2, 3));
+    }
+
+    #[test]
+    fn filters_expire_when_not_polled() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        add_block(&storage, 0);
+        let filters = ActiveFilters::new(Duration::ZERO);
+        let id = new_pending_transaction_filter(storage.clone(), filters.clone()).unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(matches!(
+            get_filter_changes(&id_request(&id), storage, filters, LogsLimits::default()),
+            Err(RpcErr::FilterNotFound)
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+use ethereum_rust_core::{
+    types::{BlockHash, BlockHeader, BlockNumber, Log},
+    Address, Bloom, BloomInput, H256,
+};
+use ethereum_rust_storage::{error::StoreError, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::utils::RpcErr;
+
+use super::block::{resolve_block_number, BlockIdentifier};
+
+/// Limits on the amount of work a single log query can do
+#[derive(Debug, Clone, Copy)]
+pub struct LogsLimits {
+    /// Maximum amount of blocks that can be queried at once
+    pub max_block_range: u64,
+    /// Maximum amount of logs a query can return
+    pub max_results: usize,
+}
+
+impl Default for LogsLimits {
+    fn default() -> Self {
+        Self {
+            max_block_range: 10_000,
+            max_results: 10_000,
+        }
+    }
+}
+
+pub struct GetLogsRequest {
+    pub filter: LogsFilter,
+}
+
+/// Blocks to look for logs in, either a range of block numbers or a single block hash
+#[derive(Clone, Debug)]
+pub enum LogsRange {
+    Numbers {
+        from: BlockIdentifier,
+        to: BlockIdentifier,
+    },
+    Hash(BlockHash),
+}
+
+/// Criteria logs must match
+/// A log matches if it was emitted by any of the addresses (or any address if empty), and its topics
+/// match each position of `topics`, where each position accepts any of the given topics (or any topic if empty)
+#[derive(Clone, Debug)]
+pub struct LogsFilter {
+    pub range: LogsRange,
+    pub addresses: Vec<Address>,
+    pub topics: Vec<Vec<H256>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<OneOrMany<T>> for Vec<T> {
+    fn from(value: OneOrMany<T>) -> Self {
+        match value {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogsFilterParams {
+    from_block: Option<BlockIdentifier>,
+    to_block: Option<BlockIdentifier>,
+    block_hash: Option<BlockHash>,
+    address: Option<OneOrMany<Address>>,
+    #[serde(default)]
+    topics: Vec<Option<OneOrMany<H256>>>,
+}
+
+/// Log along with the block and transaction it was emitted in
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcLog {
+    #[serde(flatten)]
+    pub log: Log,
+    pub block_hash: BlockHash,
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    pub block_number: BlockNumber,
+    pub transaction_hash: H256,
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    pub transaction_index: u64,
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    pub log_index: u64,
+    pub removed: bool,
+}
+
+impl GetLogsRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<GetLogsRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        Some(GetLogsRequest {
+            filter: LogsFilter::parse(&params[0])?,
+        })
+    }
+}
+
+impl LogsFilter {
+    pub fn parse(value: &Value) -> Option<LogsFilter> {
+        let params: LogsFilterParams = serde_json::from_value(value.clone()).ok()?;
+        let range = match (params.block_hash, params.from_block, params.to_block) {
+            (Some(hash), None, None) => LogsRange::Hash(hash),
+            // A block hash can't be combined with a block range
+            (Some(_), _, _) => return None,
+            (None, from, to) => LogsRange::Numbers {
+                from: from.unwrap_or_default(),
+                to: to.unwrap_or_default(),
+            },
+        };
+        Some(LogsFilter {
+            range,
+            addresses: params.address.map(Vec::from).unwrap_or_default(),
+            topics: params
+                .topics
+                .into_iter()
+                .map(|topics| topics.map(Vec::from).unwrap_or_default())
+                .collect(),
+        })
+    }
+
+    /// Returns false if the bloom shows that none of the logs it was built from match the filter
+    pub fn bloom_may_match(&self, bloom: &Bloom) -> bool {
+        let in_bloom = |input: &[u8]| bloom.contains_input(BloomInput::Raw(input));
+        (self.addresses.is_empty()
+            || self
+                .addresses
+                .iter()
+                .any(|address| in_bloom(address.as_bytes())))
+            && self.topics.iter().all(|topics| {
+                topics.is_empty() || topics.iter().any(|topic| in_bloom(topic.as_bytes()))
+            })
+    }
+
+    pub fn matches(&self, log: &Log) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, topics)| {
+            topics.is_empty()
+                || log
+                    .topics
+                    .get(position)
+                    .is_some_and(|topic| topics.contains(topic))
+        })
+    }
+}
+
+/// Returns the logs matching the filter, in the order they were emitted
+pub fn get_logs(
+    request: &GetLogsRequest,
+    storage: Store,
+    limits: LogsLimits,
+) -> Result<Value, RpcErr> {
+    let filter = &request.filter;
+    info!("Requested logs with filter: {:?}", filter);
+    let (from, to) = resolve_logs_range(&filter.range, &storage)?;
+    let logs = logs_in_range(filter, from, to, &storage, limits)?;
+    serde_json::to_value(logs).map_err(|_| RpcErr::Internal)
+}
+
+/// Returns the first and last block numbers covered by the range
+pub(crate) fn resolve_logs_range(
+    range: &LogsRange,
+    storage: &Store,
+) -> Result<(BlockNumber, BlockNumber), RpcErr> {
+    match range {
+        LogsRange::Hash(hash) => match storage.get_block_number(*hash) {
+            Ok(Some(number)) => Ok((number, number)),
+            Ok(_) => Err(RpcErr::BadParams),
+            _ => Err(RpcErr::Internal),
+        },
+        LogsRange::Numbers { from, to } => {
+            match (
+                resolve_block_number(from, storage),
+                resolve_block_number(to, storage),
+            ) {
+                (Ok(Some(from)), Ok(Some(to))) if from <= to => Ok((from, to)),
+                (Ok(_), Ok(_)) => Err(RpcErr::BadParams),
+                _ => Err(RpcErr::Internal),
+            }
+        }
+    }
+}
+
+/// Returns the logs matching the filter emitted between the given blocks (inclusive)
+/// Fails if the range or the amount of logs found exceed the limits
+pub(crate) fn logs_in_range(
+    filter: &LogsFilter,
+    from: BlockNumber,
+    to: BlockNumber,
+    storage: &Store,
+    limits: LogsLimits,
+) -> Result<Vec<RpcLog>, RpcErr> {
+    if to - from >= limits.max_block_range {
+        return Err(RpcErr::LimitExceeded(format!(
+            "block range exceeds the maximum of {}",
+            limits.max_block_range
+        )));
+    }
+    let mut logs = Vec::new();
+    for number in from..=to {
+        let Some(header) = storage
+            .get_block_header(number)
+            .map_err(|_| RpcErr::Internal)?
+        else {
+            continue;
+        };
+        logs.extend(block_logs(&header, filter, storage).map_err(|_| RpcErr::Internal)?);
+        if logs.len() > limits.max_results {
+            return Err(RpcErr::LimitExceeded(format!(
+                "query returned more than {} results",
+                limits.max_results
+            )));
+        }
+    }
+    Ok(logs)
+}
+
+/// Returns the logs in the given block that match the filter
+pub(crate) fn block_logs(
+    header: &BlockHeader,
+    filter: &LogsFilter,
+    storage: &Store,
+) -> Result<Vec<RpcLog>, StoreError> {
+    if !filter.bloom_may_match(&header.logs_bloom) {
+        return Ok(Vec::new());
+    }
+    let Some(body) = storage.get_block_body(header.number)? else {
+        return Ok(Vec::new());
+    };
+    let block_hash = header.compute_block_hash();
+    let mut logs = Vec::new();
+    let mut log_index = 0;
+    for (index, transaction) in body.transactions.iter().enumerate() {
+        let transaction_index = index as u64;
+        let Some(receipt) = storage.get_receipt(header.number, transaction_index)? else {
+            continue;
+        };
+        let transaction_hash = transaction.compute_hash();
+        for log in receipt.logs {
+            if filter.matches(&log) {
+                logs.push(RpcLog {
+                    log,
+                    block_hash,
+                    block_number: header.number,
+                    transaction_hash,
+                    transaction_index,
+                    log_index,
+                    removed: false,
+                });
+            }
+            log_index += 1;
+        }
+    }
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_rust_core::Bytes;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_logs_filter() {
+        let address = Address::repeat_byte(1);
+        let topic = H256::repeat_byte(2);
+        let filter = LogsFilter::parse(&json!({
+            "fromBlock": "0x1",
+            "address": address,
+            "topics": [null, [topic, topic], topic]
+        }))
+        .unwrap();
+        assert!(matches!(
+            filter.range,
+            LogsRange::Numbers {
+                from: BlockIdentifier::Number(1),
+                to: BlockIdentifier::Tag(_)
+            }
+        ));
+        assert_eq!(filter.addresses, vec![address]);
+        assert_eq!(filter.topics, vec![vec![], vec![topic, topic], vec![topic]]);
+        // Block hash and block range are mutually exclusive
+        assert!(LogsFilter::parse(&json!({"blockHash": topic, "toBlock": "latest"})).is_none());
+    }
+
+    #[test]
+    fn filter_matches_logs() {
+        let (first, second) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        let log = Log {
+            address: Address::repeat_byte(0xaa),
+            topics: vec![first, second],
+            data: Bytes::new(),
+        };
+        let filter = |addresses, topics| LogsFilter {
+            range: LogsRange::Hash(H256::zero()),
+            addresses,
+            topics,
+        };
+        let bloom = Log::bloom(std::slice::from_ref(&log));
+        for (filter, expected) in [
+            (filter(vec![], vec![]), true),
+            (filter(vec![log.address], vec![vec![], vec![second]]), true),
+            (filter(vec![], vec![vec![second, first]]), true),
+            (filter(vec![Address::zero()], vec![]), false),
+            (filter(vec![], vec![vec![second]]), false),
+            (filter(vec![], vec![vec![], vec![], vec![first]]), false),
+        ] {
+            assert_eq!(filter.matches(&log), expected);
+            // The bloom can only give false positives
+            if expected {
+                assert!(filter.bloom_may_match(&bloom));
+            }
+        }
+        assert!(!filter(vec![Address::zero()], vec![]).bloom_may_match(&bloom));
+    }
+}
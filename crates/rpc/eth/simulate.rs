@@ -0,0 +1,322 @@
+use ethereum_rust_core::{
+    types::{BlockHash, BlockHeader, BlockOverrides, GenericTransaction, Log, StateOverride},
+    Bytes, H256,
+};
+use ethereum_rust_evm::{
+    apply_state_overrides, evm_state_at_block, simulate_tx_from_generic_and_commit, spec_id,
+    EvmError, ExecutionResult, Output,
+};
+use ethereum_rust_storage::Store;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::utils::{RpcErr, RpcErrorMetadata};
+
+use super::{
+    block::BlockIdentifier,
+    fee_market::next_base_fee,
+    logs::RpcLog,
+    transaction::{failed_execution_error, resolve_header_and_spec},
+};
+
+/// Maximum amount of blocks that can be simulated in a single request
+const MAX_SIMULATED_BLOCKS: usize = 256;
+/// Time between simulated blocks whose timestamp is not overridden
+const SIMULATED_BLOCK_TIME: u64 = 12;
+
+pub struct SimulateRequest {
+    pub payload: SimulatePayload,
+    pub block: BlockIdentifier,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatePayload {
+    pub block_state_calls: Vec<BlockStateCall>,
+}
+
+/// Calls to simulate in a block, on top of the given overrides
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStateCall {
+    #[serde(default)]
+    pub block_overrides: Option<BlockOverrides>,
+    #[serde(default)]
+    pub state_overrides: Option<StateOverride>,
+    #[serde(default)]
+    pub calls: Vec<GenericTransaction>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SimulatedBlock {
+    #[serde(flatten)]
+    header: BlockHeader,
+    hash: BlockHash,
+    calls: Vec<SimulatedCall>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SimulatedCall {
+    #[serde(with = "ethereum_rust_core::serde_utils::bytes")]
+    return_data: Bytes,
+    logs: Vec<RpcLog>,
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    gas_used: u64,
+    #[serde(with = "ethereum_rust_core::serde_utils::u64::hex_str")]
+    status: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcErrorMetadata>,
+}
+
+impl SimulateRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<SimulateRequest> {
+        let params = params.as_ref()?;
+        if params.is_empty() || params.len() > 2 {
+            return None;
+        };
+        let payload: SimulatePayload = serde_json::from_value(params[0].clone()).ok()?;
+        let valid_overrides = payload.block_state_calls.iter().all(|block_state_call| {
+            block_state_call
+                .state_overrides
+                .as_ref()
+                .map_or(true, is_valid_state_override)
+        });
+        if !valid_overrides {
+            return None;
+        }
+        let block = match params.get(1) {
+            Some(value) => serde_json::from_value(value.clone()).ok()?,
+            None => BlockIdentifier::Tag(Default::default()),
+        };
+        Some(SimulateRequest { payload, block })
+    }
+}
+
+/// Parses the state overrides accepted by calls and simulations
+pub(crate) fn parse_state_override(value: &Value) -> Option<StateOverride> {
+    let overrides = serde_json::from_value(value.clone()).ok()?;
+    is_valid_state_override(&overrides).then_some(overrides)
+}
+
+// The whole storage of an account and some of its slots can't be overridden at the same time
+fn is_valid_state_override(overrides: &StateOverride) -> bool {
+    overrides
+        .values()
+        .all(|account| account.state.is_none() || account.state_diff.is_none())
+}
+
+/// Simulates the calls of each block on top of the requested block, along with the state and block overrides
+/// Each block is built on top of the previous one, and nothing is persisted
+/// Numbers skipped by a block's overrides are filled with empty blocks
+/// The state root of simulated blocks is not recomputed, they keep the one of the requested block
+pub fn simulate_v1(request: &SimulateRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested simulation on block: {}", request.block);
+    let block_state_calls = &request.payload.block_state_calls;
+    check_simulated_blocks(block_state_calls.len())?;
+    let (mut parent, _) = resolve_header_and_spec(&request.block, &storage)?;
+    let chain_config = match storage.get_chain_config() {
+        Ok(Some(chain_config)) => chain_config,
+        _ => return Err(RpcErr::Internal),
+    };
+    let mut state = evm_state_at_block(storage, parent.number);
+    let mut blocks = Vec::with_capacity(block_state_calls.len());
+    for block_state_call in block_state_calls {
+        let block_overrides = block_state_call.block_overrides.as_ref();
+        if let Some(number) = block_overrides.and_then(|overrides| overrides.number) {
+            // Simulated blocks must follow each other
+            if number <= parent.number {
+                return Err(RpcErr::BadParams);
+            }
+            while parent.number + 1 < number {
+                check_simulated_blocks(blocks.len() + 1)?;
+                let header = child_header(&parent);
+                let hash = header.compute_block_hash();
+                parent = header.clone();
+                blocks.push(SimulatedBlock {
+                    header,
+                    hash,
+                    calls: Vec::new(),
+                });
+            }
+        }
+        check_simulated_blocks(blocks.len() + 1)?;
+        let mut header = child_header(&parent);
+        if let Some(block_overrides) = block_overrides {
+            block_overrides.apply(&mut header);
+        }
+        if header.timestamp <= parent.timestamp {
+            return Err(RpcErr::BadParams);
+        }
+        if let Some(state_overrides) = &block_state_call.state_overrides {
+            apply_state_overrides(&mut state, state_overrides).map_err(|_| RpcErr::BadParams)?;
+        }
+        let spec_id = spec_id(&chain_config, header.timestamp);
+
+        let mut results = Vec::with_capacity(block_state_call.calls.len());
+        for call in &block_state_call.calls {
+            let result = simulate_tx_from_generic_and_commit(call, &header, &mut state, spec_id)
+                .map_err(|error| match error {
+                    EvmError::Transaction(_) => RpcErr::InvalidTransaction(error.to_string()),
+                    _ => RpcErr::Vm,
+                })?;
+            results.push(result);
+        }
+        let logs: Vec<Log> = results.iter().flat_map(ExecutionResult::logs).collect();
+        header.gas_used = results.iter().map(ExecutionResult::gas_used).sum();
+        header.logs_bloom = Log::bloom(&logs);
+        let hash = header.compute_block_hash();
+
+        let mut log_index = 0;
+        let calls = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| simulated_call(result, &header, hash, index, &mut log_index))
+            .collect();
+        parent = header.clone();
+        blocks.push(SimulatedBlock {
+            header,
+            hash,
+            calls,
+        });
+    }
+    serde_json::to_value(blocks).map_err(|_| RpcErr::Internal)
+}
+
+// Fails if the amount of simulated blocks exceeds the limit, counting the empty ones that fill number gaps
+fn check_simulated_blocks(amount: usize) -> Result<(), RpcErr> {
+    if amount > MAX_SIMULATED_BLOCKS {
+        return Err(RpcErr::LimitExceeded(format!(
+            "simulations can't have more than {MAX_SIMULATED_BLOCKS} blocks"
+        )));
+    }
+    Ok(())
+}
+
+// Builds the header of an empty block on top of the parent, before applying any overrides
+fn child_header(parent: &BlockHeader) -> BlockHeader {
+    BlockHeader {
+        parent_hash: parent.compute_block_hash(),
+        number: parent.number + 1,
+        timestamp: parent.timestamp + SIMULATED_BLOCK_TIME,
+        base_fee_per_gas: next_base_fee(parent),
+        gas_used: 0,
+        logs_bloom: Default::default(),
+        ..parent.clone()
+    }
+}
+
+// Builds the result of a call, numbering its logs after the ones emitted by previous calls in the block
+// Calls are not transactions, so their logs have no transaction hash
+fn simulated_call(
+    result: ExecutionResult,
+    header: &BlockHeader,
+    block_hash: BlockHash,
+    index: usize,
+    log_index: &mut u64,
+) -> SimulatedCall {
+    let gas_used = result.gas_used();
+    match result {
+        ExecutionResult::Success { logs, output, .. } => {
+            let return_data = match output {
+                Output::Call(output) | Output::Create(output, _) => output,
+            };
+            let logs = logs
+                .into_iter()
+                .map(|log| {
+                    let log = RpcLog {
+                        log,
+                        block_hash,
+                        block_number: header.number,
+                        transaction_hash: H256::zero(),
+                        transaction_index: index as u64,
+                        log_index: *log_index,
+                        removed: false,
+                    };
+                    *log_index += 1;
+                    log
+                })
+                .collect();
+            SimulatedCall {
+                return_data,
+                logs,
+                gas_used,
+                status: 1,
+                error: None,
+            }
+        }
+        result => {
+            let return_data = match &result {
+                ExecutionResult::Revert { output, .. } => output.clone(),
+                _ => Bytes::new(),
+            };
+            SimulatedCall {
+                return_data,
+                logs: Vec::new(),
+                gas_used,
+                status: 0,
+                error: Some(failed_execution_error(result).into()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_simulate_request() {
+        let call = json!({
+            "from": "0x0000000000000000000000000000000000000001",
+            "to": "0x0000000000000000000000000000000000000002",
+        });
+        let request = SimulateRequest::parse(&Some(vec![
+            json!({
+                "blockStateCalls": [
+                    {
+                        "blockOverrides": {"number": "0x10", "feeRecipient": "0x0000000000000000000000000000000000000003"},
+                        "stateOverrides": {"0x0000000000000000000000000000000000000001": {"balance": "0x100"}},
+                        "calls": [call],
+                    },
+                    {"calls": [call, call]},
+                ],
+            }),
+            json!("0x5"),
+        ]))
+        .unwrap();
+        let block_state_calls = &request.payload.block_state_calls;
+        assert_eq!(block_state_calls.len(), 2);
+        assert_eq!(
+            block_state_calls[0]
+                .block_overrides
+                .as_ref()
+                .unwrap()
+                .number,
+            Some(16)
+        );
+        assert!(block_state_calls[1].state_overrides.is_none());
+        assert_eq!(block_state_calls[1].calls.len(), 2);
+        assert!(matches!(request.block, BlockIdentifier::Number(5)));
+    }
+
+    #[test]
+    fn reject_conflicting_storage_overrides() {
+        let slots = json!({
+            "0x0000000000000000000000000000000000000000000000000000000000000001":
+                "0x0000000000000000000000000000000000000000000000000000000000000002",
+        });
+        let overrides = json!({
+            "0x0000000000000000000000000000000000000001": {"state": slots, "stateDiff": slots},
+        });
+        assert!(parse_state_override(&overrides).is_none());
+        let overrides = json!({
+            "0x0000000000000000000000000000000000000001": {"stateDiff": slots},
+        });
+        assert!(parse_state_override(&overrides).is_some());
+    }
+}
@@ -0,0 +1,279 @@
+use ethereum_rust_consensus::{mempool, MempoolError};
+use ethereum_rust_core::{
+    types::{BlockHeader, GenericTransaction, StateOverride, Transaction},
+    U256,
+};
+use ethereum_rust_evm::{
+    apply_state_overrides, evm_state_at_block, simulate_tx_from_generic, spec_id, EvmError,
+    ExecutionResult, Output, SpecId,
+};
+use ethereum_rust_storage::Store;
+use serde_json::Value;
+use tracing::info;
+
+use crate::utils::RpcErr;
+
+use super::{
+    block::{resolve_block_number, BlockIdentifier},
+    simulate::parse_state_override,
+};
+
+/// Gas refunded to the callee of a value transfer, which must be available on top of the gas used
+const CALL_STIPEND: u64 = 2_300;
+
+pub struct CallRequest {
+    pub transaction: GenericTransaction,
+    pub block: Option<BlockIdentifier>,
+    pub state_overrides: Option<StateOverride>,
+}
+
+pub struct EstimateGasRequest {
+    pub transaction: GenericTransaction,
+    pub block: Option<BlockIdentifier>,
+    pub state_overrides: Option<StateOverride>,
+}
+
+pub struct SendRawTransactionRequest {
+    pub transaction: Transaction,
+}
+
+impl CallRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<CallRequest> {
+        let params = params.as_ref()?;
+        if params.is_empty() || params.len() > 3 {
+            return None;
+        };
+        let block = match params.get(1) {
+            // Differentiate between missing and bad block param
+            Some(value) => Some(serde_json::from_value(value.clone()).ok()?),
+            None => None,
+        };
+        let state_overrides = match params.get(2) {
+            Some(value) => Some(parse_state_override(value)?),
+            None => None,
+        };
+        Some(CallRequest {
+            transaction: serde_json::from_value(params[0].clone()).ok()?,
+            block,
+            state_overrides,
+        })
+    }
+}
+
+impl EstimateGasRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<EstimateGasRequest> {
+        let params = params.as_ref()?;
+        if params.is_empty() || params.len() > 3 {
+            return None;
+        };
+        let block = match params.get(1) {
+            // Differentiate between missing and bad block param
+            Some(value) => Some(serde_json::from_value(value.clone()).ok()?),
+            None => None,
+        };
+        let state_overrides = match params.get(2) {
+            Some(value) => Some(parse_state_override(value)?),
+            None => None,
+        };
+        Some(EstimateGasRequest {
+            transaction: serde_json::from_value(params[0].clone()).ok()?,
+            block,
+            state_overrides,
+        })
+    }
+}
+
+impl SendRawTransactionRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<SendRawTransactionRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        let encoded: String = serde_json::from_value(params[0].clone()).ok()?;
+        let encoded = hex::decode(encoded.strip_prefix("0x")?).ok()?;
+        Some(SendRawTransactionRequest {
+            transaction: Transaction::decode_canonical(&encoded).ok()?,
+        })
+    }
+}
+
+/// Executes the transaction on top of the requested block without creating a transaction on chain
+/// Returns the output of the call, or the revert data if the call reverted
+pub fn call(request: &CallRequest, storage: Store) -> Result<Value, RpcErr> {
+    let block = request.block.clone().unwrap_or_default();
+    info!("Requested call on block: {}", block);
+    let (header, spec_id) = resolve_header_and_spec(&block, &storage)?;
+    let result = simulate(
+        &request.transaction,
+        &header,
+        &storage,
+        request.state_overrides.as_ref(),
+        spec_id,
+    )
+    .map_err(|_| RpcErr::Vm)?;
+    match result {
+        ExecutionResult::Success {
+            output: Output::Call(output) | Output::Create(output, _),
+            ..
+        } => {
+            serde_json::to_value(format!("0x{}", hex::encode(output))).map_err(|_| RpcErr::Internal)
+        }
+        result => Err(failed_execution_error(result)),
+    }
+}
+
+/// Returns the lowest gas limit with which the transaction executes successfully on top of the requested block
+/// The gas limit is searched for between the gas used by the transaction and a cap given by the block gas limit,
+/// the gas limit of the transaction if set, and the gas the sender can pay for
+pub fn estimate_gas(request: &EstimateGasRequest, storage: Store) -> Result<Value, RpcErr> {
+    let block = request.block.clone().unwrap_or_default();
+    info!("Requested gas estimation on block: {}", block);
+    let (header, spec_id) = resolve_header_and_spec(&block, &storage)?;
+    let mut transaction = request.transaction.clone();
+    let overrides = request.state_overrides.as_ref();
+
+    // Find the highest gas limit the transaction could use
+    let mut highest_gas_limit = header.gas_limit;
+    if let Some(gas) = transaction.gas {
+        highest_gas_limit = highest_gas_limit.min(gas);
+    }
+    let gas_price = transaction.max_fee_per_gas.unwrap_or(transaction.gas_price);
+    if gas_price != 0 {
+        let overridden_balance = overrides
+            .and_then(|overrides| overrides.get(&transaction.from))
+            .and_then(|account_override| account_override.balance);
+        let balance = match overridden_balance {
+            Some(balance) => balance,
+            None => storage
+                .get_account_info_by_block(header.number, transaction.from)
+                .map_err(|_| RpcErr::Internal)?
+                .map(|info| info.balance)
+                .unwrap_or_default(),
+        };
+        let allowance = balance.saturating_sub(transaction.value) / gas_price;
+        if allowance < U256::from(highest_gas_limit) {
+            highest_gas_limit = allowance.as_u64();
+        }
+    }
+
+    // If the transaction fails with the highest gas limit it will fail with any other
+    transaction.gas = Some(highest_gas_limit);
+    let result =
+        simulate(&transaction, &header, &storage, overrides, spec_id).map_err(|_| RpcErr::Vm)?;
+    let ExecutionResult::Success {
+        gas_used,
+        gas_refunded,
+        ..
+    } = result
+    else {
+        return Err(failed_execution_error(result));
+    };
+
+    // The gas used is the lower bound, but more gas may be needed to cover refunds and the
+    // gas retained by each call frame (EIP-150), so try with enough gas to cover them first
+    let mut lowest_gas_limit = gas_used.saturating_sub(1);
+    let optimistic_gas_limit = (gas_used + gas_refunded + CALL_STIPEND) * 64 / 63;
+    if optimistic_gas_limit < highest_gas_limit {
+        transaction.gas = Some(optimistic_gas_limit);
+        if simulation_succeeds(&transaction, &header, &storage, overrides, spec_id)? {
+            highest_gas_limit = optimistic_gas_limit;
+        } else {
+            lowest_gas_limit = optimistic_gas_limit;
+        }
+    }
+
+    // Binary search for the lowest gas limit that succeeds
+    while lowest_gas_limit + 1 < highest_gas_limit {
+        let middle_gas_limit = lowest_gas_limit + (highest_gas_limit - lowest_gas_limit) / 2;
+        transaction.gas = Some(middle_gas_limit);
+        if simulation_succeeds(&transaction, &header, &storage, overrides, spec_id)? {
+            highest_gas_limit = middle_gas_limit;
+        } else {
+            lowest_gas_limit = middle_gas_limit;
+        }
+    }
+
+    serde_json::to_value(format!("{:#x}", highest_gas_limit)).map_err(|_| RpcErr::Internal)
+}
+
+/// Validates the transaction against the latest state and adds it to the mempool
+/// Returns the hash of the transaction
+pub fn send_raw_transaction(
+    request: &SendRawTransactionRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    let hash = match mempool::add_transaction(request.transaction.clone(), &storage) {
+        Ok(hash) => hash,
+        Err(MempoolError::Store(_)) => return Err(RpcErr::Internal),
+        Err(error) => return Err(RpcErr::InvalidTransaction(error.to_string())),
+    };
+    info!("Added transaction {hash:#x} to the mempool");
+    serde_json::to_value(format!("{hash:#x}")).map_err(|_| RpcErr::Internal)
+}
+
+// Returns the header of the requested block along with the spec to execute transactions on top of it
+pub(crate) fn resolve_header_and_spec(
+    block: &BlockIdentifier,
+    storage: &Store,
+) -> Result<(BlockHeader, SpecId), RpcErr> {
+    let header = match resolve_block_number(block, storage) {
+        Ok(Some(block_number)) => match storage.get_block_header(block_number) {
+            Ok(Some(header)) => header,
+            // Block not found
+            Ok(_) => return Err(RpcErr::BadParams),
+            // DB error
+            _ => return Err(RpcErr::Internal),
+        },
+        // Block not found
+        Ok(_) => return Err(RpcErr::BadParams),
+        // DB error
+        _ => return Err(RpcErr::Internal),
+    };
+    let chain_config = match storage.get_chain_config() {
+        Ok(Some(chain_config)) => chain_config,
+        _ => return Err(RpcErr::Internal),
+    };
+    let spec_id = spec_id(&chain_config, header.timestamp);
+    Ok((header, spec_id))
+}
+
+// Runs the transaction on top of the state resulting from the given block, with the overrides applied to it
+fn simulate(
+    transaction: &GenericTransaction,
+    header: &BlockHeader,
+    storage: &Store,
+    overrides: Option<&StateOverride>,
+    spec_id: SpecId,
+) -> Result<ExecutionResult, EvmError> {
+    let mut state = evm_state_at_block(storage.clone(), header.number);
+    if let Some(overrides) = overrides {
+        apply_state_overrides(&mut state, overrides)?;
+    }
+    simulate_tx_from_generic(transaction, header, &mut state, spec_id)
+}
+
+// Transactions that can't be executed with the given gas limit (such as when it is lower than the intrinsic gas) don't succeed
+fn simulation_succeeds(
+    transaction: &GenericTransaction,
+    header: &BlockHeader,
+    storage: &Store,
+    overrides: Option<&StateOverride>,
+    spec_id: SpecId,
+) -> Result<bool, RpcErr> {
+    match simulate(transaction, header, storage, overrides, spec_id) {
+        Ok(result) => Ok(result.is_success()),
+        Err(EvmError::Transaction(_)) => Ok(false),
+        Err(_) => Err(RpcErr::Vm),
+    }
+}
+
+// Maps a reverted or halted execution to its rpc error
+pub(crate) fn failed_execution_error(result: ExecutionResult) -> RpcErr {
+    match result {
+        ExecutionResult::Revert { output, .. } => RpcErr::Revert {
+            data: format!("0x{}", hex::encode(output)),
+        },
+        ExecutionResult::Halt { reason, gas_used } => RpcErr::Halt { reason, gas_used },
+        ExecutionResult::Success { .. } => RpcErr::Internal,
+    }
+}
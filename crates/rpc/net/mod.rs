@@ -0,0 +1,24 @@
+use ethereum_rust_net::node::NetworkHandle;
+use ethereum_rust_storage::Store;
+use serde_json::Value;
+use tracing::info;
+
+use crate::utils::RpcErr;
+
+/// Returns the network id, which matches the chain id, as a decimal string
+pub fn version(storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested network id");
+    match storage.get_chain_id() {
+        Ok(Some(chain_id)) => Ok(Value::String(chain_id.to_string())),
+        // The chain id is loaded in the db from the genesis file
+        _ => Err(RpcErr::Internal),
+    }
+}
+
+pub fn listening() -> Result<Value, RpcErr> {
+    Ok(Value::Bool(true))
+}
+
+pub fn peer_count(network: NetworkHandle) -> Result<Value, RpcErr> {
+    Ok(Value::String(format!("{:#x}", network.peers().len())))
+}
@@ -1,9 +1,32 @@
-use std::{future::IntoFuture, net::SocketAddr};
+use std::{future::IntoFuture, net::SocketAddr, time::Duration};
 
-use axum::{routing::post, Json, Router};
-use engine::{ExchangeCapabilitiesRequest, NewPayloadV3Request};
+use admin::PeerRequest;
+use authentication::authenticate;
+use axum::{
+    extract::ws::WebSocketUpgrade,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use debug::{
+    raw::{self, GetRawBlockRequest, GetRawTransactionRequest},
+    trace::{
+        self as debug_trace, TraceBlockByHashRequest, TraceBlockByNumberRequest, TraceCallRequest,
+        TraceTransactionRequest,
+    },
+};
+use engine::{
+    fork_choice::{
+        self, ForkChoiceUpdatedV1Request, ForkChoiceUpdatedV2Request, ForkChoiceUpdatedV3Request,
+    },
+    payload::{
+        self, GetPayloadBodiesByHashV1Request, GetPayloadBodiesByRangeV1Request, GetPayloadRequest,
+    },
+    ExchangeCapabilitiesRequest, GetClientVersionV1Request, NewPayloadV1Request,
+    NewPayloadV2Request, NewPayloadV3Request,
+};
 use eth::{
-    account::{self, GetBalanceRequest, GetCodeRequest, GetStorageAtRequest},
+    account::{self, GetBalanceRequest, GetCodeRequest, GetProofRequest, GetStorageAtRequest},
     block::{
         self, CreateAccessListRequest, GetBlockByHashRequest, GetBlockByNumberRequest,
         GetBlockReceiptsRequest, GetBlockTransactionCountByNumberRequest,
@@ -11,29 +34,106 @@ use eth::{
         GetTransactionByHashRequest, GetTransactionReceiptRequest,
     },
     client,
+    fee_market::{self, FeeHistoryRequest},
+    filter::{self, ActiveFilters, FilterIdRequest, NewFilterRequest},
+    logs::{self, GetLogsRequest},
+    simulate::{self, SimulateRequest},
+    transaction::{self, CallRequest, EstimateGasRequest, SendRawTransactionRequest},
 };
 use serde_json::Value;
+use subscriptions::{serve_websocket, ChainEvents};
 use tokio::net::TcpListener;
-use tracing::info;
-use utils::{RpcErr, RpcErrorMetadata, RpcErrorResponse, RpcRequest, RpcSuccessResponse};
+use trace::{
+    ReplayBlockTransactionsRequest, ReplayTransactionRequest, TraceBlockRequest,
+    TraceFilterRequest, TraceTransactionRequest as ParityTraceTransactionRequest,
+};
+use tracing::{info, warn};
+use utils::{
+    RpcErr, RpcErrorMetadata, RpcErrorResponse, RpcRequest, RpcRequestId, RpcSuccessResponse,
+};
+use web3::Sha3Request;
 
 mod admin;
+mod authentication;
+mod debug;
 mod engine;
 mod eth;
+mod net;
+mod subscriptions;
+mod trace;
 mod utils;
+mod web3;
 
-use axum::extract::State;
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+};
+use ethereum_rust_core::{
+    types::{BlockHash, BlockNumber, ForkChoiceResponse},
+    Bytes,
+};
+use ethereum_rust_net::node::NetworkHandle;
 use ethereum_rust_storage::Store;
 
-pub async fn start_api(http_addr: SocketAddr, authrpc_addr: SocketAddr, storage: Store) {
+pub use authentication::decode_jwt_secret;
+pub use eth::logs::LogsLimits;
+
+#[derive(Clone)]
+pub struct RpcApiContext {
+    storage: Store,
+    jwt_secret: Bytes,
+    logs_limits: LogsLimits,
+    active_filters: ActiveFilters,
+    events: ChainEvents,
+    network: NetworkHandle,
+}
+
+/// Methods from the eth namespace that are also served by the Auth-RPC endpoint
+/// as defined in the [Engine API spec](https://github.com/ethereum/execution-apis/blob/main/src/engine/common.md#underlying-protocol)
+const AUTHRPC_ETH_METHODS: [&str; 9] = [
+    "eth_blockNumber",
+    "eth_call",
+    "eth_chainId",
+    "eth_getCode",
+    "eth_getBlockByHash",
+    "eth_getBlockByNumber",
+    "eth_getLogs",
+    "eth_sendRawTransaction",
+    "eth_syncing",
+];
+
+pub async fn start_api(
+    http_addr: SocketAddr,
+    ws_addr: SocketAddr,
+    authrpc_addr: SocketAddr,
+    storage: Store,
+    jwt_secret: Bytes,
+    logs_limits: LogsLimits,
+    filter_timeout: Duration,
+    network: NetworkHandle,
+) {
+    let service_context = RpcApiContext {
+        storage,
+        jwt_secret,
+        logs_limits,
+        active_filters: ActiveFilters::new(filter_timeout),
+        events: ChainEvents::default(),
+        network,
+    };
+
     let http_router = Router::new()
         .route("/", post(handle_http_request))
-        .with_state(storage.clone());
+        .with_state(service_context.clone());
     let http_listener = TcpListener::bind(http_addr).await.unwrap();
 
+    let ws_router = Router::new()
+        .route("/", get(handle_ws_request))
+        .with_state(service_context.clone());
+    let ws_listener = TcpListener::bind(ws_addr).await.unwrap();
+
     let authrpc_router = Router::new()
         .route("/", post(handle_authrpc_request))
-        .with_state(storage);
+        .with_state(service_context);
     let authrpc_listener = TcpListener::bind(authrpc_addr).await.unwrap();
 
     let authrpc_server = axum::serve(authrpc_listener, authrpc_router)
@@ -42,11 +142,15 @@ pub async fn start_api(http_addr: SocketAddr, authrpc_addr: SocketAddr, storage:
     let http_server = axum::serve(http_listener, http_router)
         .with_graceful_shutdown(shutdown_signal())
         .into_future();
+    let ws_server = axum::serve(ws_listener, ws_router)
+        .with_graceful_shutdown(shutdown_signal())
+        .into_future();
 
     info!("Starting HTTP server at {http_addr}");
+    info!("Starting WebSocket server at {ws_addr}");
     info!("Starting Auth-RPC server at {}", authrpc_addr);
 
-    let _ = tokio::try_join!(authrpc_server, http_server)
+    let _ = tokio::try_join!(authrpc_server, http_server, ws_server)
         .inspect_err(|e| info!("Error shutting down servers: {:?}", e));
 }
 
@@ -56,34 +160,121 @@ async fn shutdown_signal() {
         .expect("failed to install Ctrl+C handler");
 }
 
-pub async fn handle_authrpc_request(State(storage): State<Store>, body: String) -> Json<Value> {
-    let req: RpcRequest = serde_json::from_str(&body).unwrap();
-    let res = match map_requests(&req, storage.clone()) {
-        res @ Ok(_) => res,
-        _ => map_internal_requests(&req, storage),
+pub async fn handle_authrpc_request(
+    State(service_context): State<RpcApiContext>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let auth_header = headers
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok());
+    if let Err(error) = authenticate(&service_context.jwt_secret, auth_header) {
+        warn!("Rejected Auth-RPC request: {error:?}");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    http_response(handle_rpc_message(&body, |req| {
+        map_authrpc_requests(req, service_context.clone())
+    }))
+}
+
+pub async fn handle_http_request(
+    State(service_context): State<RpcApiContext>,
+    body: String,
+) -> Response {
+    http_response(handle_rpc_message(&body, |req| {
+        map_http_requests(req, service_context.clone())
+    }))
+}
+
+pub async fn handle_ws_request(
+    State(service_context): State<RpcApiContext>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(|socket| serve_websocket(socket, service_context))
+}
+
+/// Handles a JSON-RPC message holding either a single request or a batch of them
+/// Returns None if there is nothing to respond, which happens when all requests were notifications
+pub(crate) fn handle_rpc_message(
+    body: &str,
+    mut handle_request: impl FnMut(&RpcRequest) -> Result<Value, RpcErr>,
+) -> Option<Value> {
+    let message: Value = match serde_json::from_str(body) {
+        Ok(message) => message,
+        Err(_) => return Some(rpc_response(RpcRequestId::Null, Err(RpcErr::ParseError))),
     };
-    rpc_response(req.id, res)
+    match message {
+        Value::Array(requests) if !requests.is_empty() => {
+            let responses: Vec<Value> = requests
+                .into_iter()
+                .filter_map(|request| handle_single_request(request, &mut handle_request))
+                .collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        // Empty batches are invalid requests
+        request => handle_single_request(request, &mut handle_request),
+    }
 }
 
-pub async fn handle_http_request(State(storage): State<Store>, body: String) -> Json<Value> {
-    let req: RpcRequest = serde_json::from_str(&body).unwrap();
-    let res = map_requests(&req, storage);
-    rpc_response(req.id, res)
+fn handle_single_request(
+    mut request: Value,
+    handle_request: &mut impl FnMut(&RpcRequest) -> Result<Value, RpcErr>,
+) -> Option<Value> {
+    // Params given by name are not mapped to positions, so they are taken out and the request
+    // is rejected once it is known to be otherwise valid
+    let named_params = request.get("params").is_some_and(Value::is_object);
+    if named_params {
+        if let Some(request) = request.as_object_mut() {
+            request.remove("params");
+        }
+    }
+    let req = match RpcRequest::parse(request) {
+        Ok(req) => req,
+        // The id of an invalid request can't be trusted
+        Err(error) => return Some(rpc_response(RpcRequestId::Null, Err(error))),
+    };
+    let res = if named_params {
+        Err(RpcErr::NamedParamsNotSupported)
+    } else {
+        handle_request(&req)
+    };
+    req.id.map(|id| rpc_response(id, res))
 }
 
-/// Handle requests that can come from either clients or other users
-pub fn map_requests(req: &RpcRequest, storage: Store) -> Result<Value, RpcErr> {
+// Nothing but the status is sent back when there is no response
+fn http_response(response: Option<Value>) -> Response {
+    match response {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::OK.into_response(),
+    }
+}
+
+/// Handle requests from users and other clients
+pub fn map_http_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    match req.method.split('_').next() {
+        Some("eth") => map_eth_requests(req, context),
+        Some("debug") => map_debug_requests(req, context.storage),
+        Some("trace") => map_trace_requests(req, context),
+        Some("net") => map_net_requests(req, context),
+        Some("web3") => map_web3_requests(req),
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+/// Handle requests from the consensus client
+pub fn map_authrpc_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        method if method.starts_with("engine_") => map_engine_requests(req, context),
+        // Admin methods can change the node's peers, so they are only served to authenticated clients
+        method if method.starts_with("admin_") => map_admin_requests(req, context),
+        method if AUTHRPC_ETH_METHODS.contains(&method) => map_eth_requests(req, context),
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+pub fn map_eth_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    let storage = context.storage;
     match req.method.as_str() {
-        "engine_exchangeCapabilities" => {
-            let capabilities: ExchangeCapabilitiesRequest = req
-                .params
-                .as_ref()
-                .ok_or(RpcErr::BadParams)?
-                .first()
-                .ok_or(RpcErr::BadParams)
-                .and_then(|v| serde_json::from_value(v.clone()).map_err(|_| RpcErr::BadParams))?;
-            engine::exchange_capabilities(&capabilities)
-        }
         "eth_chainId" => client::chain_id(storage),
         "eth_syncing" => client::syncing(),
         "eth_getBlockByNumber" => {
@@ -106,6 +297,10 @@ pub fn map_requests(req: &RpcRequest, storage: Store) -> Result<Value, RpcErr> {
             let request = GetStorageAtRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
             account::get_storage_at(&request, storage)
         }
+        "eth_getProof" => {
+            let request = GetProofRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            account::get_proof(&request, storage)
+        }
         "eth_getBlockTransactionCountByNumber" => {
             let request = GetBlockTransactionCountByNumberRequest::parse(&req.params)
                 .ok_or(RpcErr::BadParams)?;
@@ -140,55 +335,352 @@ pub fn map_requests(req: &RpcRequest, storage: Store) -> Result<Value, RpcErr> {
             block::create_access_list(&request, storage)
         }
         "eth_blockNumber" => block::block_number(storage),
-        "engine_forkchoiceUpdatedV3" => engine::forkchoice_updated_v3(),
+        "eth_call" => {
+            let request = CallRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            transaction::call(&request, storage)
+        }
+        "eth_estimateGas" => {
+            let request = EstimateGasRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            transaction::estimate_gas(&request, storage)
+        }
+        "eth_simulateV1" => {
+            let request = SimulateRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            simulate::simulate_v1(&request, storage)
+        }
+        "eth_sendRawTransaction" => {
+            let request = SendRawTransactionRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            let hash = transaction::send_raw_transaction(&request, storage)?;
+            context
+                .events
+                .new_pending_transaction(request.transaction.compute_hash());
+            Ok(hash)
+        }
+        "eth_getLogs" => {
+            let request = GetLogsRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            logs::get_logs(&request, storage, context.logs_limits)
+        }
+        "eth_gasPrice" => fee_market::gas_price(storage),
+        "eth_maxPriorityFeePerGas" => fee_market::max_priority_fee_per_gas(storage),
+        "eth_blobBaseFee" => fee_market::blob_base_fee(storage),
+        "eth_feeHistory" => {
+            let request = FeeHistoryRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            fee_market::fee_history(&request, storage)
+        }
+        "eth_newFilter" => {
+            let request = NewFilterRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            filter::new_filter(request, storage, context.active_filters)
+        }
+        "eth_newBlockFilter" => filter::new_block_filter(storage, context.active_filters),
+        "eth_newPendingTransactionFilter" => {
+            filter::new_pending_transaction_filter(storage, context.active_filters)
+        }
+        "eth_getFilterChanges" => {
+            let request = FilterIdRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            filter::get_filter_changes(
+                &request,
+                storage,
+                context.active_filters,
+                context.logs_limits,
+            )
+        }
+        "eth_getFilterLogs" => {
+            let request = FilterIdRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            filter::get_filter_logs(
+                &request,
+                storage,
+                context.active_filters,
+                context.logs_limits,
+            )
+        }
+        "eth_uninstallFilter" => {
+            let request = FilterIdRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            filter::uninstall_filter(&request, context.active_filters)
+        }
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+pub fn map_engine_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    let storage = context.storage;
+    let events = context.events;
+    match req.method.as_str() {
+        "engine_exchangeCapabilities" => {
+            let capabilities: ExchangeCapabilitiesRequest = req
+                .params
+                .as_ref()
+                .ok_or(RpcErr::BadParams)?
+                .first()
+                .ok_or(RpcErr::BadParams)
+                .and_then(|v| serde_json::from_value(v.clone()).map_err(|_| RpcErr::BadParams))?;
+            engine::exchange_capabilities(&capabilities)
+        }
+        "engine_forkchoiceUpdatedV1" => {
+            let request =
+                ForkChoiceUpdatedV1Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            forkchoice_updated(&storage, &events, |storage| {
+                fork_choice::forkchoice_updated_v1(request, storage)
+            })
+        }
+        "engine_forkchoiceUpdatedV2" => {
+            let request =
+                ForkChoiceUpdatedV2Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            forkchoice_updated(&storage, &events, |storage| {
+                fork_choice::forkchoice_updated_v2(request, storage)
+            })
+        }
+        "engine_forkchoiceUpdatedV3" => {
+            let request =
+                ForkChoiceUpdatedV3Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            forkchoice_updated(&storage, &events, |storage| {
+                fork_choice::forkchoice_updated_v3(request, storage)
+            })
+        }
+        "engine_newPayloadV1" => {
+            let request = NewPayloadV1Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            let status = engine::new_payload_v1(request, storage)?;
+            serde_json::to_value(status).map_err(|_| RpcErr::Internal)
+        }
+        "engine_newPayloadV2" => {
+            let request = NewPayloadV2Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            let status = engine::new_payload_v2(request, storage)?;
+            serde_json::to_value(status).map_err(|_| RpcErr::Internal)
+        }
         "engine_newPayloadV3" => {
             let request = NewPayloadV3Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
-            Ok(serde_json::to_value(engine::new_payload_v3(request, storage)?).unwrap())
+            let status = engine::new_payload_v3(request, storage)?;
+            serde_json::to_value(status).map_err(|_| RpcErr::Internal)
+        }
+        "engine_getPayloadV1" => {
+            let request = GetPayloadRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            serde_json::to_value(payload::get_payload_v1(&request, storage)?)
+                .map_err(|_| RpcErr::Internal)
+        }
+        "engine_getPayloadV2" => {
+            let request = GetPayloadRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            serde_json::to_value(payload::get_payload_v2(&request, storage)?)
+                .map_err(|_| RpcErr::Internal)
+        }
+        "engine_getPayloadV3" => {
+            let request = GetPayloadRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            serde_json::to_value(payload::get_payload_v3(&request, storage)?)
+                .map_err(|_| RpcErr::Internal)
+        }
+        "engine_getPayloadBodiesByHashV1" => {
+            let request =
+                GetPayloadBodiesByHashV1Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            serde_json::to_value(payload::get_payload_bodies_by_hash_v1(&request, storage)?)
+                .map_err(|_| RpcErr::Internal)
+        }
+        "engine_getPayloadBodiesByRangeV1" => {
+            let request =
+                GetPayloadBodiesByRangeV1Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            serde_json::to_value(payload::get_payload_bodies_by_range_v1(&request, storage)?)
+                .map_err(|_| RpcErr::Internal)
+        }
+        "engine_getClientVersionV1" => {
+            let request = GetClientVersionV1Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            serde_json::to_value(engine::get_client_version_v1(&request)?)
+                .map_err(|_| RpcErr::Internal)
         }
-        "admin_nodeInfo" => admin::node_info(),
         _ => Err(RpcErr::MethodNotFound),
     }
 }
 
-/// Handle requests from other clients
-pub fn map_internal_requests(_req: &RpcRequest, _storage: Store) -> Result<Value, RpcErr> {
-    Err(RpcErr::MethodNotFound)
+/// Returns the hash of the current head of the canonical chain, used to notify head changes only once
+/// Applies a fork choice update, notifying subscribers of every block that became canonical if the head moved
+/// The head is moved before the payload attributes are validated, so subscribers are notified even if the update fails
+fn forkchoice_updated(
+    storage: &Store,
+    events: &ChainEvents,
+    update: impl FnOnce(Store) -> Result<ForkChoiceResponse, RpcErr>,
+) -> Result<Value, RpcErr> {
+    let previous_head = latest_block(storage);
+    let result = update(storage.clone());
+    if let Some((number, hash)) = latest_block(storage) {
+        let first = match previous_head {
+            Some(previous_head) if previous_head == (number, hash) => None,
+            // Blocks skipped since the previous head are notified as well if the new head descends from it
+            Some((previous_number, previous_hash))
+                if previous_number < number
+                    && block_hash(previous_number, storage) == Some(previous_hash) =>
+            {
+                Some(previous_number + 1)
+            }
+            _ => Some(number),
+        };
+        if let Some(first) = first {
+            events.new_heads(first..=number, storage);
+        }
+    }
+    serde_json::to_value(result?).map_err(|_| RpcErr::Internal)
+}
+
+// Returns the number and hash of the latest block
+fn latest_block(storage: &Store) -> Option<(BlockNumber, BlockHash)> {
+    let number = storage.get_latest_block_number().ok()??;
+    Some((number, block_hash(number, storage)?))
+}
+
+fn block_hash(number: BlockNumber, storage: &Store) -> Option<BlockHash> {
+    let header = storage.get_block_header(number).ok()??;
+    Some(header.compute_block_hash())
+}
+
+pub fn map_admin_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    let network = context.network;
+    match req.method.as_str() {
+        "admin_nodeInfo" => admin::node_info(network),
+        "admin_peers" => admin::peers(network),
+        "admin_addPeer" => {
+            let request = PeerRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            admin::add_peer(&request, network)
+        }
+        "admin_removePeer" => {
+            let request = PeerRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            admin::remove_peer(&request, network)
+        }
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+pub fn map_net_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "net_version" => net::version(context.storage),
+        "net_listening" => net::listening(),
+        "net_peerCount" => net::peer_count(context.network),
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+pub fn map_web3_requests(req: &RpcRequest) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "web3_clientVersion" => Ok(Value::String(web3::client_version())),
+        "web3_sha3" => {
+            let request = Sha3Request::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            web3::sha3(&request)
+        }
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+pub fn map_debug_requests(req: &RpcRequest, storage: Store) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "debug_traceTransaction" => {
+            let request = TraceTransactionRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            debug_trace::trace_transaction(&request, storage)
+        }
+        "debug_traceBlockByNumber" => {
+            let request = TraceBlockByNumberRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            debug_trace::trace_block_by_number(&request, storage)
+        }
+        "debug_traceBlockByHash" => {
+            let request = TraceBlockByHashRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            debug_trace::trace_block_by_hash(&request, storage)
+        }
+        "debug_traceCall" => {
+            let request = TraceCallRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            debug_trace::trace_call(&request, storage)
+        }
+        "debug_getRawHeader" => {
+            let request = GetRawBlockRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            raw::get_raw_header(&request, storage)
+        }
+        "debug_getRawBlock" => {
+            let request = GetRawBlockRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            raw::get_raw_block(&request, storage)
+        }
+        "debug_getRawReceipts" => {
+            let request = GetRawBlockRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            raw::get_raw_receipts(&request, storage)
+        }
+        "debug_getRawTransaction" => {
+            let request = GetRawTransactionRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            raw::get_raw_transaction(&request, storage)
+        }
+        _ => Err(RpcErr::MethodNotFound),
+    }
+}
+
+pub fn map_trace_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    let storage = context.storage;
+    match req.method.as_str() {
+        "trace_block" => {
+            let request = TraceBlockRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            trace::trace_block(&request, storage)
+        }
+        "trace_transaction" => {
+            let request =
+                ParityTraceTransactionRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            trace::trace_transaction(&request, storage)
+        }
+        "trace_replayTransaction" => {
+            let request = ReplayTransactionRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            trace::replay_transaction(&request, storage)
+        }
+        "trace_replayBlockTransactions" => {
+            let request =
+                ReplayBlockTransactionsRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            trace::replay_block_transactions(&request, storage)
+        }
+        "trace_filter" => {
+            let request = TraceFilterRequest::parse(&req.params).ok_or(RpcErr::BadParams)?;
+            trace::trace_filter(&request, storage, context.logs_limits)
+        }
+        _ => Err(RpcErr::MethodNotFound),
+    }
 }
 
-fn rpc_response<E>(id: i32, res: Result<Value, E>) -> Json<Value>
+fn rpc_response<E>(id: RpcRequestId, res: Result<Value, E>) -> Value
 where
     E: Into<RpcErrorMetadata>,
 {
     match res {
-        Ok(result) => Json(
-            serde_json::to_value(RpcSuccessResponse {
-                id,
-                jsonrpc: "2.0".to_string(),
-                result,
-            })
-            .unwrap(),
-        ),
-        Err(error) => Json(
-            serde_json::to_value(RpcErrorResponse {
-                id,
-                jsonrpc: "2.0".to_string(),
-                error: error.into(),
-            })
-            .unwrap(),
-        ),
+        Ok(result) => serde_json::to_value(RpcSuccessResponse {
+            id,
+            jsonrpc: "2.0".to_string(),
+            result,
+        })
+        .unwrap(),
+        Err(error) => serde_json::to_value(RpcErrorResponse {
+            id,
+            jsonrpc: "2.0".to_string(),
+            error: error.into(),
+        })
+        .unwrap(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use ethereum_rust_core::{
-        types::{code_hash, AccountInfo, BlockHeader},
-        Address, Bytes, U256,
+        rlp::{decode::RLPDecode, encode::RLPEncode},
+        types::{
+            calculate_base_fee_per_gas, code_hash, AccountInfo, Block, BlockBody, BlockHeader,
+            ChainConfig, ExecutionPayloadV3, Genesis, Log, Receipt, Transaction, TxType,
+            Withdrawal,
+        },
+        Address, Bytes, H256, U256,
     };
-    use ethereum_rust_storage::EngineType;
-    use std::str::FromStr;
+    use ethereum_rust_storage::{AccountUpdate, EngineType};
+    use serde_json::json;
+    use std::{collections::HashMap, str::FromStr};
 
     use super::*;
+    use crate::subscriptions::ChainEvent;
+
+    fn test_context(storage: Store) -> RpcApiContext {
+        RpcApiContext {
+            storage,
+            jwt_secret: Bytes::new(),
+            logs_limits: LogsLimits::default(),
+            active_filters: ActiveFilters::default(),
+            events: ChainEvents::default(),
+            network: NetworkHandle::new(
+                "127.0.0.1:30303".parse().unwrap(),
+                "127.0.0.1:30303".parse().unwrap(),
+            ),
+        }
+    }
 
     // Maps string rpc response to RpcSuccessResponse as serde Value
     // This is used to avoid failures due to field order and allow easier string comparisons for responses
@@ -196,6 +688,103 @@ mod tests {
         serde_json::to_value(serde_json::from_str::<RpcSuccessResponse>(str).unwrap()).unwrap()
     }
 
+    #[test]
+    fn json_rpc_envelope() {
+        let handle = |body: &str| {
+            handle_rpc_message(body, |req| match req.method.as_str() {
+                "eth_syncing" => client::syncing(),
+                _ => Err(RpcErr::MethodNotFound),
+            })
+        };
+        // String, null and numeric ids are echoed back
+        let response = handle(r#"{"jsonrpc":"2.0","id":"abc","method":"eth_syncing"}"#).unwrap();
+        assert_eq!(response, json!({"jsonrpc":"2.0","id":"abc","result":false}));
+        let response = handle(r#"{"jsonrpc":"2.0","id":null,"method":"eth_syncing"}"#).unwrap();
+        assert_eq!(response["id"], Value::Null);
+        // Notifications are not responded to
+        assert!(handle(r#"{"jsonrpc":"2.0","method":"eth_syncing","params":[]}"#).is_none());
+        assert!(handle(r#"[{"jsonrpc":"2.0","method":"eth_syncing"}]"#).is_none());
+        // Batches are responded to in order, skipping notifications
+        let response = handle(
+            r#"[{"jsonrpc":"2.0","id":1,"method":"eth_syncing"},{"jsonrpc":"2.0","method":"eth_syncing"},{"jsonrpc":"2.0","id":2,"method":"eth_foo"},1]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            json!([
+                {"jsonrpc":"2.0","id":1,"result":false},
+                {"jsonrpc":"2.0","id":2,"error":{"code":-32601,"message":"Method not found"}},
+                {"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"Invalid request"}}
+            ])
+        );
+        // Malformed messages are rejected
+        let response = handle(r#"{"jsonrpc":"2.0","id":1,"method""#).unwrap();
+        assert_eq!(response["error"]["code"], -32700);
+        for body in [
+            "[]",
+            r#"{"jsonrpc":"1.0","id":1,"method":"eth_syncing"}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"eth_syncing","params":1}"#,
+        ] {
+            assert_eq!(handle(body).unwrap()["error"]["code"], -32600);
+        }
+        // Params given by name are rejected
+        let response = handle(
+            r#"{"jsonrpc":"2.0","id":1,"method":"eth_getLogs","params":{"fromBlock":"0x1"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            response,
+            json!({"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"named params not supported"}})
+        );
+    }
+
+    #[test]
+    fn named_params_multi_param_method() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_block_header(0, BlockHeader::default())
+            .expect("Failed to write to test DB");
+        storage
+            .update_latest_block_number(0)
+            .expect("Failed to write to test DB");
+        let address = Address::repeat_byte(0xaa);
+        storage
+            .add_account_info(
+                address,
+                AccountInfo {
+                    balance: U256::from(10),
+                    ..Default::default()
+                },
+            )
+            .expect("Failed to write to test DB");
+        let handle = |body: &str| {
+            handle_rpc_message(body, |req| {
+                map_http_requests(req, test_context(storage.clone()))
+            })
+            .unwrap()
+        };
+        // Positional params are mapped in order
+        let response = handle(&format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_getBalance","params":["{address:#x}","latest"]}}"#
+        ));
+        assert_eq!(response["result"], json!("0xa"));
+        // The same params given by name are rejected instead of being passed as the first param
+        let response = handle(&format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"eth_getBalance","params":{{"address":"{address:#x}","block":"latest"}}}}"#
+        ));
+        assert_eq!(
+            response,
+            json!({"jsonrpc":"2.0","id":2,"error":{"code":-32602,"message":"named params not supported"}})
+        );
+        // Notifications with params given by name are still not responded to
+        assert!(handle_rpc_message(
+            r#"{"jsonrpc":"2.0","method":"eth_getBalance","params":{}}"#,
+            |_| unreachable!()
+        )
+        .is_none());
+    }
+
     #[test]
     fn create_access_list_simple_transfer() {
         // Create Request
@@ -210,6 +799,13 @@ mod tests {
         storage
             .add_block_header(0, BlockHeader::default())
             .expect("Failed to write to test DB");
+        storage
+            .set_chain_config(&ChainConfig {
+                shanghai_time: Some(0),
+                cancun_time: Some(0),
+                ..Default::default()
+            })
+            .expect("Failed to write to test DB");
         let address = Address::from_str("0c2c51a0990aee1d73c1228de158688341557508").unwrap();
         let account_info = AccountInfo {
             balance: U256::from_str_radix("c097ce7bc90715b34b9f1000000000", 16).unwrap(),
@@ -219,12 +815,567 @@ mod tests {
             .add_account_info(address, account_info)
             .expect("Failed to write to test DB");
         // Process request
-        let result = map_requests(&request, storage);
-        let response = rpc_response(request.id, result);
+        let result = map_http_requests(&request, test_context(storage.clone()));
+        let response = rpc_response(request.id.clone().unwrap(), result);
         let expected_response = to_rpc_response_success_value(
             r#"{"jsonrpc":"2.0","id":1,"result":{"accessList":[],"gasUsed":"0x5208"}}"#,
         );
         assert_eq!(response.to_string(), expected_response.to_string());
+        // Unknown blocks are rejected
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_createAccessList","params":[{"from":"0x0c2c51a0990aee1d73c1228de158688341557508","nonce":"0x0","to":"0x0100000000000000000000000000000000000000","value":"0xa"},"0x01"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        assert!(matches!(
+            map_http_requests(&request, test_context(storage)),
+            Err(RpcErr::BadParams)
+        ));
+    }
+
+    #[test]
+    fn forkchoice_updated_v3_moves_head() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let genesis = BlockHeader::default();
+        let genesis_hash = genesis.compute_block_hash();
+        let child = BlockHeader {
+            number: 1,
+            parent_hash: genesis_hash,
+            ..Default::default()
+        };
+        let child_hash = child.compute_block_hash();
+        for header in [genesis, child] {
+            storage
+                .add_block_number(header.compute_block_hash(), header.number)
+                .expect("Failed to write to test DB");
+            storage
+                .add_block_header(header.number, header)
+                .expect("Failed to write to test DB");
+        }
+        // Unknown head block
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{"headBlockHash":"0x0000000000000000000000000000000000000000000000000000000000000001","safeBlockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","finalizedBlockHash":"0x0000000000000000000000000000000000000000000000000000000000000000"},null]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["payloadStatus"]["status"], "SYNCING");
+        // Known head block with genesis as finalized
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{{"headBlockHash":"{child_hash:#x}","safeBlockHash":"{child_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["payloadStatus"]["status"], "VALID");
+        assert_eq!(
+            result["payloadStatus"]["latestValidHash"],
+            format!("{child_hash:#x}")
+        );
+        assert_eq!(result["payloadId"], Value::Null);
+        assert_eq!(storage.get_latest_block_number().unwrap(), Some(1));
+        assert_eq!(storage.get_safe_block_number().unwrap(), Some(1));
+        assert_eq!(storage.get_finalized_block_number().unwrap(), Some(0));
+        // Finalized block ahead of the safe block
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{{"headBlockHash":"{child_hash:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{child_hash:#x}"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage)),
+            Err(RpcErr::InvalidForkChoiceState)
+        ));
+    }
+
+    #[test]
+    fn forkchoice_updated_notifies_head_changes_once() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let genesis = BlockHeader::default();
+        let genesis_hash = genesis.compute_block_hash();
+        let child = BlockHeader {
+            number: 1,
+            parent_hash: genesis_hash,
+            ..Default::default()
+        };
+        let child_hash = child.compute_block_hash();
+        for header in [genesis, child] {
+            storage
+                .add_block_number(header.compute_block_hash(), header.number)
+                .expect("Failed to write to test DB");
+            storage
+                .add_block_header(header.number, header)
+                .expect("Failed to write to test DB");
+        }
+        storage
+            .update_latest_block_number(0)
+            .expect("Failed to write to test DB");
+        let context = test_context(storage);
+        let mut events = context.events.subscribe();
+        // Repeated updates to the same head and updates to the current head are not notified
+        for head in [child_hash, child_hash, genesis_hash, genesis_hash] {
+            let body = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{{"headBlockHash":"{head:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}}]}}"#
+            );
+            let request: RpcRequest = serde_json::from_str(&body).unwrap();
+            let result = map_authrpc_requests(&request, context.clone()).expect("Request failed");
+            assert_eq!(result["payloadStatus"]["status"], "VALID");
+        }
+        let mut notified_heads = Vec::new();
+        while let Ok(ChainEvent::NewHead(header)) = events.try_recv() {
+            notified_heads.push(header.compute_block_hash());
+        }
+        assert_eq!(notified_heads, vec![child_hash, genesis_hash]);
+    }
+
+    #[test]
+    fn forkchoice_updated_notifies_skipped_heads() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .set_chain_config(&Default::default())
+            .expect("Failed to write to test DB");
+        let mut hashes = Vec::new();
+        let mut parent_hash = H256::zero();
+        for number in 0..4 {
+            let header = BlockHeader {
+                number,
+                parent_hash,
+                ..Default::default()
+            };
+            parent_hash = header.compute_block_hash();
+            hashes.push(parent_hash);
+            storage
+                .add_block_number(parent_hash, number)
+                .expect("Failed to write to test DB");
+            storage
+                .add_block_header(number, header)
+                .expect("Failed to write to test DB");
+        }
+        storage
+            .update_latest_block_number(0)
+            .expect("Failed to write to test DB");
+        let context = test_context(storage);
+        let mut events = context.events.subscribe();
+        // The head moves from genesis to the last block even though the payload attributes are invalid
+        let (genesis_hash, head_hash) = (hashes[0], hashes[3]);
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV1","params":[{{"headBlockHash":"{head_hash:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}},{{"timestamp":"0x0","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0x8943545177806ed17b9f23f0a21ee5948ecaa776"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, context.clone()),
+            Err(RpcErr::InvalidPayloadAttributes)
+        ));
+        assert_eq!(context.storage.get_latest_block_number().unwrap(), Some(3));
+        // Every block between the previous head and the new one is notified in order
+        let mut notified_heads = Vec::new();
+        while let Ok(ChainEvent::NewHead(header)) = events.try_recv() {
+            notified_heads.push(header.compute_block_hash());
+        }
+        assert_eq!(notified_heads, hashes[1..]);
+    }
+
+    #[test]
+    fn build_payload_on_top_of_genesis() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let genesis_hash = genesis.get_block().header.compute_block_hash();
+        let timestamp = genesis.timestamp + 12;
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // Start building a payload
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{{"headBlockHash":"{genesis_hash:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}},{{"timestamp":"{timestamp:#x}","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0x8943545177806ed17b9f23f0a21ee5948ecaa776","withdrawals":[{{"index":"0x0","validatorIndex":"0x1","address":"0x8943545177806ed17b9f23f0a21ee5948ecaa776","amount":"0x64"}}],"parentBeaconBlockRoot":"0x0000000000000000000000000000000000000000000000000000000000000000"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["payloadStatus"]["status"], "VALID");
+        let payload_id = result["payloadId"].as_str().unwrap().to_string();
+        assert_eq!(payload_id.len(), 18);
+        // Retrieve the built payload
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadV3","params":["{payload_id}"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        let payload = &result["executionPayload"];
+        assert_eq!(payload["blockNumber"], "0x1");
+        assert_eq!(payload["parentHash"], format!("{genesis_hash:#x}"));
+        assert_eq!(result["blockValue"], "0x0");
+        // Payloads whose header doesn't match the execution results are rejected
+        let parent_beacon_block_root = H256::zero();
+        let valid_block = serde_json::from_value::<ExecutionPayloadV3>(payload.clone())
+            .unwrap()
+            .into_block(parent_beacon_block_root)
+            .unwrap();
+        let mut wrong_state_root = valid_block.clone();
+        wrong_state_root.header.state_root = H256::repeat_byte(1);
+        let invalid_hash = wrong_state_root.header.compute_block_hash();
+        let mut wrong_gas_used = valid_block.clone();
+        wrong_gas_used.header.gas_used = 1;
+        for (block, error) in [
+            (wrong_state_root, "State root mismatch"),
+            (wrong_gas_used, "Gas used mismatch"),
+        ] {
+            let invalid_payload =
+                serde_json::to_value(ExecutionPayloadV3::from_block(block)).unwrap();
+            let body = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"engine_newPayloadV3","params":[{invalid_payload},[],"{parent_beacon_block_root:#x}"]}}"#
+            );
+            let request: RpcRequest = serde_json::from_str(&body).unwrap();
+            let result = map_authrpc_requests(&request, test_context(storage.clone()))
+                .expect("Request failed");
+            assert_eq!(result["status"], "INVALID");
+            assert_eq!(result["latestValidHash"], format!("{genesis_hash:#x}"));
+            assert!(result["validationError"]
+                .as_str()
+                .unwrap()
+                .starts_with(error));
+        }
+        // Descendants of a rejected payload are rejected without being executed
+        let mut invalid_child = valid_block;
+        invalid_child.header.parent_hash = invalid_hash;
+        invalid_child.header.number += 1;
+        invalid_child.header.timestamp += 12;
+        let invalid_child =
+            serde_json::to_value(ExecutionPayloadV3::from_block(invalid_child)).unwrap();
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_newPayloadV3","params":[{invalid_child},[],"{parent_beacon_block_root:#x}"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["status"], "INVALID");
+        assert_eq!(result["latestValidHash"], format!("{genesis_hash:#x}"));
+        // Rejected blocks can't become the head of the chain
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{{"headBlockHash":"{invalid_hash:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["payloadStatus"]["status"], "INVALID");
+        assert_eq!(
+            result["payloadStatus"]["latestValidHash"],
+            format!("{genesis_hash:#x}")
+        );
+        assert_eq!(result["payloadId"], Value::Null);
+        // The built payload is accepted by newPayload
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_newPayloadV3","params":[{payload},[],"0x0000000000000000000000000000000000000000000000000000000000000000"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["status"], "VALID");
+        // Unknown payload id
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadV3","params":["0x0000000000000001"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage)),
+            Err(RpcErr::UnknownPayload)
+        ));
+    }
+
+    #[test]
+    fn import_sibling_payloads() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let genesis_hash = genesis.get_block().header.compute_block_hash();
+        let timestamp = genesis.timestamp + 12;
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        let send = |body: String| {
+            let request: RpcRequest = serde_json::from_str(&body).unwrap();
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed")
+        };
+        // Build two children of the genesis block that withdraw different amounts to the same address
+        let recipient = Address::repeat_byte(0xaa);
+        let siblings: Vec<Value> = [100_u64, 200]
+            .into_iter()
+            .map(|amount| {
+                let result = send(format!(
+                    r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{{"headBlockHash":"{genesis_hash:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}},{{"timestamp":"{timestamp:#x}","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0x8943545177806ed17b9f23f0a21ee5948ecaa776","withdrawals":[{{"index":"0x0","validatorIndex":"0x1","address":"{recipient:#x}","amount":"{amount:#x}"}}],"parentBeaconBlockRoot":"0x0000000000000000000000000000000000000000000000000000000000000000"}}]}}"#
+                ));
+                let payload_id = result["payloadId"].as_str().unwrap().to_string();
+                let result = send(format!(
+                    r#"{{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadV3","params":["{payload_id}"]}}"#
+                ));
+                result["executionPayload"].clone()
+            })
+            .collect();
+        // Each sibling is executed on top of the genesis state, regardless of the one imported before it
+        for (payload, amount) in siblings
+            .iter()
+            .chain(siblings.iter())
+            .zip([100_u64, 200, 100, 200])
+        {
+            let result = send(format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"engine_newPayloadV3","params":[{payload},[],"0x0000000000000000000000000000000000000000000000000000000000000000"]}}"#
+            ));
+            assert_eq!(result["status"], "VALID");
+            let balance = storage
+                .get_account_info(recipient)
+                .unwrap()
+                .map(|info| info.balance);
+            assert_eq!(balance, Some(U256::from(amount) * U256::exp10(9)));
+        }
+    }
+
+    #[test]
+    fn build_shanghai_payload_on_top_of_genesis() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let mut genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        // Chain that hasn't upgraded to Cancun yet
+        genesis.config.cancun_time = None;
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let genesis_hash = genesis.get_block().header.compute_block_hash();
+        let timestamp = genesis.timestamp + 12;
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        let fork_choice_state = format!(
+            r#"{{"headBlockHash":"{genesis_hash:#x}","safeBlockHash":"{genesis_hash:#x}","finalizedBlockHash":"{genesis_hash:#x}"}}"#
+        );
+        // Cancun payload attributes are not supported
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV3","params":[{fork_choice_state},{{"timestamp":"{timestamp:#x}","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0x8943545177806ed17b9f23f0a21ee5948ecaa776","withdrawals":[],"parentBeaconBlockRoot":"0x0000000000000000000000000000000000000000000000000000000000000000"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage.clone())),
+            Err(RpcErr::UnsuportedFork)
+        ));
+        // Shanghai payload attributes must contain withdrawals
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV2","params":[{fork_choice_state},{{"timestamp":"{timestamp:#x}","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0x8943545177806ed17b9f23f0a21ee5948ecaa776"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage.clone())),
+            Err(RpcErr::BadParams)
+        ));
+        // Start building a payload
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_forkchoiceUpdatedV2","params":[{fork_choice_state},{{"timestamp":"{timestamp:#x}","prevRandao":"0x0000000000000000000000000000000000000000000000000000000000000000","suggestedFeeRecipient":"0x8943545177806ed17b9f23f0a21ee5948ecaa776","withdrawals":[]}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result["payloadStatus"]["status"], "VALID");
+        let payload_id = result["payloadId"].as_str().unwrap().to_string();
+        // The payload can't be retrieved as a Cancun payload
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadV3","params":["{payload_id}"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage.clone())),
+            Err(RpcErr::UnsuportedFork)
+        ));
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadV2","params":["{payload_id}"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        let payload = &result["executionPayload"];
+        assert_eq!(payload["withdrawals"], serde_json::json!([]));
+        assert_eq!(payload["blobGasUsed"], Value::Null);
+        // The built payload is accepted by newPayloadV2 but not by newPayloadV1
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_newPayloadV1","params":[{payload}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage.clone())),
+            Err(RpcErr::UnsuportedFork)
+        ));
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_newPayloadV2","params":[{payload}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result = map_authrpc_requests(&request, test_context(storage)).expect("Request failed");
+        assert_eq!(result["status"], "VALID");
+    }
+
+    #[test]
+    fn get_payload_bodies() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let encoded_tx = "0xf86d80843baa0c4082f618946177843db3138ae69679a54b95cf345ed759450d870aa87bee538000808360306ba0151ccc02146b9b11adf516e6787b59acae3e76544fdcd75e77e67c6b598ce65da064c5dd5aae2fbb535830ebbdad0234975cd7ece3562013b63ea18cc0df6c97d4";
+        let tx = Transaction::decode_canonical(&hex::decode(&encoded_tx[2..]).unwrap()).unwrap();
+        let bodies = [
+            BlockBody {
+                transactions: vec![],
+                ommers: vec![],
+                withdrawals: None,
+            },
+            BlockBody {
+                transactions: vec![tx],
+                ommers: vec![],
+                withdrawals: Some(vec![Withdrawal {
+                    index: 0,
+                    validator_index: 1,
+                    address: Address::zero(),
+                    amount: 100,
+                }]),
+            },
+        ];
+        let mut hashes = Vec::new();
+        for (number, body) in bodies.into_iter().enumerate() {
+            let header = BlockHeader {
+                number: number as u64,
+                ..Default::default()
+            };
+            hashes.push(header.compute_block_hash());
+            storage
+                .add_block_number(header.compute_block_hash(), header.number)
+                .expect("Failed to write to test DB");
+            storage
+                .add_block(Block { header, body })
+                .expect("Failed to write to test DB");
+        }
+        storage
+            .update_latest_block_number(1)
+            .expect("Failed to write to test DB");
+        // By hash, with an unknown block
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadBodiesByHashV1","params":[["{:#x}","0x0000000000000000000000000000000000000000000000000000000000000001","{:#x}"]]}}"#,
+            hashes[1], hashes[0]
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result[0]["transactions"], serde_json::json!([encoded_tx]));
+        assert_eq!(result[0]["withdrawals"][0]["amount"], "0x64");
+        assert_eq!(result[1], Value::Null);
+        assert_eq!(result[2]["transactions"], serde_json::json!([]));
+        assert_eq!(result[2]["withdrawals"], Value::Null);
+        // By range, past the latest block
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadBodiesByRangeV1","params":["0x1","0x10"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result.as_array().unwrap().len(), 1);
+        assert_eq!(result[0]["transactions"], serde_json::json!([encoded_tx]));
+        // Invalid ranges
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadBodiesByRangeV1","params":["0x0","0x1"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage.clone())),
+            Err(RpcErr::BadParams)
+        ));
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_getPayloadBodiesByRangeV1","params":["0x1","0x401"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        assert!(matches!(
+            map_authrpc_requests(&request, test_context(storage)),
+            Err(RpcErr::TooLargeRequest)
+        ));
+    }
+
+    #[test]
+    fn engine_capabilities() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        // Every reported capability is handled
+        for method in engine::ENGINE_CAPABILITIES {
+            let request = RpcRequest {
+                id: Some(RpcRequestId::Number(1.into())),
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params: None,
+            };
+            assert!(!matches!(
+                map_authrpc_requests(&request, test_context(storage.clone())),
+                Err(RpcErr::MethodNotFound)
+            ));
+        }
+        // Only our capabilities are reported back
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_exchangeCapabilities","params":[["engine_newPayloadV1","engine_newPayloadV4"]]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let result =
+            map_authrpc_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, serde_json::json!(engine::ENGINE_CAPABILITIES));
+        // Client version
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"engine_getClientVersionV1","params":[{"code":"LH","name":"Lighthouse","version":"v5.2.1","commit":"0x9e12c21f"}]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let result = map_authrpc_requests(&request, test_context(storage)).expect("Request failed");
+        assert_eq!(result[0]["code"], "ER");
+        assert_eq!(result[0]["name"], "ethereum_rust");
+        assert_eq!(result[0]["commit"].as_str().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn node_and_client_info() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        let context = test_context(storage);
+        let request = |body: &str| {
+            let request: RpcRequest = serde_json::from_str(body).unwrap();
+            map_http_requests(&request, context.clone()).expect("Request failed")
+        };
+        let admin_request = |body: &str| {
+            let request: RpcRequest = serde_json::from_str(body).unwrap();
+            map_authrpc_requests(&request, context.clone()).expect("Request failed")
+        };
+        // The admin namespace is not served on the public endpoint
+        let public_admin_request: RpcRequest =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","id":1,"method":"admin_nodeInfo"}"#).unwrap();
+        assert!(matches!(
+            map_http_requests(&public_admin_request, context.clone()),
+            Err(RpcErr::MethodNotFound)
+        ));
+        let node_info = admin_request(r#"{"jsonrpc":"2.0","id":1,"method":"admin_nodeInfo"}"#);
+        let local_node = context.network.local_node();
+        assert_eq!(node_info["enode"], local_node.enode_url());
+        assert_eq!(node_info["listenAddr"], "127.0.0.1:30303");
+        assert_eq!(node_info["ports"]["listener"], 30303);
+        assert_eq!(node_info["protocols"]["p2p"]["version"], 5);
+        assert_eq!(
+            admin_request(r#"{"jsonrpc":"2.0","id":1,"method":"admin_peers"}"#),
+            json!([])
+        );
+        assert_eq!(
+            request(r#"{"jsonrpc":"2.0","id":1,"method":"net_version"}"#),
+            "3151908"
+        );
+        assert_eq!(
+            request(r#"{"jsonrpc":"2.0","id":1,"method":"net_peerCount"}"#),
+            "0x0"
+        );
+        assert_eq!(
+            node_info["name"],
+            request(r#"{"jsonrpc":"2.0","id":1,"method":"web3_clientVersion"}"#)
+        );
+        assert!(node_info["name"]
+            .as_str()
+            .unwrap()
+            .starts_with("ethereum_rust/v"));
+        // Keccak of the empty string
+        assert_eq!(
+            request(r#"{"jsonrpc":"2.0","id":1,"method":"web3_sha3","params":["0x"]}"#),
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
     }
 
     #[test]
@@ -241,6 +1392,13 @@ mod tests {
         storage
             .add_block_header(0, BlockHeader::default())
             .expect("Failed to write to test DB");
+        storage
+            .set_chain_config(&ChainConfig {
+                shanghai_time: Some(0),
+                cancun_time: Some(0),
+                ..Default::default()
+            })
+            .expect("Failed to write to test DB");
         let address = Address::from_str("0c2c51a0990aee1d73c1228de158688341557508").unwrap();
         let account_info = AccountInfo {
             balance: U256::from_str_radix("c097ce7bc90715b34b9f1000000000", 16).unwrap(),
@@ -266,10 +1424,12 @@ mod tests {
             .add_account_code(code_hash, code)
             .expect("Failed to write to test DB");
         // Process request
-        let result = map_requests(&request, storage);
-        let response =
-            serde_json::from_value::<RpcSuccessResponse>(rpc_response(request.id, result).0)
-                .expect("Request failed");
+        let result = map_http_requests(&request, test_context(storage));
+        let response = serde_json::from_value::<RpcSuccessResponse>(rpc_response(
+            request.id.clone().unwrap(),
+            result,
+        ))
+        .expect("Request failed");
         let expected_response_string = r#"{"jsonrpc":"2.0","id":1,"result":{"accessList":[{"address":"0x7dcd17433742f4c0ca53122ab541d0ba67fc27df","storageKeys":["0x0000000000000000000000000000000000000000000000000000000000000000","0x13a08e3cd39a1bc7bf9103f63f83273cced2beada9f723945176d6b983c65bd2"]}],"gasUsed":"0xca3c"}}"#;
         let expected_response =
             serde_json::from_str::<RpcSuccessResponse>(expected_response_string).unwrap();
@@ -281,4 +1441,472 @@ mod tests {
             expected_response.result["accessList"]
         )
     }
+
+    #[test]
+    fn call_and_estimate_gas() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // Contracts that store 42 in memory and then return or revert with it
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN/REVERT
+        let returning = Address::repeat_byte(0xaa);
+        let reverting = Address::repeat_byte(0xbb);
+        for (address, code) in [
+            (returning, "602a60005260206000f3"),
+            (reverting, "602a60005260206000fd"),
+        ] {
+            let code = Bytes::from(hex::decode(code).unwrap());
+            let code_hash = code_hash(&code);
+            storage
+                .add_account_info(
+                    address,
+                    AccountInfo {
+                        code_hash,
+                        ..Default::default()
+                    },
+                )
+                .expect("Failed to write to test DB");
+            storage
+                .add_account_code(code_hash, code)
+                .expect("Failed to write to test DB");
+        }
+        let output = format!("0x{:064x}", 42);
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_call","params":[{{"to":"{returning:#x}"}},"latest"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, Value::String(output.clone()));
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_call","params":[{{"to":"{reverting:#x}"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let response = rpc_response(
+            request.id.clone().unwrap(),
+            map_http_requests(&request, test_context(storage.clone())),
+        );
+        assert_eq!(response["error"]["code"], 3);
+        assert_eq!(response["error"]["data"], output);
+        // Plain transfers need the intrinsic gas
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_estimateGas","params":[{"to":"0x000000000000000000000000000000000000dead","value":"0x0"}]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, "0x5208");
+        // Intrinsic gas + 4 pushes + MSTORE + memory expansion
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_estimateGas","params":[{{"to":"{returning:#x}"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, format!("{:#x}", 21_000 + 4 * 3 + 3 + 3));
+        // Reverting calls fail with any gas limit
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_estimateGas","params":[{{"to":"{reverting:#x}"}}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let response = rpc_response(
+            request.id.clone().unwrap(),
+            map_http_requests(&request, test_context(storage)),
+        );
+        assert_eq!(response["error"]["code"], 3);
+        assert_eq!(response["error"]["data"], output);
+    }
+
+    #[test]
+    fn call_and_estimate_gas_on_past_block() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // Contract that returns the value of its first storage slot
+        // PUSH1 0x00 SLOAD PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let contract = Address::repeat_byte(0xaa);
+        let code = Bytes::from(hex::decode("60005460005260206000f3").unwrap());
+        let sender = Address::repeat_byte(0xbb);
+        // The slot and the sender's balance change on each block
+        let mut header = storage.get_block_header(0).unwrap().unwrap();
+        for (number, value, balance) in [
+            (1, 1, U256::from(20_000) * U256::exp10(9)),
+            (2, 2, U256::exp10(18)),
+        ] {
+            header.parent_hash = header.compute_block_hash();
+            header.number = number;
+            header.timestamp += 12;
+            storage
+                .add_block_header(number, header.clone())
+                .expect("Failed to write to test DB");
+            let updates = [
+                AccountUpdate {
+                    address: contract,
+                    info: Some(AccountInfo {
+                        code_hash: code_hash(&code),
+                        ..Default::default()
+                    }),
+                    code: Some(code.clone()),
+                    added_storage: HashMap::from([(H256::zero(), U256::from(value))]),
+                    ..Default::default()
+                },
+                AccountUpdate {
+                    address: sender,
+                    info: Some(AccountInfo {
+                        balance,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ];
+            storage
+                .apply_account_updates(number, &updates)
+                .expect("Failed to write to test DB");
+        }
+        storage.update_latest_block_number(2).unwrap();
+
+        let request = |method: &str, transaction: Value, block: &str| {
+            let body =
+                json!({"jsonrpc":"2.0","id":1,"method":method,"params":[transaction, block]});
+            let request: RpcRequest = serde_json::from_value(body).unwrap();
+            map_http_requests(&request, test_context(storage.clone()))
+        };
+        // Calls see the storage as of the requested block
+        let call = json!({"to": contract});
+        assert_eq!(
+            request("eth_call", call.clone(), "0x1").unwrap(),
+            format!("0x{:064x}", 1)
+        );
+        assert_eq!(
+            request("eth_call", call, "latest").unwrap(),
+            format!("0x{:064x}", 2)
+        );
+        // The sender couldn't pay for a transfer until the latest block
+        let transfer = json!({"from": sender, "to": "0x000000000000000000000000000000000000dead", "gasPrice": "0x3b9aca00"});
+        assert!(request("eth_estimateGas", transfer.clone(), "0x1").is_err());
+        assert_eq!(
+            request("eth_estimateGas", transfer, "latest").unwrap(),
+            "0x5208"
+        );
+        // Overrides are applied on top of the state of the requested block, both for calls and simulations
+        let slot = format!("{:#x}", H256::from_low_u64_be(1));
+        let value = format!("{:#x}", H256::from_low_u64_be(5));
+        let overrides = json!({format!("{contract:#x}"): {"stateDiff": {slot: value}}});
+        let body = json!({"jsonrpc":"2.0","id":1,"method":"eth_call","params":[{"to": contract}, "0x1", overrides]});
+        let request: RpcRequest = serde_json::from_value(body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, format!("0x{:064x}", 1));
+        let body = json!({"jsonrpc":"2.0","id":1,"method":"eth_simulateV1","params":[{"blockStateCalls":[{"stateOverrides": overrides, "calls":[{"to": contract}]}]}, "0x1"]});
+        let request: RpcRequest = serde_json::from_value(body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result[0]["calls"][0]["returnData"], format!("0x{:064x}", 1));
+    }
+
+    #[test]
+    fn call_and_simulate_with_overrides() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // The contract only exists in the overrides
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let contract = Address::repeat_byte(0xaa);
+        let overrides = format!(r#"{{"{contract:#x}":{{"code":"0x602a60005260206000f3"}}}}"#);
+        let output = format!("0x{:064x}", 42);
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_call","params":[{{"to":"{contract:#x}"}},"latest",{overrides}]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, Value::String(output.clone()));
+        // Without overrides the call doesn't return anything
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_call","params":[{{"to":"{contract:#x}"}},"latest"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        assert_eq!(result, "0x");
+        // Overrides given in a simulated block persist in the following ones
+        let body = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"eth_simulateV1","params":[{{"blockStateCalls":[{{"stateOverrides":{overrides},"calls":[{{"to":"{contract:#x}"}}]}},{{"blockOverrides":{{"number":"0x5"}},"calls":[{{"to":"{contract:#x}"}}]}}]}},"latest"]}}"#
+        );
+        let request: RpcRequest = serde_json::from_str(&body).unwrap();
+        let result =
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed");
+        let blocks = result.as_array().unwrap();
+        // The numbers skipped by the second block are filled with empty blocks
+        assert_eq!(blocks.len(), 5);
+        for (number, block) in blocks.iter().enumerate() {
+            assert_eq!(block["number"], format!("{:#x}", number + 1));
+        }
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1]["parentHash"], pair[0]["hash"]);
+        }
+        for block in &blocks[1..4] {
+            assert_eq!(block["calls"], json!([]));
+        }
+        for block in [&blocks[0], &blocks[4]] {
+            assert_eq!(block["calls"][0]["status"], "0x1");
+            assert_eq!(block["calls"][0]["returnData"], output);
+        }
+        // The base fee follows from the parent block
+        let genesis = storage.get_block_header(0).unwrap().unwrap();
+        let base_fee = calculate_base_fee_per_gas(
+            genesis.gas_limit,
+            genesis.gas_limit,
+            genesis.gas_used,
+            genesis.base_fee_per_gas,
+        )
+        .unwrap();
+        assert_eq!(blocks[0]["baseFeePerGas"], format!("{base_fee:#x}"));
+        // Blocks can't go back in number, and invalid overrides are rejected
+        for block_state_calls in [
+            json!([{"blockOverrides": {"number": "0x5"}}, {"blockOverrides": {"number": "0x5"}}]),
+            json!([{"stateOverrides": {format!("{contract:#x}"): {"code": "0x00", "state": {}, "stateDiff": {}}}}]),
+        ] {
+            let body = json!({"jsonrpc":"2.0","id":1,"method":"eth_simulateV1","params":[{"blockStateCalls": block_state_calls}, "latest"]});
+            let request: RpcRequest = serde_json::from_value(body).unwrap();
+            assert!(matches!(
+                map_http_requests(&request, test_context(storage.clone())),
+                Err(RpcErr::BadParams)
+            ));
+        }
+        // Nothing is persisted
+        assert_eq!(storage.get_latest_block_number().unwrap(), Some(0));
+        assert!(storage.get_account_info(contract).unwrap().is_none());
+    }
+
+    #[test]
+    fn send_raw_transaction() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut chain_config = genesis.config;
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // Transfer signed for chain id 1 from the EIP-155 spec
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_sendRawTransaction","params":["0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"]}"#;
+        let request: RpcRequest = serde_json::from_str(body).unwrap();
+        let send = || {
+            rpc_response(
+                request.id.clone().unwrap(),
+                map_http_requests(&request, test_context(storage.clone())),
+            )
+        };
+        let response = send();
+        assert_eq!(response["error"]["code"], -32000);
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid chain id"));
+        // The sender can't pay for the transfer
+        chain_config.chain_id = U256::from(1);
+        storage
+            .set_chain_config(&chain_config)
+            .expect("Failed to write to test DB");
+        let response = send();
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Insufficient funds"));
+        let sender = Address::from_str("0x9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap();
+        storage
+            .add_account_info(
+                sender,
+                AccountInfo {
+                    balance: U256::from(2) * U256::exp10(18),
+                    ..Default::default()
+                },
+            )
+            .expect("Failed to write to test DB");
+        let response = send();
+        let hash = H256::from_str(response["result"].as_str().unwrap()).unwrap();
+        assert!(storage.get_transaction_from_pool(hash).is_some());
+        // The same transaction can't replace itself
+        let response = send();
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Replacement transaction underpriced"));
+    }
+
+    #[test]
+    fn get_logs() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // Store a block with a single transaction emitting two logs
+        let (first, second) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb));
+        let topic = H256::repeat_byte(1);
+        let logs = vec![
+            Log {
+                address: first,
+                topics: vec![],
+                data: Bytes::new(),
+            },
+            Log {
+                address: second,
+                topics: vec![topic],
+                data: Bytes::new(),
+            },
+        ];
+        let transaction = Transaction::decode_canonical(&hex::decode("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap()).unwrap();
+        let mut header = storage.get_block_header(0).unwrap().unwrap();
+        header.parent_hash = header.compute_block_hash();
+        header.number = 1;
+        header.logs_bloom = Log::bloom(&logs);
+        let block_hash = header.compute_block_hash();
+        let body = BlockBody {
+            transactions: vec![transaction],
+            ommers: vec![],
+            withdrawals: Some(vec![]),
+        };
+        storage.add_block_number(block_hash, 1).unwrap();
+        storage.add_block(Block { header, body }).unwrap();
+        storage
+            .add_receipt(
+                1,
+                0,
+                Receipt::new(TxType::Legacy, true, 21_000, Log::bloom(&logs), logs),
+            )
+            .unwrap();
+        storage.update_latest_block_number(1).unwrap();
+
+        let get_logs = |filter: Value, context: RpcApiContext| {
+            let body = json!({"jsonrpc":"2.0","id":1,"method":"eth_getLogs","params":[filter]});
+            let request: RpcRequest = serde_json::from_value(body).unwrap();
+            rpc_response(
+                request.id.clone().unwrap(),
+                map_http_requests(&request, context),
+            )
+        };
+        let response = get_logs(
+            json!({"fromBlock":"earliest","address":[first]}),
+            test_context(storage.clone()),
+        );
+        assert_eq!(response["result"].as_array().unwrap().len(), 1);
+        assert_eq!(response["result"][0]["address"], json!(first));
+        assert_eq!(response["result"][0]["blockNumber"], "0x1");
+        assert_eq!(response["result"][0]["logIndex"], "0x0");
+        let response = get_logs(
+            json!({"fromBlock":"0x0","toBlock":"0x1","topics":[topic]}),
+            test_context(storage.clone()),
+        );
+        assert_eq!(response["result"].as_array().unwrap().len(), 1);
+        assert_eq!(response["result"][0]["address"], json!(second));
+        assert_eq!(response["result"][0]["logIndex"], "0x1");
+        let response = get_logs(
+            json!({"blockHash":block_hash}),
+            test_context(storage.clone()),
+        );
+        assert_eq!(response["result"].as_array().unwrap().len(), 2);
+        // The log with the topic was emitted by the other address
+        let response = get_logs(
+            json!({"address":first,"topics":[topic]}),
+            test_context(storage.clone()),
+        );
+        assert_eq!(response["result"], json!([]));
+        // Queries exceeding the limits are rejected
+        let mut context = test_context(storage);
+        context.logs_limits.max_block_range = 1;
+        let response = get_logs(json!({"fromBlock":"0x0"}), context.clone());
+        assert_eq!(response["error"]["code"], -32005);
+        context.logs_limits.max_block_range = 2;
+        context.logs_limits.max_results = 1;
+        let response = get_logs(json!({"fromBlock":"0x0"}), context);
+        assert_eq!(response["error"]["code"], -32005);
+    }
+
+    #[test]
+    fn get_raw_data() {
+        let file = std::fs::File::open("../../test_data/genesis.json")
+            .expect("Failed to open genesis file");
+        let genesis: Genesis = serde_json::from_reader(std::io::BufReader::new(file))
+            .expect("Failed to deserialize genesis file");
+        let mut storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to write to test DB");
+        // Store a block with a single transaction
+        let raw_transaction = "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+        let transaction =
+            Transaction::decode_canonical(&hex::decode(&raw_transaction[2..]).unwrap()).unwrap();
+        let mut header = storage.get_block_header(0).unwrap().unwrap();
+        header.parent_hash = header.compute_block_hash();
+        header.number = 1;
+        let block_hash = header.compute_block_hash();
+        let block = Block {
+            header,
+            body: BlockBody {
+                transactions: vec![transaction.clone()],
+                ommers: vec![],
+                withdrawals: Some(vec![]),
+            },
+        };
+        let receipt = Receipt::new(TxType::Legacy, true, 21_000, Default::default(), vec![]);
+        storage.add_block_number(block_hash, 1).unwrap();
+        storage.add_block(block.clone()).unwrap();
+        storage.add_receipt(1, 0, receipt.clone()).unwrap();
+        storage
+            .add_transaction_location(transaction.compute_hash(), 1, 0)
+            .unwrap();
+        storage.update_latest_block_number(1).unwrap();
+
+        let request = |method: &str, param: Value| {
+            let body = json!({"jsonrpc":"2.0","id":1,"method":method,"params":[param]});
+            let request: RpcRequest = serde_json::from_value(body).unwrap();
+            map_http_requests(&request, test_context(storage.clone())).expect("Request failed")
+        };
+        let decode = |value: Value| hex::decode(&value.as_str().unwrap()[2..]).unwrap();
+        // Blocks can be requested by number, tag or hash
+        for block_id in [json!("0x1"), json!("latest"), json!(block_hash)] {
+            let header = decode(request("debug_getRawHeader", block_id.clone()));
+            assert_eq!(BlockHeader::decode(&header).unwrap(), block.header);
+            let raw_block = decode(request("debug_getRawBlock", block_id.clone()));
+            assert_eq!(Block::decode(&raw_block).unwrap(), block);
+            let receipts = request("debug_getRawReceipts", block_id);
+            assert_eq!(receipts.as_array().unwrap().len(), 1);
+            assert_eq!(decode(receipts[0].clone()), receipt.encode_to_vec());
+        }
+        assert_eq!(
+            request("debug_getRawTransaction", json!(transaction.compute_hash())),
+            raw_transaction
+        );
+        // Unknown blocks and transactions
+        assert_eq!(request("debug_getRawBlock", json!("0x2")), Value::Null);
+        assert_eq!(
+            request("debug_getRawTransaction", json!(H256::repeat_byte(1))),
+            Value::Null
+        );
+    }
 }
@@ -0,0 +1,331 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use axum::extract::ws::{Message, WebSocket};
+use ethereum_rust_core::{
+    types::{BlockHeader, BlockNumber},
+    H256,
+};
+use ethereum_rust_storage::Store;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+
+use crate::{
+    eth::logs::{block_logs, LogsFilter},
+    handle_rpc_message, map_http_requests,
+    utils::RpcErr,
+    RpcApiContext,
+};
+
+/// Amount of events kept for subscribers that fall behind before they start missing them
+const EVENTS_CAPACITY: usize = 1024;
+
+/// Events that websocket subscribers are notified of
+#[derive(Debug, Clone)]
+pub enum ChainEvent {
+    NewHead(BlockHeader),
+    NewPendingTransaction(H256),
+}
+
+/// Broadcasts chain events to every open websocket connection
+#[derive(Debug, Clone)]
+pub struct ChainEvents {
+    sender: broadcast::Sender<ChainEvent>,
+}
+
+impl Default for ChainEvents {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(EVENTS_CAPACITY).0,
+        }
+    }
+}
+
+impl ChainEvents {
+    pub fn subscribe(&self) -> broadcast::Receiver<ChainEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Notifies the stored blocks with the given numbers as new heads of the chain, in order
+    pub fn new_heads(&self, numbers: RangeInclusive<BlockNumber>, storage: &Store) {
+        for number in numbers {
+            let Ok(Some(header)) = storage.get_block_header(number) else {
+                warn!("Can't notify unknown head block {number}");
+                return;
+            };
+            // Sending only fails if nobody is subscribed
+            let _ = self.sender.send(ChainEvent::NewHead(header));
+        }
+    }
+
+    pub fn new_pending_transaction(&self, hash: H256) {
+        let _ = self.sender.send(ChainEvent::NewPendingTransaction(hash));
+    }
+}
+
+#[derive(Debug)]
+pub enum SubscriptionKind {
+    NewHeads,
+    Logs(LogsFilter),
+    NewPendingTransactions,
+}
+
+pub struct SubscribeRequest {
+    pub kind: SubscriptionKind,
+}
+
+pub struct UnsubscribeRequest {
+    pub id: u64,
+}
+
+impl SubscribeRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<SubscribeRequest> {
+        let params = params.as_ref()?;
+        let kind = match (params.first()?.as_str()?, params.get(1)) {
+            ("newHeads", None) => SubscriptionKind::NewHeads,
+            ("newPendingTransactions", None) => SubscriptionKind::NewPendingTransactions,
+            ("logs", None) => SubscriptionKind::Logs(LogsFilter::parse(&json!({}))?),
+            ("logs", Some(filter)) if params.len() == 2 => {
+                SubscriptionKind::Logs(LogsFilter::parse(filter)?)
+            }
+            _ => return None,
+        };
+        Some(SubscribeRequest { kind })
+    }
+}
+
+impl UnsubscribeRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<UnsubscribeRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        let id: String = serde_json::from_value(params[0].clone()).ok()?;
+        Some(UnsubscribeRequest {
+            id: u64::from_str_radix(id.strip_prefix("0x")?, 16).ok()?,
+        })
+    }
+}
+
+/// Subscriptions made through a single websocket connection
+#[derive(Debug, Default)]
+struct Subscriptions {
+    active: HashMap<u64, SubscriptionKind>,
+    next_id: u64,
+}
+
+impl Subscriptions {
+    fn subscribe(&mut self, request: SubscribeRequest) -> Value {
+        self.next_id += 1;
+        info!("New subscription {:#x}: {:?}", self.next_id, request.kind);
+        self.active.insert(self.next_id, request.kind);
+        subscription_id(self.next_id)
+    }
+
+    fn unsubscribe(&mut self, request: &UnsubscribeRequest) -> Value {
+        Value::Bool(self.active.remove(&request.id).is_some())
+    }
+
+    /// Returns the notifications to send for the event, in the order they must be sent
+    fn notifications(&self, event: &ChainEvent, storage: &Store) -> Vec<Value> {
+        let mut notifications = Vec::new();
+        for (id, kind) in &self.active {
+            match (kind, event) {
+                (SubscriptionKind::NewHeads, ChainEvent::NewHead(header)) => {
+                    let mut result = json!(header);
+                    result["hash"] = json!(header.compute_block_hash());
+                    notifications.push(notification(*id, result));
+                }
+                (SubscriptionKind::Logs(filter), ChainEvent::NewHead(header)) => {
+                    let Ok(logs) = block_logs(header, filter, storage) else {
+                        warn!("Failed to read logs of block {}", header.number);
+                        continue;
+                    };
+                    notifications.extend(logs.into_iter().map(|log| notification(*id, json!(log))));
+                }
+                (
+                    SubscriptionKind::NewPendingTransactions,
+                    ChainEvent::NewPendingTransaction(hash),
+                ) => notifications.push(notification(*id, json!(hash))),
+                _ => {}
+            }
+        }
+        notifications
+    }
+}
+
+fn subscription_id(id: u64) -> Value {
+    Value::String(format!("{id:#x}"))
+}
+
+fn notification(id: u64, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": {
+            "subscription": subscription_id(id),
+            "result": result,
+        }
+    })
+}
+
+/// Serves requests and pushes subscription notifications over a websocket connection until it is closed
+pub(crate) async fn serve_websocket(mut socket: WebSocket, context: RpcApiContext) {
+    let mut events = context.events.subscribe();
+    let mut subscriptions = Subscriptions::default();
+    loop {
+        let outgoing: Vec<Value> = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(body))) => {
+                    handle_websocket_request(&body, &mut subscriptions, context.clone())
+                        .into_iter()
+                        .collect()
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by axum
+                Some(Ok(_)) => continue,
+            },
+            event = events.recv() => match event {
+                Ok(event) => subscriptions.notifications(&event, &context.storage),
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Websocket connection missed {missed} chain events");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        for message in outgoing {
+            if socket
+                .send(Message::Text(message.to_string()))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
+
+fn handle_websocket_request(
+    body: &str,
+    subscriptions: &mut Subscriptions,
+    context: RpcApiContext,
+) -> Option<Value> {
+    handle_rpc_message(body, |req| match req.method.as_str() {
+        "eth_subscribe" => SubscribeRequest::parse(&req.params)
+            .ok_or(RpcErr::BadParams)
+            .map(|request| subscriptions.subscribe(request)),
+        "eth_unsubscribe" => UnsubscribeRequest::parse(&req.params)
+            .ok_or(RpcErr::BadParams)
+            .map(|request| subscriptions.unsubscribe(&request)),
+        _ => map_http_requests(req, context.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_rust_core::{
+        types::{Block, BlockBody, Log, Receipt, Transaction, TxType},
+        Address, Bytes,
+    };
+    use ethereum_rust_storage::EngineType;
+
+    use super::*;
+
+    #[test]
+    fn parse_subscribe_request() {
+        let parse =
+            |params: Value| SubscribeRequest::parse(&serde_json::from_value(params).unwrap());
+        assert!(matches!(
+            parse(json!(["newHeads"])).unwrap().kind,
+            SubscriptionKind::NewHeads
+        ));
+        assert!(matches!(
+            parse(json!(["logs", {"address": Address::zero()}])).unwrap().kind,
+            SubscriptionKind::Logs(filter) if filter.addresses == vec![Address::zero()]
+        ));
+        assert!(parse(json!(["newHeads", {}])).is_none());
+        assert!(parse(json!(["syncing"])).is_none());
+    }
+
+    #[test]
+    fn notify_subscribers_of_events() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let address = Address::repeat_byte(0xaa);
+        let logs = vec![Log {
+            address,
+            topics: vec![],
+            data: Bytes::new(),
+        }];
+        let transaction = Transaction::decode_canonical(&hex::decode("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83").unwrap()).unwrap();
+        let header = BlockHeader {
+            number: 1,
+            logs_bloom: Log::bloom(&logs),
+            ..Default::default()
+        };
+        let block_hash = header.compute_block_hash();
+        storage.add_block_number(block_hash, 1).unwrap();
+        storage
+            .add_block(Block {
+                header: header.clone(),
+                body: BlockBody {
+                    transactions: vec![transaction],
+                    ommers: vec![],
+                    withdrawals: None,
+                },
+            })
+            .unwrap();
+        storage
+            .add_receipt(
+                1,
+                0,
+                Receipt::new(TxType::Legacy, true, 21_000, Log::bloom(&logs), logs),
+            )
+            .unwrap();
+
+        let mut subscriptions = Subscriptions::default();
+        let heads = subscriptions.subscribe(SubscribeRequest {
+            kind: SubscriptionKind::NewHeads,
+        });
+        let matching_logs = subscriptions.subscribe(SubscribeRequest {
+            kind: SubscriptionKind::Logs(
+                LogsFilter::parse(&json!({ "address": address })).unwrap(),
+            ),
+        });
+        subscriptions.subscribe(SubscribeRequest {
+            kind: SubscriptionKind::Logs(
+                LogsFilter::parse(&json!({ "address": Address::zero() })).unwrap(),
+            ),
+        });
+        let notifications = subscriptions.notifications(&ChainEvent::NewHead(header), &storage);
+        assert_eq!(notifications.len(), 2);
+        let notified = |id: &Value| {
+            notifications
+                .iter()
+                .find(|notification| notification["params"]["subscription"] == *id)
+                .unwrap()["params"]["result"]
+                .clone()
+        };
+        assert_eq!(notified(&heads)["hash"], json!(block_hash));
+        assert_eq!(notified(&heads)["number"], "0x1");
+        assert_eq!(notified(&matching_logs)["address"], json!(address));
+        // Pending transactions only reach their own subscriptions
+        let hash = H256::repeat_byte(1);
+        assert!(subscriptions
+            .notifications(&ChainEvent::NewPendingTransaction(hash), &storage)
+            .is_empty());
+        let pending = subscriptions.subscribe(SubscribeRequest {
+            kind: SubscriptionKind::NewPendingTransactions,
+        });
+        let notifications =
+            subscriptions.notifications(&ChainEvent::NewPendingTransaction(hash), &storage);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0]["params"]["subscription"], pending);
+        assert_eq!(notifications[0]["params"]["result"], json!(hash));
+        assert_eq!(
+            subscriptions.unsubscribe(&UnsubscribeRequest { id: 3 }),
+            json!(true)
+        );
+    }
+}
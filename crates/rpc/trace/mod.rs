@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+
+use ethereum_rust_core::{types::Block, Address, H256};
+use ethereum_rust_evm::{
+    process_withdrawals,
+    trace::{self, LocalizedTransactionTrace, TraceType},
+    SpecId,
+};
+use ethereum_rust_storage::Store;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    debug::trace::{block_and_spec, parent_state},
+    eth::{
+        block::{resolve_block_number, BlockIdentifier, BlockTag},
+        logs::LogsLimits,
+    },
+    utils::RpcErr,
+};
+
+/// Maximum amount of blocks a trace filter can cover, as every block in its range is re-executed
+const MAX_TRACE_FILTER_BLOCKS: u64 = 100;
+
+pub struct TraceBlockRequest {
+    pub block: BlockIdentifier,
+}
+
+pub struct TraceTransactionRequest {
+    pub transaction_hash: H256,
+}
+
+pub struct ReplayTransactionRequest {
+    pub transaction_hash: H256,
+    pub trace_types: HashSet<TraceType>,
+}
+
+pub struct ReplayBlockTransactionsRequest {
+    pub block: BlockIdentifier,
+    pub trace_types: HashSet<TraceType>,
+}
+
+pub struct TraceFilterRequest {
+    pub filter: TraceFilter,
+}
+
+/// Selects the traces of the blocks in the range whose action was sent from and to the given addresses
+/// Empty address lists match any address
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFilter {
+    #[serde(default)]
+    pub from_block: Option<BlockIdentifier>,
+    #[serde(default)]
+    pub to_block: Option<BlockIdentifier>,
+    #[serde(default)]
+    pub from_address: Vec<Address>,
+    #[serde(default)]
+    pub to_address: Vec<Address>,
+    /// Amount of matching traces to skip
+    #[serde(default)]
+    pub after: Option<usize>,
+    /// Maximum amount of traces to return
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+impl TraceFilter {
+    fn matches(&self, trace: &LocalizedTransactionTrace) -> bool {
+        let (from, to) = trace::trace_addresses(&trace.trace);
+        (self.from_address.is_empty() || self.from_address.contains(&from))
+            && (self.to_address.is_empty() || to.is_some_and(|to| self.to_address.contains(&to)))
+    }
+}
+
+impl TraceBlockRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceBlockRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        Some(TraceBlockRequest {
+            block: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+impl TraceTransactionRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceTransactionRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        Some(TraceTransactionRequest {
+            transaction_hash: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+impl ReplayTransactionRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<ReplayTransactionRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 2 {
+            return None;
+        };
+        Some(ReplayTransactionRequest {
+            transaction_hash: serde_json::from_value(params[0].clone()).ok()?,
+            trace_types: serde_json::from_value(params[1].clone()).ok()?,
+        })
+    }
+}
+
+impl ReplayBlockTransactionsRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<ReplayBlockTransactionsRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 2 {
+            return None;
+        };
+        Some(ReplayBlockTransactionsRequest {
+            block: serde_json::from_value(params[0].clone()).ok()?,
+            trace_types: serde_json::from_value(params[1].clone()).ok()?,
+        })
+    }
+}
+
+impl TraceFilterRequest {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<TraceFilterRequest> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        Some(TraceFilterRequest {
+            filter: serde_json::from_value(params[0].clone()).ok()?,
+        })
+    }
+}
+
+/// Returns the traces of every transaction in the block
+pub fn trace_block(request: &TraceBlockRequest, storage: Store) -> Result<Value, RpcErr> {
+    info!("Requested parity traces of block {}", request.block);
+    let (block, spec_id) = resolve_block(&request.block, &storage)?;
+    let mut state = parent_state(&block, storage);
+    let traces =
+        trace::localized_block_traces(&block, &mut state, spec_id).map_err(|_| RpcErr::Vm)?;
+    serde_json::to_value(traces).map_err(|_| RpcErr::Internal)
+}
+
+pub fn trace_transaction(
+    request: &TraceTransactionRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!(
+        "Requested parity traces of transaction {:#x}",
+        request.transaction_hash
+    );
+    let (block, index, spec_id) = resolve_transaction(request.transaction_hash, &storage)?;
+    let mut state = parent_state(&block, storage);
+    let traces = trace::localized_transaction_traces(&block, index, &mut state, spec_id)
+        .map_err(|_| RpcErr::Vm)?;
+    serde_json::to_value(traces).map_err(|_| RpcErr::Internal)
+}
+
+pub fn replay_transaction(
+    request: &ReplayTransactionRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!(
+        "Requested replay of transaction {:#x}",
+        request.transaction_hash
+    );
+    let (block, index, spec_id) = resolve_transaction(request.transaction_hash, &storage)?;
+    let mut state = parent_state(&block, storage);
+    let results =
+        trace::replay_transaction(&block, index, &mut state, spec_id, &request.trace_types)
+            .map_err(|_| RpcErr::Vm)?;
+    serde_json::to_value(results).map_err(|_| RpcErr::Internal)
+}
+
+pub fn replay_block_transactions(
+    request: &ReplayBlockTransactionsRequest,
+    storage: Store,
+) -> Result<Value, RpcErr> {
+    info!("Requested replay of block {}", request.block);
+    let (block, spec_id) = resolve_block(&request.block, &storage)?;
+    let mut state = parent_state(&block, storage);
+    let results =
+        trace::replay_block_transactions(&block, &mut state, spec_id, &request.trace_types)
+            .map_err(|_| RpcErr::Vm)?;
+    serde_json::to_value(results).map_err(|_| RpcErr::Internal)
+}
+
+/// Returns the traces matching the filter, re-executing every block in its range
+/// The range is bounded by its own limit, while the amount of traces is bounded by the same limit as log queries
+pub fn trace_filter(
+    request: &TraceFilterRequest,
+    storage: Store,
+    limits: LogsLimits,
+) -> Result<Value, RpcErr> {
+    let filter = &request.filter;
+    info!("Requested traces with filter: {:?}", filter);
+    let latest = BlockIdentifier::Tag(BlockTag::Latest);
+    let from = resolve_block_number(filter.from_block.as_ref().unwrap_or(&latest), &storage);
+    let to = resolve_block_number(filter.to_block.as_ref().unwrap_or(&latest), &storage);
+    let (from, to) = match (from, to) {
+        (Ok(Some(from)), Ok(Some(to))) if from <= to => (from, to),
+        (Ok(_), Ok(_)) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    if to - from >= MAX_TRACE_FILTER_BLOCKS {
+        return Err(RpcErr::LimitExceeded(format!(
+            "block range exceeds the maximum of {MAX_TRACE_FILTER_BLOCKS}"
+        )));
+    }
+    let mut traces = Vec::new();
+    // Blocks are executed in order on top of the same state, which is only built once
+    let mut state = None;
+    for number in from..=to {
+        // Blocks are stored in order, so there are none after a missing one
+        let Some((block, spec_id)) = block_and_spec(number, &storage)? else {
+            break;
+        };
+        let state = state.get_or_insert_with(|| parent_state(&block, storage.clone()));
+        let block_traces =
+            trace::localized_block_traces(&block, state, spec_id).map_err(|_| RpcErr::Vm)?;
+        // Withdrawals are processed after the transactions, leaving the state ready for the next block
+        if let Some(withdrawals) = &block.body.withdrawals {
+            process_withdrawals(state, withdrawals).map_err(|_| RpcErr::Internal)?;
+        }
+        traces.extend(
+            block_traces
+                .into_iter()
+                .filter(|trace| filter.matches(trace)),
+        );
+        if traces.len() > limits.max_results {
+            return Err(RpcErr::LimitExceeded(format!(
+                "query returned more than {} results",
+                limits.max_results
+            )));
+        }
+    }
+    let traces: Vec<LocalizedTransactionTrace> = traces
+        .into_iter()
+        .skip(filter.after.unwrap_or_default())
+        .take(filter.count.unwrap_or(usize::MAX))
+        .collect();
+    serde_json::to_value(traces).map_err(|_| RpcErr::Internal)
+}
+
+// Returns the requested block along with the spec it was executed with
+fn resolve_block(block: &BlockIdentifier, storage: &Store) -> Result<(Block, SpecId), RpcErr> {
+    let block_number = match resolve_block_number(block, storage) {
+        Ok(Some(block_number)) => block_number,
+        Ok(_) => return Err(RpcErr::BadParams),
+        _ => return Err(RpcErr::Internal),
+    };
+    block_and_spec(block_number, storage)?.ok_or(RpcErr::BadParams)
+}
+
+// Returns the block the transaction was included in, its index in the block and the spec the block was executed with
+fn resolve_transaction(
+    transaction_hash: H256,
+    storage: &Store,
+) -> Result<(Block, usize, SpecId), RpcErr> {
+    let (block_number, index) = match storage.get_transaction_location(transaction_hash) {
+        Ok(Some(location)) => location,
+        // Transaction not found
+        Ok(_) => return Err(RpcErr::BadParams),
+        // DB error
+        _ => return Err(RpcErr::Internal),
+    };
+    let (block, spec_id) = block_and_spec(block_number, storage)?.ok_or(RpcErr::Internal)?;
+    Ok((block, index as usize, spec_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_rust_storage::EngineType;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_trace_requests() {
+        let hash = json!(format!("{:#x}", H256::repeat_byte(1)));
+        let request = ReplayTransactionRequest::parse(&Some(vec![
+            hash.clone(),
+            json!(["trace", "stateDiff"]),
+        ]))
+        .unwrap();
+        assert_eq!(
+            request.trace_types,
+            HashSet::from([TraceType::Trace, TraceType::StateDiff])
+        );
+        assert!(ReplayTransactionRequest::parse(&Some(vec![hash, json!(["unknown"])])).is_none());
+
+        let request = TraceFilterRequest::parse(&Some(vec![json!({
+            "fromBlock": "0x1",
+            "toAddress": [format!("{:#x}", Address::repeat_byte(2))],
+            "count": 10,
+        })]))
+        .unwrap();
+        assert!(matches!(
+            request.filter.from_block,
+            Some(BlockIdentifier::Number(1))
+        ));
+        assert!(request.filter.to_block.is_none());
+        assert!(request.filter.from_address.is_empty());
+        assert_eq!(request.filter.to_address, vec![Address::repeat_byte(2)]);
+        assert_eq!(request.filter.count, Some(10));
+    }
+
+    #[test]
+    fn trace_filter_block_range_limit() {
+        let storage =
+            Store::new("temp.db", EngineType::InMemory).expect("Failed to create test DB");
+        let filter = |from: u64, to: u64| TraceFilterRequest {
+            filter: serde_json::from_value(json!({
+                "fromBlock": format!("{from:#x}"),
+                "toBlock": format!("{to:#x}"),
+            }))
+            .unwrap(),
+        };
+        // The range is bounded even if log queries can cover it
+        let result = trace_filter(
+            &filter(0, MAX_TRACE_FILTER_BLOCKS),
+            storage.clone(),
+            LogsLimits::default(),
+        );
+        assert!(matches!(result, Err(RpcErr::LimitExceeded(_))));
+        // Blocks after the latest one are not traced
+        let result = trace_filter(
+            &filter(1, MAX_TRACE_FILTER_BLOCKS),
+            storage,
+            LogsLimits::default(),
+        );
+        assert_eq!(result.unwrap(), json!([]));
+    }
+}
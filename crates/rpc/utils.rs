@@ -1,65 +1,192 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
+#[derive(Debug)]
 pub enum RpcErr {
+    ParseError,
+    InvalidRequest,
     MethodNotFound,
     BadParams,
     UnsuportedFork,
+    InvalidForkChoiceState,
+    InvalidPayloadAttributes,
+    UnknownPayload,
+    TooLargeRequest,
     Internal,
     Vm,
+    Revert { data: String },
+    Halt { reason: String, gas_used: u64 },
+    InvalidTransaction(String),
+    LimitExceeded(String),
+    FilterNotFound,
+    NamedParamsNotSupported,
 }
 
 impl From<RpcErr> for RpcErrorMetadata {
     fn from(value: RpcErr) -> Self {
         match value {
+            RpcErr::ParseError => RpcErrorMetadata {
+                code: -32700,
+                message: "Parse error".to_string(),
+                data: None,
+            },
+            RpcErr::InvalidRequest => RpcErrorMetadata {
+                code: -32600,
+                message: "Invalid request".to_string(),
+                data: None,
+            },
             RpcErr::MethodNotFound => RpcErrorMetadata {
                 code: -32601,
                 message: "Method not found".to_string(),
+                data: None,
             },
             RpcErr::BadParams => RpcErrorMetadata {
                 code: -32602,
                 message: "Invalid params".to_string(),
+                data: None,
             },
             RpcErr::UnsuportedFork => RpcErrorMetadata {
                 code: -38005,
                 message: "Unsupported fork".to_string(),
+                data: None,
+            },
+            RpcErr::InvalidForkChoiceState => RpcErrorMetadata {
+                code: -38002,
+                message: "Invalid forkchoice state".to_string(),
+                data: None,
+            },
+            RpcErr::InvalidPayloadAttributes => RpcErrorMetadata {
+                code: -38003,
+                message: "Invalid payload attributes".to_string(),
+                data: None,
+            },
+            RpcErr::UnknownPayload => RpcErrorMetadata {
+                code: -38001,
+                message: "Unknown payload".to_string(),
+                data: None,
+            },
+            RpcErr::TooLargeRequest => RpcErrorMetadata {
+                code: -38004,
+                message: "Too large request".to_string(),
+                data: None,
             },
             RpcErr::Internal => RpcErrorMetadata {
                 code: -32603,
                 message: "Internal Error".to_string(),
+                data: None,
             },
             RpcErr::Vm => RpcErrorMetadata {
                 code: -32015,
                 message: "Vm execution error".to_string(),
+                data: None,
+            },
+            RpcErr::Revert { data } => RpcErrorMetadata {
+                // This code is specific to the EVM revert error as defined by the execution-apis spec
+                code: 3,
+                message: "execution reverted".to_string(),
+                data: Some(data),
+            },
+            RpcErr::Halt { reason, gas_used } => RpcErrorMetadata {
+                code: -32000,
+                message: format!("execution halted: reason={reason}, gas_used={gas_used}"),
+                data: None,
+            },
+            RpcErr::InvalidTransaction(reason) => RpcErrorMetadata {
+                code: -32000,
+                message: format!("invalid transaction: {reason}"),
+                data: None,
+            },
+            RpcErr::LimitExceeded(reason) => RpcErrorMetadata {
+                code: -32005,
+                message: format!("limit exceeded: {reason}"),
+                data: None,
+            },
+            RpcErr::FilterNotFound => RpcErrorMetadata {
+                code: -32000,
+                message: "filter not found".to_string(),
+                data: None,
+            },
+            RpcErr::NamedParamsNotSupported => RpcErrorMetadata {
+                code: -32602,
+                message: "named params not supported".to_string(),
+                data: None,
             },
         }
     }
 }
 
+/// Identifier of a request, which the response must echo back
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum RpcRequestId {
+    Number(serde_json::Number),
+    String(String),
+    Null,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcRequest {
-    pub id: i32,
+    /// Requests without an id are notifications, which must not be responded to
+    #[serde(
+        default,
+        deserialize_with = "deserialize_id",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub id: Option<RpcRequestId>,
     pub jsonrpc: String,
     pub method: String,
+    #[serde(default, deserialize_with = "deserialize_params")]
     pub params: Option<Vec<Value>>,
 }
 
+impl RpcRequest {
+    /// Parses a request, checking it follows the JSON-RPC 2.0 spec
+    pub fn parse(value: Value) -> Result<RpcRequest, RpcErr> {
+        match serde_json::from_value::<RpcRequest>(value) {
+            Ok(request) if request.jsonrpc == "2.0" => Ok(request),
+            _ => Err(RpcErr::InvalidRequest),
+        }
+    }
+}
+
+// A null id is still an id, so it must be told apart from a missing one
+fn deserialize_id<'de, D>(deserializer: D) -> Result<Option<RpcRequestId>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    RpcRequestId::deserialize(deserializer).map(Some)
+}
+
+// Params given by name are rejected before parsing, see `handle_single_request`
+fn deserialize_params<'de, D>(deserializer: D) -> Result<Option<Vec<Value>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Value::Array(params)) => Ok(Some(params)),
+        Some(_) => Err(serde::de::Error::custom("params must be an array")),
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcErrorMetadata {
     pub code: i32,
     pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcSuccessResponse {
-    pub id: i32,
+    pub id: RpcRequestId,
     pub jsonrpc: String,
     pub result: Value,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcErrorResponse {
-    pub id: i32,
+    pub id: RpcRequestId,
     pub jsonrpc: String,
     pub error: RpcErrorMetadata,
 }
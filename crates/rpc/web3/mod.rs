@@ -0,0 +1,39 @@
+use ethereum_rust_core::H256;
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+use crate::{engine::CLIENT_NAME, utils::RpcErr};
+
+pub struct Sha3Request {
+    pub data: Vec<u8>,
+}
+
+impl Sha3Request {
+    pub fn parse(params: &Option<Vec<Value>>) -> Option<Sha3Request> {
+        let params = params.as_ref()?;
+        if params.len() != 1 {
+            return None;
+        };
+        let data: String = serde_json::from_value(params[0].clone()).ok()?;
+        Some(Sha3Request {
+            data: hex::decode(data.strip_prefix("0x")?).ok()?,
+        })
+    }
+}
+
+/// Returns the client's name, version, commit and platform, with the format
+/// "name/vVersion-commit/os-arch"
+pub(crate) fn client_version() -> String {
+    format!(
+        "{CLIENT_NAME}/v{}-{}/{}-{}",
+        env!("CARGO_PKG_VERSION"),
+        env!("GIT_COMMIT_PREFIX"),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+pub fn sha3(request: &Sha3Request) -> Result<Value, RpcErr> {
+    let hash = H256::from_slice(&Keccak256::digest(&request.data));
+    serde_json::to_value(hash).map_err(|_| RpcErr::Internal)
+}
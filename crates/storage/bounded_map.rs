@@ -0,0 +1,57 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// Map holding up to a fixed amount of entries
+/// When full, the oldest entries are evicted first
+#[derive(Debug)]
+pub struct BoundedMap<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    insertion_order: VecDeque<K>,
+}
+
+impl<K: Eq + Hash + Clone, V> BoundedMap<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            insertion_order: VecDeque::new(),
+        }
+    }
+
+    /// Inserts the entry, replacing the value of an existing key without refreshing its position
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.entries.insert(key.clone(), value).is_none() {
+            self.insertion_order.push_back(key);
+        }
+        if self.insertion_order.len() > self.capacity {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_entries_are_evicted() {
+        let mut map = BoundedMap::new(2);
+        map.insert(0, "a");
+        map.insert(1, "b");
+        // Replacing a value doesn't make room for a new entry
+        map.insert(0, "c");
+        map.insert(2, "d");
+        assert_eq!(map.get(&0), None);
+        assert_eq!(map.get(&1), Some(&"b"));
+        assert_eq!(map.get(&2), Some(&"d"));
+    }
+}
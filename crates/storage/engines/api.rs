@@ -6,7 +6,7 @@ use ethereum_rust_core::types::{
 use ethereum_types::{Address, H256, U256};
 use std::fmt::Debug;
 
-use crate::error::StoreError;
+use crate::{error::StoreError, history::AccountStateChange};
 
 pub trait StoreEngine: Debug + Send {
     /// Add account info
@@ -183,6 +183,9 @@ pub trait StoreEngine: Debug + Send {
     /// Ignores previously stored values if present
     fn set_chain_config(&mut self, chain_config: &ChainConfig) -> Result<(), StoreError>;
 
+    /// Obtain the stored chain configuration
+    fn get_chain_config(&self) -> Result<Option<ChainConfig>, StoreError>;
+
     /// Obtain the current chain id
     fn get_chain_id(&self) -> Result<Option<U256>, StoreError>;
 
@@ -222,4 +225,31 @@ pub trait StoreEngine: Debug + Send {
 
     // Obtain pending block number
     fn get_pending_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
+
+    /// Stores the state the accounts modified by a block had before it, indexes the block
+    /// in the history of each modified account and marks it as the block of the current state
+    fn add_state_changes(
+        &mut self,
+        block_number: BlockNumber,
+        changes: Vec<AccountStateChange>,
+    ) -> Result<(), StoreError>;
+
+    /// Removes the changes stored for a block along with its entries in the account histories,
+    /// marking its parent as the block of the current state
+    fn remove_state_changes(
+        &mut self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<AccountStateChange>>, StoreError>;
+
+    /// Obtain the number of the block whose resulting state is the current state
+    fn get_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
+
+    /// Obtain the state the accounts modified by a block had before it
+    fn get_state_changes(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<AccountStateChange>>, StoreError>;
+
+    /// Obtain the numbers of the blocks that modified the account, in ascending order
+    fn get_account_history(&self, address: Address) -> Result<Vec<BlockNumber>, StoreError>;
 }
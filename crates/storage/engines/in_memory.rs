@@ -1,4 +1,4 @@
-use crate::error::StoreError;
+use crate::{error::StoreError, history::AccountStateChange};
 use bytes::Bytes;
 use ethereum_rust_core::types::{
     AccountInfo, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt,
@@ -21,17 +21,21 @@ pub struct Store {
     // Maps transaction hashes to their block number and index within the block
     transaction_locations: HashMap<H256, (BlockNumber, Index)>,
     receipts: HashMap<BlockNumber, HashMap<Index, Receipt>>,
+    // Previous state of the accounts modified by each block
+    state_changes: HashMap<BlockNumber, Vec<AccountStateChange>>,
+    // Maps addresses to the numbers of the blocks that modified them, in ascending order
+    account_histories: HashMap<Address, Vec<BlockNumber>>,
 }
 
 #[derive(Default)]
 struct ChainData {
-    chain_id: Option<U256>,
+    chain_config: Option<ChainConfig>,
     earliest_block_number: Option<BlockNumber>,
     finalized_block_number: Option<BlockNumber>,
     safe_block_number: Option<BlockNumber>,
     latest_block_number: Option<BlockNumber>,
     pending_block_number: Option<BlockNumber>,
-    cancun_time: Option<u64>,
+    state_block_number: Option<BlockNumber>,
 }
 
 impl Store {
@@ -195,19 +199,23 @@ impl StoreEngine for Store {
     }
 
     fn set_chain_config(&mut self, chain_config: &ChainConfig) -> Result<(), StoreError> {
-        // Store cancun timestamp
-        self.chain_data.cancun_time = chain_config.cancun_time;
-        // Store chain id
-        self.chain_data.chain_id.replace(chain_config.chain_id);
+        self.chain_data.chain_config.replace(*chain_config);
         Ok(())
     }
 
+    fn get_chain_config(&self) -> Result<Option<ChainConfig>, StoreError> {
+        Ok(self.chain_data.chain_config)
+    }
+
     fn get_chain_id(&self) -> Result<Option<U256>, StoreError> {
-        Ok(self.chain_data.chain_id)
+        Ok(self.chain_data.chain_config.map(|config| config.chain_id))
     }
 
     fn get_cancun_time(&self) -> Result<Option<u64>, StoreError> {
-        Ok(self.chain_data.cancun_time)
+        Ok(self
+            .chain_data
+            .chain_config
+            .and_then(|config| config.cancun_time))
     }
 
     fn update_earliest_block_number(
@@ -260,6 +268,57 @@ impl StoreEngine for Store {
     fn get_pending_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
         Ok(self.chain_data.pending_block_number)
     }
+
+    fn add_state_changes(
+        &mut self,
+        block_number: BlockNumber,
+        changes: Vec<AccountStateChange>,
+    ) -> Result<(), StoreError> {
+        for change in changes.iter() {
+            let history = self.account_histories.entry(change.address).or_default();
+            if let Err(position) = history.binary_search(&block_number) {
+                history.insert(position, block_number);
+            }
+        }
+        self.state_changes.insert(block_number, changes);
+        self.chain_data.state_block_number.replace(block_number);
+        Ok(())
+    }
+
+    fn remove_state_changes(
+        &mut self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<AccountStateChange>>, StoreError> {
+        let Some(changes) = self.state_changes.remove(&block_number) else {
+            return Ok(None);
+        };
+        for change in changes.iter() {
+            if let Some(history) = self.account_histories.get_mut(&change.address) {
+                history.retain(|number| *number != block_number);
+            }
+        }
+        self.chain_data.state_block_number = block_number.checked_sub(1);
+        Ok(Some(changes))
+    }
+
+    fn get_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        Ok(self.chain_data.state_block_number)
+    }
+
+    fn get_state_changes(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<AccountStateChange>>, StoreError> {
+        Ok(self.state_changes.get(&block_number).cloned())
+    }
+
+    fn get_account_history(&self, address: Address) -> Result<Vec<BlockNumber>, StoreError> {
+        Ok(self
+            .account_histories
+            .get(&address)
+            .cloned()
+            .unwrap_or_default())
+    }
 }
 
 impl Debug for Store {
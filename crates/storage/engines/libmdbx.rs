@@ -1,8 +1,9 @@
 use super::api::StoreEngine;
 use crate::error::StoreError;
+use crate::history::AccountStateChange;
 use crate::rlp::{
-    AccountCodeHashRLP, AccountCodeRLP, AccountInfoRLP, AddressRLP, BlockBodyRLP, BlockHashRLP,
-    BlockHeaderRLP, ReceiptRLP, TransactionHashRLP,
+    AccountCodeHashRLP, AccountCodeRLP, AccountHistoryRLP, AccountInfoRLP, AddressRLP,
+    BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, ReceiptRLP, StateChangesRLP, TransactionHashRLP,
 };
 use anyhow::Result;
 use bytes::Bytes;
@@ -214,6 +215,10 @@ impl StoreEngine for Store {
     }
 
     fn set_chain_config(&mut self, chain_config: &ChainConfig) -> Result<(), StoreError> {
+        // Store the whole chain config
+        let chain_config_json =
+            serde_json::to_vec(chain_config).map_err(|e| StoreError::Custom(e.to_string()))?;
+        self.write::<ChainData>(ChainDataIndex::ChainConfig, chain_config_json)?;
         // Store cancun timestamp
         if let Some(cancun_time) = chain_config.cancun_time {
             self.write::<ChainData>(ChainDataIndex::CancunTime, cancun_time.encode_to_vec())?;
@@ -225,6 +230,15 @@ impl StoreEngine for Store {
         )
     }
 
+    fn get_chain_config(&self) -> Result<Option<ChainConfig>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::ChainConfig)? {
+            None => Ok(None),
+            Some(ref json) => serde_json::from_slice(json)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
     fn get_chain_id(&self) -> Result<Option<U256>, StoreError> {
         match self.read::<ChainData>(ChainDataIndex::ChainId)? {
             None => Ok(None),
@@ -344,6 +358,103 @@ impl StoreEngine for Store {
                 .map_err(|_| StoreError::DecodeError),
         }
     }
+
+    fn add_state_changes(
+        &mut self,
+        block_number: BlockNumber,
+        changes: Vec<AccountStateChange>,
+    ) -> Result<(), StoreError> {
+        // Write the changes and update the histories in a single transaction so they can't diverge
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for change in changes.iter() {
+            let mut history = txn
+                .get::<AccountHistories>(change.address.into())
+                .map_err(StoreError::LibmdbxError)?
+                .map(|history| history.to())
+                .unwrap_or_default();
+            if let Err(position) = history.binary_search(&block_number) {
+                history.insert(position, block_number);
+                txn.upsert::<AccountHistories>(change.address.into(), history.into())
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+        }
+        txn.upsert::<StateChanges>(block_number, changes.into())
+            .map_err(StoreError::LibmdbxError)?;
+        txn.upsert::<ChainData>(
+            ChainDataIndex::StateBlockNumber,
+            block_number.encode_to_vec(),
+        )
+        .map_err(StoreError::LibmdbxError)?;
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn remove_state_changes(
+        &mut self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<AccountStateChange>>, StoreError> {
+        // Remove the changes and update the histories in a single transaction so they can't diverge
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        let Some(changes): Option<Vec<AccountStateChange>> = txn
+            .get::<StateChanges>(block_number)
+            .map_err(StoreError::LibmdbxError)?
+            .map(|changes| changes.to())
+        else {
+            return Ok(None);
+        };
+        for change in changes.iter() {
+            let mut history: Vec<BlockNumber> = txn
+                .get::<AccountHistories>(change.address.into())
+                .map_err(StoreError::LibmdbxError)?
+                .map(|history| history.to())
+                .unwrap_or_default();
+            history.retain(|number| *number != block_number);
+            txn.upsert::<AccountHistories>(change.address.into(), history.into())
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.delete::<StateChanges>(block_number, None)
+            .map_err(StoreError::LibmdbxError)?;
+        match block_number.checked_sub(1) {
+            Some(parent_number) => txn.upsert::<ChainData>(
+                ChainDataIndex::StateBlockNumber,
+                parent_number.encode_to_vec(),
+            ),
+            None => txn
+                .delete::<ChainData>(ChainDataIndex::StateBlockNumber, None)
+                .map(|_| ()),
+        }
+        .map_err(StoreError::LibmdbxError)?;
+        txn.commit().map_err(StoreError::LibmdbxError)?;
+        Ok(Some(changes))
+    }
+
+    fn get_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::StateBlockNumber)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn get_state_changes(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<AccountStateChange>>, StoreError> {
+        Ok(self.read::<StateChanges>(block_number)?.map(|c| c.to()))
+    }
+
+    fn get_account_history(&self, address: Address) -> Result<Vec<BlockNumber>, StoreError> {
+        Ok(self
+            .read::<AccountHistories>(address.into())?
+            .map(|h| h.to())
+            .unwrap_or_default())
+    }
 }
 
 impl Debug for Store {
@@ -395,6 +506,16 @@ table!(
     ( ChainData ) ChainDataIndex => Vec<u8>
 );
 
+table!(
+    /// Previous state of the accounts modified by each block
+    ( StateChanges ) BlockNumber => StateChangesRLP
+);
+
+table!(
+    /// Numbers of the blocks that modified each account, in ascending order
+    ( AccountHistories ) AddressRLP => AccountHistoryRLP
+);
+
 // Storage values are stored as bytes instead of using their rlp encoding
 // As they are stored in a dupsort table, they need to have a fixed size, and encoding them doesn't preserve their size
 pub struct AccountStorageKeyBytes(pub [u8; 32]);
@@ -464,6 +585,10 @@ pub enum ChainDataIndex {
     LatestBlockNumber = 4,
     PendingBlockNumber = 5,
     CancunTime = 6,
+    // Stored as json as it contains optional fields
+    ChainConfig = 7,
+    // Block whose resulting state is the current state
+    StateBlockNumber = 8,
 }
 
 impl Encodable for ChainDataIndex {
@@ -487,6 +612,8 @@ pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
         table_info!(Receipts),
         table_info!(TransactionLocations),
         table_info!(ChainData),
+        table_info!(StateChanges),
+        table_info!(AccountHistories),
     ]
     .into_iter()
     .collect();
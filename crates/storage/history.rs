@@ -0,0 +1,103 @@
+use ethereum_rust_core::{
+    rlp::{
+        decode::RLPDecode,
+        encode::RLPEncode,
+        error::RLPDecodeError,
+        structs::{Decoder, Encoder},
+    },
+    types::AccountInfo,
+};
+use ethereum_types::{Address, H256, U256};
+
+/// State an account had before a block modified it
+/// Reverting the changes of every block after a given one yields the state as of that block
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountStateChange {
+    pub address: Address,
+    /// Info of the account before the block, None if it didn't exist
+    pub previous_info: Option<AccountInfo>,
+    /// Storage slots modified by the block along with their values before it
+    pub previous_storage: Vec<(H256, U256)>,
+    /// Storage slots created by the block, which didn't exist before it
+    pub created_storage: Vec<H256>,
+}
+
+impl AccountStateChange {
+    /// Returns the value the storage slot had before the block, if the block modified it
+    /// The inner option is None if the slot didn't exist before the block
+    pub fn previous_storage_at(&self, storage_key: H256) -> Option<Option<U256>> {
+        if self.previous_info.is_none() || self.created_storage.contains(&storage_key) {
+            return Some(None);
+        }
+        self.previous_storage
+            .iter()
+            .find(|(key, _)| *key == storage_key)
+            .map(|(_, value)| Some(*value))
+    }
+}
+
+impl RLPEncode for AccountStateChange {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.address)
+            .encode_field(&self.previous_info.is_some())
+            .encode_field(&self.previous_info.clone().unwrap_or_default())
+            .encode_field(&self.previous_storage)
+            .encode_field(&self.created_storage)
+            .finish();
+    }
+}
+
+impl RLPDecode for AccountStateChange {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(AccountStateChange, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (address, decoder) = decoder.decode_field("address")?;
+        let (existed, decoder) = decoder.decode_field("existed")?;
+        let (previous_info, decoder): (AccountInfo, _) = decoder.decode_field("previous_info")?;
+        let (previous_storage, decoder) = decoder.decode_field("previous_storage")?;
+        let (created_storage, decoder) = decoder.decode_field("created_storage")?;
+        let change = AccountStateChange {
+            address,
+            previous_info: existed.then_some(previous_info),
+            previous_storage,
+            created_storage,
+        };
+        Ok((change, decoder.finish()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_state_change() {
+        let change = AccountStateChange {
+            address: Address::repeat_byte(1),
+            previous_info: Some(AccountInfo {
+                nonce: 3,
+                ..Default::default()
+            }),
+            previous_storage: vec![(H256::repeat_byte(2), U256::from(5))],
+            created_storage: vec![H256::repeat_byte(3)],
+        };
+        assert_eq!(
+            AccountStateChange::decode(&change.encode_to_vec()).unwrap(),
+            change
+        );
+        assert_eq!(
+            change.previous_storage_at(H256::repeat_byte(2)),
+            Some(Some(U256::from(5)))
+        );
+        assert_eq!(change.previous_storage_at(H256::repeat_byte(3)), Some(None));
+        assert_eq!(change.previous_storage_at(H256::zero()), None);
+        let created = AccountStateChange {
+            previous_info: None,
+            ..change
+        };
+        assert_eq!(
+            AccountStateChange::decode(&created.encode_to_vec()).unwrap(),
+            created
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+};
+
+use ethereum_rust_core::types::Transaction;
+use ethereum_types::{Address, H256};
+
+/// Maximum amount of transactions kept in the pool
+const MAX_MEMPOOL_SIZE: usize = 4096;
+
+#[derive(Debug, Clone)]
+struct MempoolTransaction {
+    hash: H256,
+    transaction: Transaction,
+}
+
+/// Transactions waiting to be included in a block, grouped by sender and ordered by nonce
+/// Pending transactions have consecutive nonces starting from their sender's account nonce and
+/// can be executed right away, while queued transactions are waiting for a nonce gap to be filled
+#[derive(Debug, Default)]
+pub struct Mempool {
+    pending: HashMap<Address, BTreeMap<u64, MempoolTransaction>>,
+    queued: HashMap<Address, BTreeMap<u64, MempoolTransaction>>,
+    by_hash: HashMap<H256, (Address, u64)>,
+}
+
+impl Mempool {
+    /// Adds a transaction to the pool, replacing the one from the same sender with the same nonce if present
+    /// If the pool is full the cheapest transaction is evicted, which may be the one just added
+    /// Returns whether the transaction was kept in the pool
+    pub fn insert(
+        &mut self,
+        hash: H256,
+        sender: Address,
+        transaction: Transaction,
+        account_nonce: u64,
+    ) -> bool {
+        let nonce = transaction.nonce();
+        if let Some(replaced) = self.remove_by_nonce(sender, nonce) {
+            self.by_hash.remove(&replaced.hash);
+        }
+        self.by_hash.insert(hash, (sender, nonce));
+        self.queued
+            .entry(sender)
+            .or_default()
+            .insert(nonce, MempoolTransaction { hash, transaction });
+        self.reorganize(sender, account_nonce);
+        while self.by_hash.len() > MAX_MEMPOOL_SIZE {
+            self.evict();
+        }
+        self.by_hash.contains_key(&hash)
+    }
+
+    pub fn get(&self, hash: H256) -> Option<&Transaction> {
+        let (sender, nonce) = self.by_hash.get(&hash)?;
+        self.get_by_nonce(*sender, *nonce)
+    }
+
+    pub fn get_by_nonce(&self, sender: Address, nonce: u64) -> Option<&Transaction> {
+        self.pending
+            .get(&sender)
+            .and_then(|txs| txs.get(&nonce))
+            .or_else(|| self.queued.get(&sender).and_then(|txs| txs.get(&nonce)))
+            .map(|tx| &tx.transaction)
+    }
+
+    pub fn hashes(&self) -> impl Iterator<Item = &H256> {
+        self.by_hash.keys()
+    }
+
+    /// Removes the transactions with the given senders and nonces after they were included in a block,
+    /// along with any other transaction from the same senders that can no longer be included, and
+    /// promotes the queued transactions that became executable
+    pub fn remove_mined(&mut self, mined: &[(Address, u64)]) {
+        let mut account_nonces = HashMap::new();
+        for (sender, nonce) in mined {
+            let next_nonce = nonce.saturating_add(1);
+            let account_nonce = account_nonces.entry(*sender).or_insert(next_nonce);
+            *account_nonce = (*account_nonce).max(next_nonce);
+        }
+        for (sender, account_nonce) in account_nonces {
+            self.reorganize(sender, account_nonce);
+        }
+    }
+
+    /// Returns the pending transactions that can pay the given base fee, highest tip first
+    /// Transactions from the same sender are always returned in nonce order
+    pub fn best_pending(&self, base_fee: Option<u64>) -> Vec<Transaction> {
+        let mut senders: HashMap<Address, _> = self
+            .pending
+            .iter()
+            .map(|(sender, txs)| (*sender, txs.values().peekable()))
+            .collect();
+        // Heap of the next transaction of each sender, ties are broken by hash to keep the order stable
+        let mut heads = BinaryHeap::new();
+        for (sender, txs) in senders.iter_mut() {
+            if let Some((tip, hash)) = txs.peek().and_then(|tx| ranking(tx, base_fee)) {
+                heads.push((tip, hash, *sender));
+            }
+        }
+        let mut best = Vec::new();
+        while let Some((_, _, sender)) = heads.pop() {
+            let Some(txs) = senders.get_mut(&sender) else {
+                continue;
+            };
+            if let Some(tx) = txs.next() {
+                best.push(tx.transaction.clone());
+            }
+            // A transaction that can't pay the base fee blocks the ones after it
+            if let Some((tip, hash)) = txs.peek().and_then(|tx| ranking(tx, base_fee)) {
+                heads.push((tip, hash, sender));
+            }
+        }
+        best
+    }
+
+    /// Rearranges the transactions of a sender given its account nonce: transactions with lower nonces
+    /// are dropped, transactions with consecutive nonces starting from it are pending and the rest are queued
+    fn reorganize(&mut self, sender: Address, account_nonce: u64) {
+        let mut txs = self.pending.remove(&sender).unwrap_or_default();
+        txs.append(&mut self.queued.remove(&sender).unwrap_or_default());
+        let mut queued = txs.split_off(&account_nonce);
+        for stale in txs.into_values() {
+            self.by_hash.remove(&stale.hash);
+        }
+        let mut pending = BTreeMap::new();
+        let mut next_nonce = account_nonce;
+        while let Some(tx) = queued.remove(&next_nonce) {
+            pending.insert(next_nonce, tx);
+            next_nonce += 1;
+        }
+        if !pending.is_empty() {
+            self.pending.insert(sender, pending);
+        }
+        if !queued.is_empty() {
+            self.queued.insert(sender, queued);
+        }
+    }
+
+    fn remove_by_nonce(&mut self, sender: Address, nonce: u64) -> Option<MempoolTransaction> {
+        for pool in [&mut self.pending, &mut self.queued] {
+            if let Some(txs) = pool.get_mut(&sender) {
+                if let Some(tx) = txs.remove(&nonce) {
+                    if txs.is_empty() {
+                        pool.remove(&sender);
+                    }
+                    return Some(tx);
+                }
+            }
+        }
+        None
+    }
+
+    /// Evicts the queued transaction with the lowest fee cap, or if there are none, the pending
+    /// transaction with the lowest fee cap among the last ones of each sender
+    fn evict(&mut self) {
+        let queued = self.queued.iter().flat_map(|(sender, txs)| {
+            txs.iter()
+                .map(move |(nonce, tx)| (tx.transaction.gas_price(), *sender, *nonce))
+        });
+        let victim = match queued.min() {
+            Some(victim) => Some(victim),
+            None => self
+                .pending
+                .iter()
+                .filter_map(|(sender, txs)| {
+                    let (nonce, tx) = txs.last_key_value()?;
+                    Some((tx.transaction.gas_price(), *sender, *nonce))
+                })
+                .min(),
+        };
+        // Neither kind of removal creates a nonce gap among pending transactions
+        if let Some((_, sender, nonce)) = victim {
+            if let Some(evicted) = self.remove_by_nonce(sender, nonce) {
+                self.by_hash.remove(&evicted.hash);
+            }
+        }
+    }
+}
+
+/// Transactions are ranked by their tip, or not at all if they can't pay the base fee
+fn ranking(tx: &MempoolTransaction, base_fee: Option<u64>) -> Option<(u64, Reverse<H256>)> {
+    Some((
+        tx.transaction.effective_gas_tip(base_fee)?,
+        Reverse(tx.hash),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use ethereum_rust_core::types::{EIP1559Transaction, TxKind};
+    use ethereum_types::U256;
+
+    use super::*;
+
+    fn transaction(nonce: u64, max_fee_per_gas: u64, max_priority_fee_per_gas: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            chain_id: 1,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::repeat_byte(0xaa)),
+            value: U256::zero(),
+            data: Bytes::new(),
+            access_list: Vec::new(),
+            signature_y_parity: false,
+            signature_r: U256::zero(),
+            signature_s: U256::zero(),
+        })
+    }
+
+    fn hash(sender: Address, nonce: u64, fee: u64) -> H256 {
+        H256::from_low_u64_be(sender.to_low_u64_be() << 32 | nonce << 16 | fee)
+    }
+
+    fn insert(mempool: &mut Mempool, sender: Address, nonce: u64, fee: u64, account_nonce: u64) {
+        mempool.insert(
+            hash(sender, nonce, fee),
+            sender,
+            transaction(nonce, fee, fee),
+            account_nonce,
+        );
+    }
+
+    #[test]
+    fn transactions_are_promoted_when_nonce_gap_is_filled() {
+        let mut mempool = Mempool::default();
+        let sender = Address::from_low_u64_be(1);
+        insert(&mut mempool, sender, 1, 10, 0);
+        insert(&mut mempool, sender, 2, 10, 0);
+        assert!(mempool.best_pending(None).is_empty());
+        insert(&mut mempool, sender, 0, 10, 0);
+        let nonces: Vec<u64> = mempool
+            .best_pending(None)
+            .iter()
+            .map(|tx| tx.nonce())
+            .collect();
+        assert_eq!(nonces, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn transaction_with_same_nonce_is_replaced() {
+        let mut mempool = Mempool::default();
+        let sender = Address::from_low_u64_be(1);
+        insert(&mut mempool, sender, 0, 10, 0);
+        insert(&mut mempool, sender, 0, 20, 0);
+        assert_eq!(mempool.by_hash.len(), 1);
+        assert!(mempool.get(hash(sender, 0, 10)).is_none());
+        assert_eq!(
+            mempool.get_by_nonce(sender, 0).map(|tx| tx.gas_price()),
+            Some(20)
+        );
+    }
+
+    #[test]
+    fn mined_transactions_are_removed() {
+        let mut mempool = Mempool::default();
+        let sender = Address::from_low_u64_be(1);
+        insert(&mut mempool, sender, 0, 10, 0);
+        insert(&mut mempool, sender, 1, 10, 0);
+        insert(&mut mempool, sender, 2, 10, 0);
+        insert(&mut mempool, sender, 4, 10, 0);
+        // Transactions up to the mined nonce are dropped and the nonce gap keeps the last one queued
+        mempool.remove_mined(&[(sender, 1)]);
+        assert_eq!(mempool.by_hash.len(), 2);
+        assert!(mempool.get_by_nonce(sender, 0).is_none());
+        let nonces: Vec<u64> = mempool
+            .best_pending(None)
+            .iter()
+            .map(|tx| tx.nonce())
+            .collect();
+        assert_eq!(nonces, vec![2]);
+        // Mining the missing nonce promotes the queued transaction
+        mempool.remove_mined(&[(sender, 3)]);
+        let nonces: Vec<u64> = mempool
+            .best_pending(None)
+            .iter()
+            .map(|tx| tx.nonce())
+            .collect();
+        assert_eq!(nonces, vec![4]);
+    }
+
+    #[test]
+    fn best_pending_orders_by_tip_and_nonce() {
+        let mut mempool = Mempool::default();
+        let (low, high) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        insert(&mut mempool, low, 0, 20, 0);
+        insert(&mut mempool, low, 1, 50, 0);
+        insert(&mut mempool, high, 0, 30, 0);
+        insert(&mut mempool, high, 1, 5, 0);
+        let best: Vec<u64> = mempool
+            .best_pending(Some(10))
+            .iter()
+            .map(|tx| tx.gas_price())
+            .collect();
+        // The cheapest transaction can't pay the base fee
+        assert_eq!(best, vec![30, 20, 50]);
+    }
+
+    #[test]
+    fn cheapest_transactions_are_evicted_when_full() {
+        let mut mempool = Mempool::default();
+        for i in 0..MAX_MEMPOOL_SIZE as u64 {
+            insert(&mut mempool, Address::from_low_u64_be(i + 1), 0, 10, 0);
+        }
+        let sender = Address::from_low_u64_be(MAX_MEMPOOL_SIZE as u64 + 1);
+        // Queued transactions are evicted before pending ones
+        insert(&mut mempool, sender, 1, 100, 0);
+        assert_eq!(mempool.by_hash.len(), MAX_MEMPOOL_SIZE);
+        assert!(mempool.get_by_nonce(sender, 1).is_none());
+        // The pending transaction with the lowest fee cap is evicted
+        insert(&mut mempool, sender, 0, 100, 0);
+        assert_eq!(mempool.by_hash.len(), MAX_MEMPOOL_SIZE);
+        assert!(mempool.get_by_nonce(sender, 0).is_some());
+    }
+}
@@ -0,0 +1,321 @@
+use bytes::Bytes;
+use ethereum_rust_core::{
+    rlp::{
+        constants::{RLP_EMPTY_LIST, RLP_NULL},
+        encode::RLPEncode,
+    },
+    types::AccountState,
+};
+use ethereum_types::{H256, U256};
+use sha3::{Digest as _, Keccak256};
+
+/// State of an account along with the proof of its inclusion (or absence) in the state trie,
+/// and the proofs of some of its storage slots, as defined in EIP-1186
+#[derive(Debug)]
+pub struct AccountProof {
+    pub state: AccountState,
+    pub proof: Vec<Bytes>,
+    pub storage_proofs: Vec<StorageProof>,
+}
+
+#[derive(Debug)]
+pub struct StorageProof {
+    pub key: H256,
+    pub value: U256,
+    pub proof: Vec<Bytes>,
+}
+
+/// Merkle Patricia Trie built in memory from all of its entries, used to generate inclusion and exclusion proofs
+/// Nodes are encoded as described in the yellow paper (appendix D), so its root matches the one computed with `PatriciaMerkleTree`
+pub struct ProofTrie {
+    // Entries as (key nibbles, encoded value), sorted by key
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl ProofTrie {
+    /// Builds the trie from already hashed keys and their encoded values
+    pub fn new(entries: impl IntoIterator<Item = (H256, Vec<u8>)>) -> Self {
+        let mut entries: Vec<(Vec<u8>, Vec<u8>)> = entries
+            .into_iter()
+            .map(|(key, value)| (nibbles(&key), value))
+            .collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Self { entries }
+    }
+
+    pub fn root(&self) -> H256 {
+        keccak(&encode_node(&self.entries, 0, None, &mut Vec::new()))
+    }
+
+    /// Returns the encoded nodes on the path to the given (hashed) key, starting from the root
+    /// If the key is not in the trie, the nodes prove its absence
+    /// Nodes that are embedded in their parent because their encoding is shorter than 32 bytes are not included
+    pub fn proof(&self, key: H256) -> Vec<Bytes> {
+        let mut path_nodes = Vec::new();
+        encode_node(&self.entries, 0, Some(&nibbles(&key)), &mut path_nodes);
+        // Nodes are added from the bottom up
+        path_nodes
+            .into_iter()
+            .rev()
+            .enumerate()
+            .filter(|(depth, node)| *depth == 0 || node.len() >= 32)
+            .map(|(_, node)| Bytes::from(node))
+            .collect()
+    }
+}
+
+fn keccak(data: &[u8]) -> H256 {
+    H256(Keccak256::digest(data).into())
+}
+
+fn nibbles(key: &H256) -> Vec<u8> {
+    key.as_bytes()
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Encodes the node holding the given entries, whose keys share their first `depth` nibbles
+/// Nodes on the path to `target` are pushed to `path_nodes`, children before their parents
+fn encode_node(
+    entries: &[(Vec<u8>, Vec<u8>)],
+    depth: usize,
+    target: Option<&[u8]>,
+    path_nodes: &mut Vec<Vec<u8>>,
+) -> Vec<u8> {
+    let encoded = match entries {
+        [] => vec![RLP_NULL],
+        // Leaf node
+        [(path, value)] => encode_list(&[
+            hex_prefix(&path[depth..], true).encode_to_vec(),
+            value.as_slice().encode_to_vec(),
+        ]),
+        [(first, _), .., (last, _)] => {
+            // As entries are sorted, the prefix shared by all of them is the one shared by the first and last
+            let shared = first[depth..]
+                .iter()
+                .zip(&last[depth..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if shared > 0 {
+                // Extension node
+                let prefix = &first[depth..depth + shared];
+                let child_target = target.filter(|target| target[depth..].starts_with(prefix));
+                let child = encode_node(entries, depth + shared, child_target, path_nodes);
+                encode_list(&[
+                    hex_prefix(prefix, false).encode_to_vec(),
+                    node_reference(child),
+                ])
+            } else {
+                // Branch node, values are always stored in leaves as all keys have the same length
+                let mut items = Vec::with_capacity(17);
+                let mut remaining = entries;
+                for nibble in 0..16 {
+                    let count = remaining
+                        .iter()
+                        .take_while(|(path, _)| path[depth] == nibble)
+                        .count();
+                    let (children, rest) = remaining.split_at(count);
+                    remaining = rest;
+                    if children.is_empty() {
+                        items.push(vec![RLP_NULL]);
+                        continue;
+                    }
+                    let child_target = target.filter(|target| target[depth] == nibble);
+                    let child = encode_node(children, depth + 1, child_target, path_nodes);
+                    items.push(node_reference(child));
+                }
+                items.push(vec![RLP_NULL]);
+                encode_list(&items)
+            }
+        }
+    };
+    if target.is_some() {
+        path_nodes.push(encoded.clone());
+    }
+    encoded
+}
+
+/// Nodes shorter than 32 bytes are embedded in their parent, otherwise they are referenced by their hash
+fn node_reference(encoded: Vec<u8>) -> Vec<u8> {
+    if encoded.len() < 32 {
+        encoded
+    } else {
+        keccak(&encoded).encode_to_vec()
+    }
+}
+
+/// Compact encoding of a path, flagging whether it belongs to a leaf and if it has an odd amount of nibbles
+fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let (first, rest) = if path.len() % 2 == 1 {
+        ((flag + 1) << 4 | path[0], &path[1..])
+    } else {
+        (flag << 4, path)
+    };
+    std::iter::once(first)
+        .chain(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]))
+        .collect()
+}
+
+/// Encodes a list whose items are already encoded
+fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_len: usize = items.iter().map(Vec::len).sum();
+    let mut encoded = Vec::with_capacity(payload_len + 9);
+    if payload_len < 56 {
+        encoded.push(RLP_EMPTY_LIST + payload_len as u8);
+    } else {
+        let len_bytes = payload_len.to_be_bytes();
+        let len_bytes = &len_bytes[len_bytes.iter().take_while(|byte| **byte == 0).count()..];
+        encoded.push(RLP_EMPTY_LIST + 55 + len_bytes.len() as u8);
+        encoded.extend_from_slice(len_bytes);
+    }
+    for item in items {
+        encoded.extend_from_slice(item);
+    }
+    encoded
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use ethereum_rust_core::{rlp::decode::RLPDecode, types::compute_storage_root};
+
+    use super::*;
+
+    /// Follows the proof from the root, returning the value stored for the key if it is in the trie
+    /// Panics if the proof is not valid
+    pub(crate) fn verify_proof(root: H256, key: H256, proof: &[Bytes]) -> Option<Vec<u8>> {
+        let path = nibbles(&key);
+        let mut depth = 0;
+        let mut expected_hash = root;
+        let mut nodes = proof.iter();
+        let mut node = nodes.next().unwrap().to_vec();
+        assert_eq!(keccak(&node), expected_hash);
+        loop {
+            let items = decode_list(&node);
+            let child = match items.len() {
+                17 => {
+                    let child = items[path[depth] as usize].clone();
+                    depth += 1;
+                    child
+                }
+                2 => {
+                    let encoded_path = decode_bytes(&items[0]);
+                    let is_leaf = encoded_path[0] >> 4 >= 2;
+                    let mut node_path = nibbles_of(&encoded_path);
+                    // Remove the flag nibble, and the padding one for even paths
+                    node_path.drain(..if encoded_path[0] >> 4 & 1 == 1 { 1 } else { 2 });
+                    if !path[depth..].starts_with(&node_path) {
+                        return None;
+                    }
+                    depth += node_path.len();
+                    if is_leaf {
+                        return Some(decode_bytes(&items[1]));
+                    }
+                    items[1].clone()
+                }
+                _ => panic!("Invalid node"),
+            };
+            node = match child.as_slice() {
+                [RLP_NULL] => return None,
+                // Hash reference
+                [0xa0, hash @ ..] if hash.len() == 32 => {
+                    expected_hash = H256::from_slice(hash);
+                    let node = nodes.next().unwrap().to_vec();
+                    assert_eq!(keccak(&node), expected_hash);
+                    node
+                }
+                embedded => embedded.to_vec(),
+            };
+        }
+    }
+
+    fn nibbles_of(bytes: &[u8]) -> Vec<u8> {
+        bytes
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0x0f])
+            .collect()
+    }
+
+    fn decode_bytes(encoded: &[u8]) -> Vec<u8> {
+        <Bytes as RLPDecode>::decode(encoded).unwrap().to_vec()
+    }
+
+    // Splits an encoded list into its encoded items
+    fn decode_list(encoded: &[u8]) -> Vec<Vec<u8>> {
+        let item_len = |data: &[u8]| -> (usize, usize) {
+            match data[0] {
+                0..=0x7f => (0, 1),
+                prefix @ 0x80..=0xb7 => (1, (prefix - 0x80) as usize),
+                prefix @ 0xc0..=0xf7 => (1, (prefix - 0xc0) as usize),
+                prefix => {
+                    let len_of_len = (prefix - if prefix >= 0xf8 { 0xf7 } else { 0xb7 }) as usize;
+                    let len = data[1..=len_of_len]
+                        .iter()
+                        .fold(0, |len, byte| len << 8 | *byte as usize);
+                    (1 + len_of_len, len)
+                }
+            }
+        };
+        let (header, len) = item_len(encoded);
+        let mut payload = &encoded[header..header + len];
+        let mut items = Vec::new();
+        while !payload.is_empty() {
+            let (header, len) = item_len(payload);
+            let (item, rest) = payload.split_at(header + len);
+            items.push(item.to_vec());
+            payload = rest;
+        }
+        items
+    }
+
+    fn storage_trie(storage: &HashMap<H256, U256>) -> ProofTrie {
+        ProofTrie::new(
+            storage
+                .iter()
+                .map(|(key, value)| (keccak(key.as_bytes()), value.encode_to_vec())),
+        )
+    }
+
+    #[test]
+    fn root_matches_patricia_merkle_tree() {
+        for size in [0, 1, 2, 17, 300] {
+            let storage: HashMap<H256, U256> = (0..size)
+                .map(|i| (H256::from_low_u64_be(i), U256::from(i + 1)))
+                .collect();
+            assert_eq!(
+                storage_trie(&storage).root(),
+                compute_storage_root(&storage)
+            );
+        }
+    }
+
+    #[test]
+    fn proofs_verify_against_root() {
+        let storage: HashMap<H256, U256> = (0..300)
+            .map(|i| (H256::from_low_u64_be(i), U256::from(i) << 200))
+            .collect();
+        let trie = storage_trie(&storage);
+        let root = trie.root();
+        for (key, value) in storage.iter().take(20) {
+            let hashed_key = keccak(key.as_bytes());
+            assert_eq!(
+                verify_proof(root, hashed_key, &trie.proof(hashed_key)),
+                Some(value.encode_to_vec())
+            );
+        }
+        // Absent keys
+        for i in 300..320 {
+            let hashed_key = keccak(H256::from_low_u64_be(i).as_bytes());
+            assert_eq!(
+                verify_proof(root, hashed_key, &trie.proof(hashed_key)),
+                None
+            );
+        }
+        // A single entry trie only has its root
+        let single = storage_trie(&HashMap::from([(H256::zero(), U256::one())]));
+        assert_eq!(single.proof(H256::zero()).len(), 1);
+    }
+}
@@ -3,9 +3,12 @@ use std::marker::PhantomData;
 use bytes::Bytes;
 use ethereum_rust_core::{
     rlp::{decode::RLPDecode, encode::RLPEncode},
-    types::{AccountInfo, BlockBody, BlockHash, BlockHeader, Receipt},
+    types::{AccountInfo, BlockBody, BlockHash, BlockHeader, BlockNumber, Receipt},
     Address, H256,
 };
+
+use crate::history::AccountStateChange;
+
 #[cfg(feature = "libmdbx")]
 use libmdbx::orm::{Decodable, Encodable};
 
@@ -26,6 +29,10 @@ pub type ReceiptRLP = Rlp<Receipt>;
 // Transaction types
 pub type TransactionHashRLP = Rlp<H256>;
 
+// State history types
+pub type StateChangesRLP = Rlp<Vec<AccountStateChange>>;
+pub type AccountHistoryRLP = Rlp<Vec<BlockNumber>>;
+
 #[derive(Clone)]
 pub struct Rlp<T>(Vec<u8>, PhantomData<T>);
 
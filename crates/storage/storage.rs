@@ -3,6 +3,7 @@ use self::engines::in_memory::Store as InMemoryStore;
 #[cfg(feature = "libmdbx")]
 use self::engines::libmdbx::Store as LibmdbxStore;
 use self::error::StoreError;
+use bounded_map::BoundedMap;
 use bytes::Bytes;
 use engines::api::StoreEngine;
 use ethereum_rust_core::rlp::encode::RLPEncode;
@@ -11,23 +12,57 @@ use ethereum_rust_core::types::{
     ChainConfig, Genesis, Index, Receipt, Transaction,
 };
 use ethereum_types::{Address, H256, U256};
+use mempool::Mempool;
 use patricia_merkle_tree::PatriciaMerkleTree;
+use proof::ProofTrie;
 use sha3::{Digest as _, Keccak256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
 use tracing::info;
 
+pub use history::AccountStateChange;
+pub use proof::{AccountProof, StorageProof};
+
+mod bounded_map;
 mod engines;
 pub mod error;
+mod history;
+mod mempool;
+mod proof;
 mod rlp;
 
+/// Maximum amount of built payloads kept track of
+const MAX_PAYLOADS: usize = 10;
+/// Maximum amount of invalid blocks kept track of
+const MAX_INVALID_ANCESTORS: usize = 512;
+
 #[derive(Debug, Clone)]
 pub struct Store {
     engine: Arc<Mutex<dyn StoreEngine>>,
+    // Blocks that failed validation mapped to the latest valid block of their chain, kept in memory only
+    invalid_ancestors: Arc<Mutex<BoundedMap<BlockHash, BlockHash>>>,
+    // Transactions waiting to be included in a block, kept in memory only
+    mempool: Arc<Mutex<Mempool>>,
+    // Payloads built by the node, kept in memory only
+    payloads: Arc<Mutex<BoundedMap<u64, Block>>>,
     //world_state:  PatriciaMerkleTree<Vec<u8>, Vec<u8>, Keccak256>,
 }
 
+/// Changes to an account produced by executing a block or a set of transactions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountUpdate {
+    pub address: Address,
+    /// The account was destroyed, its previous info and storage are no longer valid
+    pub removed: bool,
+    /// New account info, if it changed
+    pub info: Option<AccountInfo>,
+    /// New account code, if it changed
+    pub code: Option<Bytes>,
+    /// Storage slots that changed along with their new values
+    pub added_storage: HashMap<H256, U256>,
+}
+
 #[allow(dead_code)]
 #[derive(Debug)]
 pub enum EngineType {
@@ -44,12 +79,18 @@ impl Store {
             #[cfg(feature = "libmdbx")]
             EngineType::Libmdbx => Self {
                 engine: Arc::new(Mutex::new(LibmdbxStore::new(path)?)),
+                invalid_ancestors: Arc::new(Mutex::new(BoundedMap::new(MAX_INVALID_ANCESTORS))),
+                mempool: Default::default(),
+                payloads: Arc::new(Mutex::new(BoundedMap::new(MAX_PAYLOADS))),
                 // TODO: build from DB
                 //world_state: PatriciaMerkleTree::default(),
             },
             #[cfg(feature = "in_memory")]
             EngineType::InMemory => Self {
                 engine: Arc::new(Mutex::new(InMemoryStore::new()?)),
+                invalid_ancestors: Arc::new(Mutex::new(BoundedMap::new(MAX_INVALID_ANCESTORS))),
+                mempool: Default::default(),
+                payloads: Arc::new(Mutex::new(BoundedMap::new(MAX_PAYLOADS))),
                 //world_state: PatriciaMerkleTree::default(),
             },
         };
@@ -247,7 +288,11 @@ impl Store {
         let genesis_block = genesis.get_block();
 
         // Store genesis block
+        let genesis_number = genesis_block.header.number;
+        self.add_block_number(genesis_block.header.compute_block_hash(), genesis_number)?;
         self.add_block(genesis_block)?;
+        self.update_earliest_block_number(genesis_number)?;
+        self.update_latest_block_number(genesis_number)?;
 
         // Store each alloc account
         for (address, account) in genesis.alloc.into_iter() {
@@ -323,6 +368,10 @@ impl Store {
         self.engine.lock().unwrap().set_chain_config(chain_config)
     }
 
+    pub fn get_chain_config(&self) -> Result<Option<ChainConfig>, StoreError> {
+        self.engine.lock().unwrap().get_chain_config()
+    }
+
     pub fn get_chain_id(&self) -> Result<Option<U256>, StoreError> {
         self.engine.lock().unwrap().get_chain_id()
     }
@@ -398,8 +447,14 @@ impl Store {
     ///     Version 2: Keeps trie in memory
     ///     Version 3: Persists trie in db
     pub fn world_state_root(&mut self) -> H256 {
+        self.world_state_root_with_updates(&[])
+    }
+
+    /// Returns the root hash of the merkle tree resulting from applying the given account updates
+    /// to the current state, without persisting them
+    pub fn world_state_root_with_updates(&self, account_updates: &[AccountUpdate]) -> H256 {
         // build trie from state
-        let mut trie = self.build_trie_from_state();
+        let mut trie = self.build_trie_from_state(account_updates);
 
         // compute hash from in memory world_state trie
         //let &root = self.world_state.compute_hash();
@@ -408,25 +463,426 @@ impl Store {
         H256(root.into())
     }
 
-    fn build_trie_from_state(&self) -> PatriciaMerkleTree<Vec<u8>, Vec<u8>, Keccak256> {
-        let mut trie = PatriciaMerkleTree::<Vec<u8>, Vec<u8>, Keccak256>::new();
+    fn build_trie_from_state(
+        &self,
+        account_updates: &[AccountUpdate],
+    ) -> PatriciaMerkleTree<Vec<u8>, Vec<u8>, Keccak256> {
+        let mut accounts: HashMap<Address, (AccountInfo, HashMap<H256, U256>)> = HashMap::new();
         for (address, account) in self.account_infos_iter().unwrap() {
-            // Key: Keccak(address)
-            let k = Keccak256::new_with_prefix(address.to_fixed_bytes())
-                .finalize()
-                .to_vec();
-
             let storage: HashMap<H256, U256> = self
                 .account_storage_iter(address)
                 .unwrap_or_else(|_| panic!("Failed to retrieve storage for {address}"))
                 .collect();
-            // Value: account
-            let mut v = Vec::new();
-            AccountState::from_info_and_storage(&account, &storage).encode(&mut v);
-            trie.insert(k, v);
+            accounts.insert(address, (account, storage));
         }
-        trie
+        build_trie(accounts, account_updates)
     }
+
+    /// Returns the root hash of the merkle tree resulting from applying the given account updates
+    /// to the state right after the given block was executed, without persisting them
+    pub fn world_state_root_at_block_with_updates(
+        &self,
+        block_number: BlockNumber,
+        account_updates: &[AccountUpdate],
+    ) -> Result<H256, StoreError> {
+        let accounts = self.state_at_block(block_number)?;
+        let mut trie = build_trie(accounts, account_updates);
+        let &root = trie.compute_hash();
+        Ok(H256(root.into()))
+    }
+
+    /// Returns the state of the account along with the merkle proofs of it and the given storage slots,
+    /// built against the state right after the given block was executed
+    pub fn get_account_proof(
+        &self,
+        block_number: BlockNumber,
+        address: Address,
+        storage_keys: &[H256],
+    ) -> Result<AccountProof, StoreError> {
+        let hash = |bytes: &[u8]| H256(Keccak256::digest(bytes).into());
+        let mut accounts = self.state_at_block(block_number)?;
+        let proof = ProofTrie::new(accounts.iter().map(|(account_address, (info, storage))| {
+            let state = AccountState::from_info_and_storage(info, storage);
+            (hash(account_address.as_bytes()), state.encode_to_vec())
+        }))
+        .proof(hash(address.as_bytes()));
+
+        let (info, storage) = accounts.remove(&address).unwrap_or_default();
+        let storage_trie = ProofTrie::new(
+            storage
+                .iter()
+                .map(|(key, value)| (hash(key.as_bytes()), value.encode_to_vec())),
+        );
+        let storage_proofs = storage_keys
+            .iter()
+            .map(|key| StorageProof {
+                key: *key,
+                value: storage.get(key).copied().unwrap_or_default(),
+                proof: storage_trie.proof(hash(key.as_bytes())),
+            })
+            .collect();
+        Ok(AccountProof {
+            state: AccountState {
+                nonce: info.nonce,
+                balance: info.balance,
+                storage_root: storage_trie.root(),
+                code_hash: info.code_hash,
+            },
+            proof,
+            storage_proofs,
+        })
+    }
+
+    /// Returns every account along with its storage as they were right after the given block was executed
+    /// Accounts are read from the current state, except for the ones changed by later blocks, whose past
+    /// values are read through their history
+    fn state_at_block(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<HashMap<Address, (AccountInfo, HashMap<H256, U256>)>, StoreError> {
+        // Storage slots changed by later blocks, by account
+        let mut changed_slots: HashMap<Address, HashSet<H256>> = HashMap::new();
+        let state_block_number = self.engine.lock().unwrap().get_state_block_number()?;
+        for number in block_number + 1..=state_block_number.unwrap_or_default() {
+            let changes = self
+                .engine
+                .lock()
+                .unwrap()
+                .get_state_changes(number)?
+                .ok_or_else(|| {
+                    StoreError::Custom(format!("Missing state changes of block {number}"))
+                })?;
+            for change in changes {
+                changed_slots.entry(change.address).or_default().extend(
+                    change
+                        .previous_storage
+                        .iter()
+                        .map(|(key, _)| *key)
+                        .chain(change.created_storage),
+                );
+            }
+        }
+        let mut accounts = HashMap::new();
+        for (address, info) in self.account_infos_iter()? {
+            if changed_slots.contains_key(&address) {
+                continue;
+            }
+            let storage = self.account_storage_iter(address)?.collect();
+            accounts.insert(address, (info, storage));
+        }
+        for (address, keys) in changed_slots {
+            let Some(info) = self.get_account_info_by_block(block_number, address)? else {
+                continue;
+            };
+            let mut storage: HashMap<H256, U256> = self
+                .account_storage_iter(address)?
+                .filter(|(key, _)| !keys.contains(key))
+                .collect();
+            for key in keys {
+                if let Some(value) = self.get_storage_at_by_block(block_number, address, key)? {
+                    storage.insert(key, value);
+                }
+            }
+            accounts.insert(address, (info, storage));
+        }
+        Ok(accounts)
+    }
+
+    /// Returns the account info as it was right after the given block was executed
+    pub fn get_account_info_by_block(
+        &self,
+        block_number: BlockNumber,
+        address: Address,
+    ) -> Result<Option<AccountInfo>, StoreError> {
+        match self.find_later_change(block_number, address, |change| {
+            Some(change.previous_info.clone())
+        })? {
+            Some(info) => Ok(info),
+            None => self.get_account_info(address),
+        }
+    }
+
+    /// Returns the code of the account as it was right after the given block was executed
+    pub fn get_code_by_account_address_by_block(
+        &self,
+        block_number: BlockNumber,
+        address: Address,
+    ) -> Result<Option<Bytes>, StoreError> {
+        // Code is never removed, so it can be found by the hash the account had
+        match self.get_account_info_by_block(block_number, address)? {
+            Some(info) => self.get_account_code(info.code_hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the value of the storage slot as it was right after the given block was executed
+    pub fn get_storage_at_by_block(
+        &self,
+        block_number: BlockNumber,
+        address: Address,
+        storage_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        match self.find_later_change(block_number, address, |change| {
+            change.previous_storage_at(storage_key)
+        })? {
+            Some(value) => Ok(value),
+            None => self.get_storage_at(address, storage_key),
+        }
+    }
+
+    /// Looks through the changes made to the account by the blocks executed after the given one, in order,
+    /// and returns the first value `f` finds in them
+    fn find_later_change<T>(
+        &self,
+        block_number: BlockNumber,
+        address: Address,
+        mut f: impl FnMut(&AccountStateChange) -> Option<T>,
+    ) -> Result<Option<T>, StoreError> {
+        let history = self.engine.lock().unwrap().get_account_history(address)?;
+        let later_blocks = &history[history.partition_point(|number| *number <= block_number)..];
+        for number in later_blocks {
+            let changes = self
+                .engine
+                .lock()
+                .unwrap()
+                .get_state_changes(*number)?
+                .unwrap_or_default();
+            if let Some(value) = changes
+                .iter()
+                .find(|change| change.address == address)
+                .and_then(&mut f)
+            {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the state the account had before the update
+    fn account_state_change(
+        &self,
+        update: &AccountUpdate,
+    ) -> Result<AccountStateChange, StoreError> {
+        // Removed accounts lose all of their storage
+        let mut previous_storage: HashMap<H256, U256> = if update.removed {
+            self.account_storage_iter(update.address)?.collect()
+        } else {
+            HashMap::new()
+        };
+        let mut created_storage = Vec::new();
+        for key in update.added_storage.keys() {
+            if previous_storage.contains_key(key) {
+                continue;
+            }
+            let previous_value = if update.removed {
+                None
+            } else {
+                self.get_storage_at(update.address, *key)?
+            };
+            match previous_value {
+                Some(value) => {
+                    previous_storage.insert(*key, value);
+                }
+                None => created_storage.push(*key),
+            }
+        }
+        Ok(AccountStateChange {
+            address: update.address,
+            previous_info: self.get_account_info(update.address)?,
+            previous_storage: previous_storage.into_iter().collect(),
+            created_storage,
+        })
+    }
+
+    /// Applies the account updates produced by a block to the stored state
+    /// The previous state of the updated accounts is kept so the state as of older blocks can still be read
+    /// If the current state is ahead of the block's parent it is first reverted to it, discarding the state
+    /// of the blocks the new one replaces
+    pub fn apply_account_updates(
+        &self,
+        block_number: BlockNumber,
+        account_updates: &[AccountUpdate],
+    ) -> Result<(), StoreError> {
+        self.revert_state_to(block_number.saturating_sub(1))?;
+        let changes: Vec<AccountStateChange> = account_updates
+            .iter()
+            .map(|update| self.account_state_change(update))
+            .collect::<Result<_, _>>()?;
+        self.engine
+            .lock()
+            .unwrap()
+            .add_state_changes(block_number, changes)?;
+        for update in account_updates {
+            if update.removed {
+                self.remove_account(update.address)?;
+            }
+            if let Some(info) = &update.info {
+                self.add_account_info(update.address, info.clone())?;
+                if let Some(code) = &update.code {
+                    self.add_account_code(info.code_hash, code.clone())?;
+                }
+            }
+            for (key, value) in update.added_storage.iter() {
+                self.add_storage_at(update.address, *key, *value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverts the stored state to the one right after the given block was executed,
+    /// removing the changes of every later block
+    fn revert_state_to(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        let Some(state_block_number) = self.engine.lock().unwrap().get_state_block_number()? else {
+            return Ok(());
+        };
+        for number in (block_number + 1..=state_block_number).rev() {
+            let changes = self
+                .engine
+                .lock()
+                .unwrap()
+                .remove_state_changes(number)?
+                .ok_or_else(|| {
+                    StoreError::Custom(format!("Missing state changes of block {number}"))
+                })?;
+            for change in changes {
+                self.revert_account_change(change)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Restores the state the account had before the change
+    fn revert_account_change(&self, change: AccountStateChange) -> Result<(), StoreError> {
+        let address = change.address;
+        let Some(previous_info) = change.previous_info else {
+            // The account didn't exist before the change
+            return self.remove_account(address);
+        };
+        self.add_account_info(address, previous_info)?;
+        if !change.created_storage.is_empty() {
+            // Slots can only be removed along with the rest of the account's storage
+            let storage: Vec<(H256, U256)> = self
+                .account_storage_iter(address)?
+                .filter(|(key, _)| !change.created_storage.contains(key))
+                .collect();
+            self.engine
+                .lock()
+                .unwrap()
+                .remove_account_storage(address)?;
+            for (key, value) in storage {
+                self.add_storage_at(address, key, value)?;
+            }
+        }
+        for (key, value) in change.previous_storage {
+            self.add_storage_at(address, key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Stores a payload built by the node under the given payload id
+    /// Only the most recently built payloads are kept track of
+    pub fn add_payload(&self, payload_id: u64, block: Block) {
+        self.payloads.lock().unwrap().insert(payload_id, block)
+    }
+
+    pub fn get_payload(&self, payload_id: u64) -> Option<Block> {
+        self.payloads.lock().unwrap().get(&payload_id).cloned()
+    }
+
+    /// Marks a block as invalid, along with the latest valid block of its chain
+    /// Only the most recent invalid blocks are kept track of
+    pub fn add_invalid_ancestor(&self, invalid_block: BlockHash, latest_valid: BlockHash) {
+        self.invalid_ancestors
+            .lock()
+            .unwrap()
+            .insert(invalid_block, latest_valid)
+    }
+
+    /// Returns the latest valid block of the chain of the given block if it was marked as invalid
+    pub fn get_latest_valid_ancestor(&self, block: BlockHash) -> Option<BlockHash> {
+        self.invalid_ancestors.lock().unwrap().get(&block).copied()
+    }
+
+    /// Adds a transaction to the mempool, replacing the one from the same sender with the same nonce if present
+    /// Returns false if the pool is full and the transaction was the cheapest one
+    pub fn add_transaction_to_pool(
+        &self,
+        hash: H256,
+        sender: Address,
+        transaction: Transaction,
+        account_nonce: u64,
+    ) -> bool {
+        self.mempool
+            .lock()
+            .unwrap()
+            .insert(hash, sender, transaction, account_nonce)
+    }
+
+    pub fn get_transaction_from_pool(&self, hash: H256) -> Option<Transaction> {
+        self.mempool.lock().unwrap().get(hash).cloned()
+    }
+
+    pub fn get_transaction_from_pool_by_nonce(
+        &self,
+        sender: Address,
+        nonce: u64,
+    ) -> Option<Transaction> {
+        self.mempool
+            .lock()
+            .unwrap()
+            .get_by_nonce(sender, nonce)
+            .cloned()
+    }
+
+    pub fn get_pool_transaction_hashes(&self) -> Vec<H256> {
+        self.mempool.lock().unwrap().hashes().copied().collect()
+    }
+
+    /// Removes the transactions included in a block from the mempool, along with the ones they made stale
+    pub fn remove_mined_transactions(&self, transactions: &[Transaction]) {
+        let mined: Vec<_> = transactions
+            .iter()
+            .map(|tx| (tx.sender(), tx.nonce()))
+            .collect();
+        self.mempool.lock().unwrap().remove_mined(&mined)
+    }
+
+    /// Returns the executable transactions in the mempool that can pay the given base fee, best tip first
+    pub fn best_pool_transactions(&self, base_fee: Option<u64>) -> Vec<Transaction> {
+        self.mempool.lock().unwrap().best_pending(base_fee)
+    }
+}
+
+/// Builds the state trie from the given accounts, with the account updates applied on top of them
+fn build_trie(
+    mut accounts: HashMap<Address, (AccountInfo, HashMap<H256, U256>)>,
+    account_updates: &[AccountUpdate],
+) -> PatriciaMerkleTree<Vec<u8>, Vec<u8>, Keccak256> {
+    // Apply updates on top of the given state
+    for update in account_updates {
+        if update.removed {
+            accounts.remove(&update.address);
+        }
+        if let Some(info) = &update.info {
+            accounts.entry(update.address).or_default().0 = info.clone();
+        }
+        if let Some((_, storage)) = accounts.get_mut(&update.address) {
+            storage.extend(update.added_storage.clone());
+        }
+    }
+
+    let mut trie = PatriciaMerkleTree::<Vec<u8>, Vec<u8>, Keccak256>::new();
+    for (address, (account, storage)) in accounts {
+        // Key: Keccak(address)
+        let k = Keccak256::new_with_prefix(address.to_fixed_bytes())
+            .finalize()
+            .to_vec();
+        // Value: account
+        let mut v = Vec::new();
+        AccountState::from_info_and_storage(&account, &storage).encode(&mut v);
+        trie.insert(k, v);
+    }
+    trie
 }
 
 #[cfg(test)]
@@ -442,6 +898,7 @@ mod tests {
     use ethereum_types::{H256, U256};
 
     use super::*;
+    use crate::proof::tests::verify_proof;
 
     #[cfg(feature = "in_memory")]
     #[test]
@@ -472,6 +929,9 @@ mod tests {
         test_increment_balance(store.clone());
         test_store_chain_config(store.clone());
         test_store_block_tags(store.clone());
+        test_store_payload(store.clone());
+        test_account_proof(store.clone());
+        test_state_history(store.clone());
     }
 
     fn test_store_account(store: Store) {
@@ -533,6 +993,17 @@ mod tests {
         assert_eq!(stored_body, block_body);
     }
 
+    fn test_store_payload(store: Store) {
+        let (header, body) = create_block_for_testing();
+        let block = Block { header, body };
+        let payload_id = 0x1234;
+
+        store.add_payload(payload_id, block.clone());
+
+        assert_eq!(store.get_payload(payload_id), Some(block));
+        assert_eq!(store.get_payload(0), None);
+    }
+
     fn create_block_for_testing() -> (BlockHeader, BlockBody) {
         let block_header = BlockHeader {
             parent_hash: H256::from_str(
@@ -585,6 +1056,172 @@ mod tests {
         (block_header, block_body)
     }
 
+    fn test_account_proof(mut store: Store) {
+        let address = Address::random();
+        let key = H256::random();
+        let value = U256::from(7);
+        store
+            .add_account_info(address, new_account_info(Bytes::new(), U256::from(50), 1))
+            .unwrap();
+        store.add_storage_at(address, key, value).unwrap();
+
+        let proof = store
+            .get_account_proof(0, address, &[key, H256::zero()])
+            .unwrap();
+        assert_eq!(proof.state.balance, U256::from(50));
+        assert_eq!(proof.storage_proofs[0].value, value);
+        assert_eq!(proof.storage_proofs[1].value, U256::zero());
+
+        // The account proof leads from the state root to the encoded account
+        let hash = |bytes: &[u8]| H256(Keccak256::digest(bytes).into());
+        let encoded_state = verify_proof(
+            store.world_state_root(),
+            hash(address.as_bytes()),
+            &proof.proof,
+        )
+        .expect("Account not found in proof");
+        let state = AccountState::decode(&encoded_state).unwrap();
+        assert_eq!(state, proof.state);
+        assert_eq!(state.nonce, 1);
+        assert_eq!(state.code_hash, types::code_hash(&Bytes::new()));
+
+        // Storage proofs lead from the account's storage root to the slot, or prove it is absent
+        assert_eq!(
+            verify_proof(
+                state.storage_root,
+                hash(key.as_bytes()),
+                &proof.storage_proofs[0].proof
+            ),
+            Some(value.encode_to_vec())
+        );
+        assert_eq!(
+            verify_proof(
+                state.storage_root,
+                hash(H256::zero().as_bytes()),
+                &proof.storage_proofs[1].proof
+            ),
+            None
+        );
+    }
+
+    fn test_state_history(mut store: Store) {
+        let address = Address::random();
+        let (key_a, key_b) = (H256::random(), H256::random());
+        store
+            .apply_account_updates(
+                1,
+                &[AccountUpdate {
+                    address,
+                    info: Some(new_account_info(Bytes::new(), U256::from(10), 0)),
+                    added_storage: HashMap::from([(key_a, U256::from(1))]),
+                    ..Default::default()
+                }],
+            )
+            .unwrap();
+        let root_at_first_block = store.world_state_root();
+        store
+            .apply_account_updates(
+                2,
+                &[AccountUpdate {
+                    address,
+                    info: Some(new_account_info(Bytes::new(), U256::from(20), 1)),
+                    added_storage: HashMap::from([(key_a, U256::from(2)), (key_b, U256::from(3))]),
+                    ..Default::default()
+                }],
+            )
+            .unwrap();
+
+        let balance_at = |block_number| {
+            store
+                .get_account_info_by_block(block_number, address)
+                .unwrap()
+                .map(|info| info.balance)
+        };
+        assert_eq!(balance_at(0), None);
+        assert_eq!(balance_at(1), Some(U256::from(10)));
+        assert_eq!(balance_at(2), Some(U256::from(20)));
+        assert_eq!(balance_at(3), Some(U256::from(20)));
+        let storage_at = |block_number, key| {
+            store
+                .get_storage_at_by_block(block_number, address, key)
+                .unwrap()
+        };
+        assert_eq!(storage_at(0, key_a), None);
+        assert_eq!(storage_at(1, key_a), Some(U256::from(1)));
+        assert_eq!(storage_at(1, key_b), None);
+        assert_eq!(storage_at(2, key_a), Some(U256::from(2)));
+        assert_eq!(storage_at(2, key_b), Some(U256::from(3)));
+        assert_eq!(
+            store
+                .get_code_by_account_address_by_block(0, address)
+                .unwrap(),
+            None
+        );
+
+        // Proofs of older blocks verify against the state root they had
+        let proof = store
+            .get_account_proof(1, address, &[key_a, key_b])
+            .unwrap();
+        assert_eq!(
+            H256(Keccak256::digest(&proof.proof[0]).into()),
+            root_at_first_block
+        );
+        assert_eq!(proof.state.balance, U256::from(10));
+        assert_eq!(proof.storage_proofs[0].value, U256::from(1));
+        assert_eq!(proof.storage_proofs[1].value, U256::zero());
+
+        // State roots can be computed on top of older blocks
+        assert_eq!(
+            store
+                .world_state_root_at_block_with_updates(1, &[])
+                .unwrap(),
+            root_at_first_block
+        );
+        assert_eq!(
+            store
+                .world_state_root_at_block_with_updates(2, &[])
+                .unwrap(),
+            store.world_state_root()
+        );
+
+        // Applying another block on top of the first one replaces the second block's changes
+        store
+            .apply_account_updates(
+                2,
+                &[AccountUpdate {
+                    address,
+                    info: Some(new_account_info(Bytes::new(), U256::from(30), 1)),
+                    ..Default::default()
+                }],
+            )
+            .unwrap();
+        let info = store.get_account_info(address).unwrap().unwrap();
+        assert_eq!(info.balance, U256::from(30));
+        assert_eq!(
+            store.get_storage_at(address, key_a).unwrap(),
+            Some(U256::from(1))
+        );
+        assert_eq!(store.get_storage_at(address, key_b).unwrap(), None);
+        assert_eq!(
+            store
+                .get_account_info_by_block(1, address)
+                .unwrap()
+                .map(|info| info.balance),
+            Some(U256::from(10))
+        );
+
+        // Older states can't be rebuilt if the changes of a later block are missing
+        store
+            .engine
+            .lock()
+            .unwrap()
+            .add_state_changes(4, vec![])
+            .unwrap();
+        assert!(store
+            .world_state_root_at_block_with_updates(1, &[])
+            .is_err());
+    }
+
     fn test_store_block_number(store: Store) {
         let block_hash = H256::random();
         let block_number = 6;
@@ -728,7 +1365,9 @@ mod tests {
         let cancun_time = 12;
         let chain_config = ChainConfig {
             chain_id,
+            shanghai_time: Some(0),
             cancun_time: Some(cancun_time),
+            terminal_total_difficulty: Some(U256::from(17_000_000_000_000_000_u64)),
             ..Default::default()
         };
 
@@ -736,9 +1375,11 @@ mod tests {
 
         let stored_chain_id = store.get_chain_id().unwrap().unwrap();
         let stored_cancun_time = store.get_cancun_time().unwrap().unwrap();
+        let stored_chain_config = store.get_chain_config().unwrap().unwrap();
 
         assert_eq!(chain_id, stored_chain_id);
         assert_eq!(cancun_time, stored_cancun_time);
+        assert_eq!(chain_config, stored_chain_config);
     }
     fn test_store_block_tags(store: Store) {
         let earliest_block_number = 0;